@@ -1,9 +1,123 @@
+use crate::cli::parse_date_time;
+use crate::todo_repo::{Priority, Recurrence, TodoErrors};
+use chrono::{DateTime, Utc};
 use clap::Args;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Deserializer as JsonDeserializer, from_str};
 
-#[derive(Args, Debug)]
+/// Flags for `todo add`. The same fields can be piped in as JSON when no flag is given.
+#[derive(Args, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct AddCommandArgs {
-    #[arg(short, long)]
+    /// Name of the todo, required unless JSON is piped on stdin
+    #[arg(short, long, default_value_t, hide_default_value = true)]
     pub name: String,
-    #[arg(short, long)]
+    #[arg(short, long, default_value_t, hide_default_value = true)]
     pub description: String,
+    /// Optional due date <YYYY-MM-DD|YYYY-MM-DD HH:MM|RFC 3339>, in local time unless an offset is given
+    #[arg(long, value_parser = parse_date_time)]
+    #[serde(deserialize_with = "deserialize_due")]
+    pub due: Option<DateTime<Utc>>,
+    /// Optional priority <low|medium|high|urgent>
+    #[arg(short, long)]
+    pub priority: Option<Priority>,
+    /// Tag to attach, repeat or comma-separate for several
+    #[arg(short, long = "tag", value_delimiter = ',')]
+    #[serde(alias = "tag")]
+    pub tags: Vec<String>,
+    /// Optional repeat rule <daily|weekly[:mon,thu]|monthly:DAY|every:DAYS>
+    #[arg(long = "repeat")]
+    #[serde(alias = "repeat")]
+    pub recurrence: Option<Recurrence>,
+    /// Optional id of the todo to add this one as a subtask of
+    #[arg(long = "parent", allow_hyphen_values = true)]
+    #[serde(alias = "parent")]
+    pub parent_id: Option<String>,
+    /// Optional id of a todo that has to be done first, repeat or comma-separate for several
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    pub blocked_by: Vec<String>,
+}
+
+// Piped JSON accepts the same date forms as the --due flag.
+fn deserialize_due<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse_date_time(&value).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+impl AddCommandArgs {
+    pub fn has_fields(&self) -> bool {
+        !self.name.is_empty()
+            || !self.description.is_empty()
+            || self.due.is_some()
+            || self.priority.is_some()
+            || !self.tags.is_empty()
+            || self.recurrence.is_some()
+            || self.parent_id.is_some()
+            || !self.blocked_by.is_empty()
+    }
+
+    /// Parses one JSON object, a JSON array of objects, or a stream of objects such as NDJSON.
+    pub fn from_json(input: &str) -> Result<Vec<Self>, TodoErrors> {
+        let invalid = |e: serde_json::Error| match TodoErrors::from(e) {
+            TodoErrors::ParseError {
+                message,
+                line,
+                column,
+            } => TodoErrors::ParseError {
+                message: format!("invalid todo JSON: {}", message),
+                line,
+                column,
+            },
+            other => other,
+        };
+        let parsed: Vec<Self> = if input.trim_start().starts_with('[') {
+            from_str(input).map_err(invalid)?
+        } else {
+            JsonDeserializer::from_str(input)
+                .into_iter::<Self>()
+                .collect::<Result<_, _>>()
+                .map_err(invalid)?
+        };
+        if parsed.is_empty() {
+            return Err(TodoErrors::Validation(String::from("no todo JSON found")));
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_single_object_array_and_ndjson() {
+        let single = AddCommandArgs::from_json(r#"{"name": "Milk", "tags": ["shop"]}"#).unwrap();
+        let array = AddCommandArgs::from_json(r#"[{"name": "A"}, {"name": "B"}]"#).unwrap();
+        let ndjson = AddCommandArgs::from_json(
+            "{\"name\": \"A\"}\n{\"name\": \"B\", \"priority\": \"high\"}\n",
+        )
+        .unwrap();
+
+        assert_eq!("Milk", single[0].name);
+        assert_eq!(vec!["shop"], single[0].tags);
+        assert_eq!(2, array.len());
+        assert_eq!(2, ndjson.len());
+        assert_eq!(Some(Priority::High), ndjson[1].priority);
+    }
+
+    #[test]
+    fn should_accept_flag_date_forms_and_report_position_of_bad_json() {
+        let parsed = AddCommandArgs::from_json(r#"{"name": "Due", "due": "2026-10-20"}"#).unwrap();
+        assert_eq!(parse_date_time("2026-10-20").ok(), parsed[0].due);
+
+        let error = AddCommandArgs::from_json("{\"name\": \"A\"}\n{\"name\": }").unwrap_err();
+        assert!(
+            matches!(error, TodoErrors::ParseError { line: Some(2), ref message, .. } if message.starts_with("invalid todo JSON")),
+            "{}",
+            error
+        );
+    }
 }
@@ -1,7 +1,14 @@
+use crate::cli::SelectionArgs;
 use clap::Args;
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Default)]
 pub struct CompleteCommandArgs {
+    #[command(flatten)]
+    pub selection: SelectionArgs,
+    /// Also complete every pending subtask of the selected todos
     #[arg(long)]
-    pub id: String,
+    pub cascade: bool,
+    /// Complete todos even while todos they are blocked by are still open
+    #[arg(long)]
+    pub force: bool,
 }
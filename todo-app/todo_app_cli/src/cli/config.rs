@@ -0,0 +1,47 @@
+use clap::{Subcommand, ValueEnum};
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the value a setting resolves to
+    Get { key: ConfigKey },
+    /// Store a setting in the config file
+    Set { key: ConfigKey, value: String },
+    /// Remove a setting from the config file
+    Unset { key: ConfigKey },
+    /// Print every setting with where its value comes from
+    Show,
+}
+
+/// Settings that can be stored in the config file, named as in the file.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigKey {
+    /// Data file used when neither --file nor TODO_FILE is given
+    File,
+    /// Storage backend used when --backend is not given
+    Backend,
+    /// Output format used when neither --output nor TODO_OUTPUT is given
+    Output,
+    /// Whether table output is colored: auto, always or never
+    Color,
+    /// strftime format for due and deleted dates in text and table output
+    #[value(name = "date_format")]
+    DateFormat,
+    /// List used when --list is not given
+    List,
+    /// URL of a todo server used instead of a data file, unless --file or TODO_FILE is given
+    Remote,
+}
+
+impl ConfigKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigKey::File => "file",
+            ConfigKey::Backend => "backend",
+            ConfigKey::Output => "output",
+            ConfigKey::Color => "color",
+            ConfigKey::DateFormat => "date_format",
+            ConfigKey::List => "list",
+            ConfigKey::Remote => "remote",
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use crate::cli::SelectionArgs;
 use clap::Args;
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Default)]
 pub struct DeleteCommandArgs {
-    #[arg(long)]
-    pub id: String,
+    #[command(flatten)]
+    pub selection: SelectionArgs,
+    /// Also move every subtask of the selected todos to the trash
+    #[arg(short, long)]
+    pub recursive: bool,
 }
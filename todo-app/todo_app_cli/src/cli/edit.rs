@@ -0,0 +1,61 @@
+use crate::cli::parse_date_time;
+use crate::todo_repo::{Priority, Recurrence};
+use chrono::{DateTime, Utc};
+use clap::Args;
+
+#[derive(Args, Debug, Default, Clone)]
+pub struct EditCommandArgs {
+    /// Todo id, unique id prefix, index shown by `get all` or @last
+    // Generated ids may start with '-', which must not be read as a flag.
+    #[arg(long, allow_hyphen_values = true)]
+    pub id: String,
+    /// New name
+    #[arg(short, long)]
+    pub name: Option<String>,
+    /// New description
+    #[arg(short, long)]
+    pub description: Option<String>,
+    /// New due date <YYYY-MM-DD|YYYY-MM-DD HH:MM|RFC 3339>
+    #[arg(long, value_parser = parse_date_time, conflicts_with = "clear_due")]
+    pub due: Option<DateTime<Utc>>,
+    /// Remove the due date
+    #[arg(long)]
+    pub clear_due: bool,
+    /// New priority <low|medium|high|urgent>
+    #[arg(short, long, conflicts_with = "clear_priority")]
+    pub priority: Option<Priority>,
+    /// Remove the priority
+    #[arg(long)]
+    pub clear_priority: bool,
+    /// Tag to add, repeat or comma-separate for several
+    #[arg(long, value_delimiter = ',')]
+    pub add_tag: Vec<String>,
+    /// Tag to remove, repeat or comma-separate for several
+    #[arg(long, value_delimiter = ',')]
+    pub remove_tag: Vec<String>,
+    /// New repeat rule <daily|weekly[:mon,thu]|monthly:DAY|every:DAYS>
+    #[arg(long = "repeat", conflicts_with = "clear_repeat")]
+    pub recurrence: Option<Recurrence>,
+    /// Stop the todo from repeating
+    #[arg(long)]
+    pub clear_repeat: bool,
+    /// Mark a completed todo as pending again
+    #[arg(long)]
+    pub reopen: bool,
+}
+
+impl EditCommandArgs {
+    pub fn has_changes(&self) -> bool {
+        self.name.is_some()
+            || self.description.is_some()
+            || self.due.is_some()
+            || self.clear_due
+            || self.priority.is_some()
+            || self.clear_priority
+            || !self.add_tag.is_empty()
+            || !self.remove_tag.is_empty()
+            || self.recurrence.is_some()
+            || self.clear_repeat
+            || self.reopen
+    }
+}
@@ -0,0 +1,9 @@
+use crate::exchange::ExchangeFormat;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct ExportCommandArgs {
+    /// Format written to stdout
+    #[arg(long)]
+    pub format: ExchangeFormat,
+}
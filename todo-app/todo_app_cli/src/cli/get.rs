@@ -1,17 +1,84 @@
+use crate::cli::parse_date_time;
+use crate::todo_repo::{Priority, TodoQuery, TodoSortKey, TodoStatus};
+use chrono::{DateTime, Utc};
 use clap::{Args, Subcommand};
 
 #[derive(Subcommand, Debug)]
 pub enum GetCommand {
     All,
+    /// Get Todos by id, reading ids one per line from stdin when none is given
     Id(GetIdArgs),
+    /// Get Todos whose name contains the text, read from stdin when not given
     Name(GetNameArgs),
+    /// List todos matching the given filters
+    List(GetListArgs),
+    /// Show the next occurrences of pending recurring todos, soonest first
+    Upcoming(GetUpcomingArgs),
+    /// List pending todos that are not blocked by any open todo
+    Ready,
 }
 #[derive(Args, Debug)]
 pub struct GetIdArgs {
-    pub todo_id: String,
+    /// Todo id, unique id prefix, index shown by `get all` or @last
+    #[arg(allow_hyphen_values = true)]
+    pub todo_id: Option<String>,
 }
 
 #[derive(Args, Debug)]
 pub struct GetNameArgs {
-    pub search_string: String,
+    pub search_string: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct GetUpcomingArgs {
+    /// Occurrences to show per recurring todo, counting the pending one
+    #[arg(short = 'n', long, default_value_t = 3)]
+    pub count: usize,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct GetListArgs {
+    /// Only pending or only done todos
+    #[arg(long)]
+    pub status: Option<TodoStatus>,
+    /// Only todos carrying this tag, repeat to require several
+    #[arg(long, value_delimiter = ',')]
+    pub tag: Vec<String>,
+    /// Only todos with this priority
+    #[arg(long)]
+    pub priority: Option<Priority>,
+    /// Only todos due before this date
+    #[arg(long, value_parser = parse_date_time)]
+    pub due_before: Option<DateTime<Utc>>,
+    /// Only todos due after this date
+    #[arg(long, value_parser = parse_date_time)]
+    pub due_after: Option<DateTime<Utc>>,
+    /// Case-insensitive text to find in name or description
+    #[arg(long)]
+    pub search: Option<String>,
+    /// Sort order, stored order when omitted
+    #[arg(long)]
+    pub sort: Option<TodoSortKey>,
+    /// Maximum number of todos to print
+    #[arg(long)]
+    pub limit: Option<usize>,
+    /// Number of matching todos to skip
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+}
+
+impl From<&GetListArgs> for TodoQuery {
+    fn from(args: &GetListArgs) -> Self {
+        TodoQuery {
+            status: args.status,
+            tags: args.tag.clone(),
+            priority: args.priority,
+            due_before: args.due_before,
+            due_after: args.due_after,
+            search: args.search.clone(),
+            sort: args.sort,
+            limit: args.limit,
+            offset: args.offset,
+        }
+    }
 }
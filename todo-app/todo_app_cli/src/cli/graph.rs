@@ -0,0 +1,14 @@
+use clap::{Args, ValueEnum};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+    /// Graphviz DOT, render with e.g. `dot -Tsvg`
+    Dot,
+}
+
+#[derive(Args, Debug)]
+pub struct GraphCommandArgs {
+    /// Format written to stdout
+    #[arg(long, default_value = "dot")]
+    pub format: GraphFormat,
+}
@@ -0,0 +1,8 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct HistoryCommandArgs {
+    /// Show only the most recent operations
+    #[arg(long)]
+    pub limit: Option<usize>,
+}
@@ -0,0 +1,15 @@
+use crate::exchange::ExchangeFormat;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct ImportCommandArgs {
+    /// Format of the input
+    #[arg(long)]
+    pub format: ExchangeFormat,
+    /// File to read, stdin when omitted
+    #[arg(long)]
+    pub input: Option<String>,
+    /// Print the todos that would be added without saving them
+    #[arg(long)]
+    pub dry_run: bool,
+}
@@ -0,0 +1,17 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct LinkCommandArgs {
+    /// Todo that waits: id, unique id prefix, index shown by `get all` or @last
+    // Generated ids may start with '-', which must not be read as a flag.
+    #[arg(long, allow_hyphen_values = true)]
+    pub id: String,
+    /// Todo it waits on, referred to like --id, repeat or comma-separate for several
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        allow_hyphen_values = true
+    )]
+    pub blocked_by: Vec<String>,
+}
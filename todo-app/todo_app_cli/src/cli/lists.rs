@@ -0,0 +1,9 @@
+use crate::cli::parse_list_name;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct ListsCommandArgs {
+    /// Use this list when no --list is given, from now on
+    #[arg(long, value_parser = parse_list_name)]
+    pub set_default: Option<String>,
+}
@@ -1,13 +1,49 @@
 pub mod add;
 pub mod complete;
+pub mod config;
 pub mod delete;
+pub mod edit;
+pub mod export;
 pub mod get;
+pub mod graph;
+pub mod history;
+pub mod import;
+pub mod link;
+pub mod lists;
+pub mod move_todos;
+pub mod purge;
+pub mod restore;
+pub mod search;
+pub mod selection;
+pub mod serve;
+pub mod sync;
+pub mod trash;
+pub mod undo;
 
+use crate::printer::TodoTemplate;
 pub use add::AddCommandArgs;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use clap::{Parser, Subcommand, ValueEnum};
 pub use complete::CompleteCommandArgs;
+pub use config::{ConfigCommand, ConfigKey};
 pub use delete::DeleteCommandArgs;
+pub use edit::EditCommandArgs;
+pub use export::ExportCommandArgs;
 pub use get::GetCommand;
+pub use graph::{GraphCommandArgs, GraphFormat};
+pub use history::HistoryCommandArgs;
+pub use import::ImportCommandArgs;
+pub use link::LinkCommandArgs;
+pub use lists::ListsCommandArgs;
+pub use move_todos::MoveCommandArgs;
+pub use purge::PurgeCommandArgs;
+pub use restore::RestoreCommandArgs;
+pub use search::SearchCommandArgs;
+pub use selection::SelectionArgs;
+pub use serve::ServeCommandArgs;
+pub use sync::SyncCommandArgs;
+pub use trash::TrashCommand;
+pub use undo::UndoCommandArgs;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -15,12 +51,27 @@ pub use get::GetCommand;
 pub struct TodoCli {
     #[command(subcommand)]
     pub command: Commands,
-    /// Optional output <text|json> defaults to text
+    /// Optional output <text|json|table|ndjson|csv|yaml> defaults to TODO_OUTPUT, the config file or text
     #[arg(short, long)]
     pub output: Option<OutputFormat>,
-    /// Optional file argument defaults to home-dir/tmp/todo.json
+    /// Print each todo with a format such as '{id}\t{name}' instead of --output
+    #[arg(long, conflicts_with = "output", value_parser = TodoTemplate::parse)]
+    pub template: Option<TodoTemplate>,
+    /// Optional file argument defaults to TODO_FILE, the config file or home-dir/tmp/todo.json
     #[arg(short, long)]
     pub file: Option<String>,
+    /// Optional storage backend <json|sqlite>, inferred from the file extension when omitted
+    #[arg(short, long)]
+    pub backend: Option<StorageBackend>,
+    /// Optional list to work on, defaults to the one set with `lists --set-default`
+    #[arg(long, value_parser = parse_list_name)]
+    pub list: Option<String>,
+    /// Optional color <auto|always|never> for table output, defaults to the config file or auto
+    #[arg(long)]
+    pub color: Option<ColorChoice>,
+    /// Optional URL of a `todo serve` instance to use instead of a data file
+    #[arg(long, value_parser = parse_remote_url, conflicts_with_all = ["file", "backend"])]
+    pub remote: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -30,16 +81,217 @@ pub enum Commands {
         #[command(subcommand)]
         get_command: GetCommand,
     },
+    /// Find Todos by words in name, description and tags, most relevant first
+    Search(SearchCommandArgs),
     /// Add a Todo
     Add(AddCommandArgs),
-    /// Complete a Todo
+    /// Complete Todos by id or filter
     Complete(CompleteCommandArgs),
-    /// Delete a Todo
+    /// Move Todos to the trash by id or filter
     Delete(DeleteCommandArgs),
+    /// Inspect the trash
+    Trash {
+        #[command(subcommand)]
+        trash_command: TrashCommand,
+    },
+    /// Restore a Todo from the trash
+    Restore(RestoreCommandArgs),
+    /// Permanently remove Todos from the trash
+    Purge(PurgeCommandArgs),
+    /// Edit a Todo, changing only the given fields
+    Edit(EditCommandArgs),
+    /// Make a Todo wait on other Todos before it can be completed
+    Link(LinkCommandArgs),
+    /// Stop a Todo from waiting on other Todos
+    Unlink(LinkCommandArgs),
+    /// Move Todos into another list
+    Move(MoveCommandArgs),
+    /// Show every list with its number of pending and done Todos
+    Lists(ListsCommandArgs),
+    /// Write the dependency graph of Todos outside the trash to stdout
+    Graph(GraphCommandArgs),
+    /// Write all Todos outside the trash to stdout in an exchange format
+    Export(ExportCommandArgs),
+    /// Add Todos from a file or stdin in an exchange format
+    Import(ImportCommandArgs),
+    /// Undo the most recent operations
+    Undo(UndoCommandArgs),
+    /// Redo the most recently undone operations
+    Redo(UndoCommandArgs),
+    /// List journaled operations, newest first
+    History(HistoryCommandArgs),
+    /// Browse, complete, edit and delete Todos in a full-screen terminal interface
+    Tui,
+    /// Serve the Todos over HTTP as a JSON API
+    Serve(ServeCommandArgs),
+    /// Commit the JSON data file, merge it with a git remote Todo by Todo and push
+    Sync(SyncCommandArgs),
+    /// Read or change settings in the config file
+    Config {
+        #[command(subcommand)]
+        config_command: ConfigCommand,
+    },
 }
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
 pub enum OutputFormat {
     Text,
     Json,
+    /// Aligned columns with a header row, colored when printing to a terminal
+    Table,
+    /// One compact JSON object per line
+    Ndjson,
+    /// Header row and one row per todo
+    Csv,
+    Yaml,
+    /// Set by `--template`
+    #[value(skip)]
+    Template,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    /// Color when printing to a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum StorageBackend {
+    Json,
+    Sqlite,
+}
+
+impl StorageBackend {
+    /// Picks the backend matching a data file extension, falling back to JSON.
+    pub fn from_path(path: &str) -> Self {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+        match extension.as_deref() {
+            Some("db" | "sqlite" | "sqlite3") => StorageBackend::Sqlite,
+            _ => StorageBackend::Json,
+        }
+    }
+
+    pub fn default_file_name(&self) -> &'static str {
+        match self {
+            StorageBackend::Json => "todo.json",
+            StorageBackend::Sqlite => "todo.db",
+        }
+    }
+}
+
+/// Parses a user supplied date or date-time; values without an offset are read as local time
+/// and a bare date means the start of that day.
+pub fn parse_date_time(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| {
+            format!(
+                "invalid date '{}', expected YYYY-MM-DD, YYYY-MM-DD HH:MM or RFC 3339",
+                value
+            )
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|date_time| date_time.with_timezone(&Utc))
+        .ok_or_else(|| format!("date '{}' does not exist in the local time zone", value))
+}
+
+/// Trims a list name, which must not be empty.
+pub fn parse_list_name(value: &str) -> Result<String, String> {
+    match value.trim() {
+        "" => Err(String::from("list name cannot be empty")),
+        name => Ok(String::from(name)),
+    }
+}
+
+/// Checks the URL of a todo server and drops a trailing slash.
+pub fn parse_remote_url(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let host = value
+        .strip_prefix("http://")
+        .or_else(|| value.strip_prefix("https://"))
+        .ok_or_else(|| format!("invalid URL '{}', expected http:// or https://", value))?;
+    if host.trim_end_matches('/').is_empty() {
+        return Err(format!("invalid URL '{}', missing host", value));
+    }
+    Ok(String::from(value.trim_end_matches('/')))
+}
+
+/// Parses an age such as `30d`; the unit is one of `m` (minutes), `h`, `d` or `w`.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let invalid = || {
+        format!(
+            "invalid duration '{}', expected a number followed by m, h, d or w",
+            value
+        )
+    };
+    let unit_start = value.char_indices().last().map_or(0, |(index, _)| index);
+    let (amount, unit) = value.split_at(unit_start);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    match unit {
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    }
+    .filter(|duration| *duration >= Duration::zero())
+    .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_duration_with_unit_suffix() {
+        assert_eq!(Ok(Duration::days(30)), parse_duration("30d"));
+        assert_eq!(Ok(Duration::hours(12)), parse_duration(" 12h "));
+        assert_eq!(Ok(Duration::weeks(2)), parse_duration("2w"));
+        assert_eq!(Ok(Duration::minutes(45)), parse_duration("45m"));
+    }
+
+    #[test]
+    fn should_accept_http_urls_with_host_only() {
+        assert_eq!(
+            Ok(String::from("http://localhost:8080")),
+            parse_remote_url(" http://localhost:8080/ ")
+        );
+        assert!(parse_remote_url("https://todo.example.com").is_ok());
+        for value in ["localhost:8080", "ftp://host", "http://", "https:///"] {
+            assert!(
+                parse_remote_url(value).is_err(),
+                "{} should be rejected",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_duration_without_valid_unit_or_amount() {
+        for value in ["30", "d", "-1d", "30x", "3ä", ""] {
+            assert!(
+                parse_duration(value).is_err(),
+                "{} should be rejected",
+                value
+            );
+        }
+    }
 }
@@ -0,0 +1,11 @@
+use crate::cli::{SelectionArgs, parse_list_name};
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct MoveCommandArgs {
+    #[command(flatten)]
+    pub selection: SelectionArgs,
+    /// Name of the list to move the todos and their subtasks into
+    #[arg(long, value_parser = parse_list_name)]
+    pub to: String,
+}
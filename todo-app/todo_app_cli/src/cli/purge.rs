@@ -0,0 +1,10 @@
+use crate::cli::parse_duration;
+use chrono::Duration;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct PurgeCommandArgs {
+    /// Purge only todos trashed at least this long ago, e.g. 30d, 12h, 2w
+    #[arg(long, value_parser = parse_duration)]
+    pub older_than: Option<Duration>,
+}
@@ -0,0 +1,9 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct RestoreCommandArgs {
+    /// Id of the trashed todo; an id prefix or index only refers to todos outside the trash
+    // Generated ids may start with '-', which must not be read as a flag.
+    #[arg(long, allow_hyphen_values = true)]
+    pub id: String,
+}
@@ -0,0 +1,12 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct SearchCommandArgs {
+    /// Words to find in name, description and tags; `word*` matches a prefix and
+    /// "quoted words" a phrase
+    #[arg(required = true, num_args = 1..)]
+    pub query: Vec<String>,
+    /// Show at most this many results
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+}
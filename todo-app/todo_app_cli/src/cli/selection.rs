@@ -0,0 +1,42 @@
+use crate::todo_repo::{TodoQuery, TodoSelection, TodoStatus};
+use clap::Args;
+
+/// Picks the todos a bulk command acts on, either by id or by filter.
+#[derive(Args, Debug, Default)]
+pub struct SelectionArgs {
+    /// Todo id, unique id prefix, index shown by `get all` or @last, repeat for several; read one
+    /// per line from stdin when no id or filter is given
+    // Generated ids may start with '-', which must not be read as a flag.
+    #[arg(long = "id", allow_hyphen_values = true, conflicts_with_all = ["status", "tag", "search"])]
+    pub ids: Vec<String>,
+    /// Select every pending or every done todo
+    #[arg(long)]
+    pub status: Option<TodoStatus>,
+    /// Select todos carrying this tag, repeat to require several
+    #[arg(long, value_delimiter = ',')]
+    pub tag: Vec<String>,
+    /// Select todos whose name or description contains this text, ignoring case
+    #[arg(long)]
+    pub search: Option<String>,
+}
+
+impl SelectionArgs {
+    pub fn has_filter(&self) -> bool {
+        self.status.is_some() || !self.tag.is_empty() || self.search.is_some()
+    }
+}
+
+impl From<&SelectionArgs> for TodoSelection {
+    fn from(args: &SelectionArgs) -> Self {
+        if args.has_filter() {
+            TodoSelection::Matching(TodoQuery {
+                status: args.status,
+                tags: args.tag.clone(),
+                search: args.search.clone(),
+                ..TodoQuery::default()
+            })
+        } else {
+            TodoSelection::Ids(args.ids.clone())
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct ServeCommandArgs {
+    /// Port to listen on, 0 picks a free one
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+}
@@ -0,0 +1,11 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct SyncCommandArgs {
+    /// Git remote to pull from and push to
+    #[arg(long, default_value = "origin")]
+    pub git_remote: String,
+    /// Message of the commit holding local changes
+    #[arg(short, long, default_value = "Update todos")]
+    pub message: String,
+}
@@ -0,0 +1,7 @@
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug)]
+pub enum TrashCommand {
+    /// List todos in the trash
+    List,
+}
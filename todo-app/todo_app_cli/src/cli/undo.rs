@@ -0,0 +1,8 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct UndoCommandArgs {
+    /// Number of operations to step through
+    #[arg(short = 'n', long, default_value_t = 1)]
+    pub count: usize,
+}
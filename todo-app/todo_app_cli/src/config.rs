@@ -0,0 +1,511 @@
+use crate::cli::{
+    ColorChoice, ConfigKey, OutputFormat, StorageBackend, TodoCli, parse_list_name,
+    parse_remote_url,
+};
+use crate::todo_repo::TodoErrors;
+use chrono::format::{Item, StrftimeItems};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the data file.
+pub const FILE_ENV: &str = "TODO_FILE";
+/// Environment variable overriding the output format.
+pub const OUTPUT_ENV: &str = "TODO_OUTPUT";
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Settings stored in `config.toml`; every one is optional. Values are kept as written and
+/// checked when the file is loaded, so an invalid one names its key.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/todo/config.toml`, falling back to `~/.config/todo/config.toml`.
+    pub fn default_path(
+        env: impl Fn(&str) -> Option<String>,
+        home: Option<&Path>,
+    ) -> Option<PathBuf> {
+        let config_home = env("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| home.map(|home| home.join(".config")))?;
+        Some(config_home.join("todo").join("config.toml"))
+    }
+
+    /// Reads the config at `path`; a missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Self, TodoErrors> {
+        match std::fs::read_to_string(path) {
+            Ok(input) => Self::parse(&input),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(TodoErrors::from(e)),
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Self, TodoErrors> {
+        let config: Self = toml::from_str(input).map_err(|e| {
+            let (line, column) = match e.span() {
+                Some(span) => {
+                    let before = &input[..span.start];
+                    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+                    (
+                        Some(before.matches('\n').count() + 1),
+                        Some(before[line_start..].chars().count() + 1),
+                    )
+                }
+                None => (None, None),
+            };
+            TodoErrors::ParseError {
+                message: String::from(e.message()),
+                line,
+                column,
+            }
+        })?;
+        for key in ConfigKey::value_variants() {
+            if let Some(value) = config.get(*key) {
+                check_value(*key, value)
+                    .map_err(|e| TodoErrors::parse_error(format!("{}: {}", key.as_str(), e)))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Writes the config to `path`, creating its directory. Comments in an existing file
+    /// are not kept.
+    pub fn save(&self, path: &Path) -> Result<(), TodoErrors> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let output = toml::to_string(self)
+            .map_err(|e| TodoErrors::Io(format!("failed to write config: {}", e)))?;
+        std::fs::write(path, output).map_err(TodoErrors::from)
+    }
+
+    pub fn get(&self, key: ConfigKey) -> Option<&str> {
+        match key {
+            ConfigKey::File => self.file.as_deref(),
+            ConfigKey::Backend => self.backend.as_deref(),
+            ConfigKey::Output => self.output.as_deref(),
+            ConfigKey::Color => self.color.as_deref(),
+            ConfigKey::DateFormat => self.date_format.as_deref(),
+            ConfigKey::List => self.list.as_deref(),
+            ConfigKey::Remote => self.remote.as_deref(),
+        }
+    }
+
+    /// Stores `value` under `key` in the form it is written to the file, refusing values the
+    /// setting can't take.
+    pub fn set(&mut self, key: ConfigKey, value: &str) -> Result<String, TodoErrors> {
+        let value = check_value(key, value)
+            .map_err(|e| TodoErrors::Validation(format!("{}: {}", key.as_str(), e)))?;
+        *self.slot(key) = Some(value.clone());
+        Ok(value)
+    }
+
+    pub fn unset(&mut self, key: ConfigKey) {
+        *self.slot(key) = None;
+    }
+
+    fn slot(&mut self, key: ConfigKey) -> &mut Option<String> {
+        match key {
+            ConfigKey::File => &mut self.file,
+            ConfigKey::Backend => &mut self.backend,
+            ConfigKey::Output => &mut self.output,
+            ConfigKey::Color => &mut self.color,
+            ConfigKey::DateFormat => &mut self.date_format,
+            ConfigKey::List => &mut self.list,
+            ConfigKey::Remote => &mut self.remote,
+        }
+    }
+}
+
+/// The value `key` is stored as, or why it can't take `value`.
+fn check_value(key: ConfigKey, value: &str) -> Result<String, String> {
+    match key {
+        ConfigKey::File => match value.trim() {
+            "" => Err(String::from("file cannot be empty")),
+            file => Ok(String::from(file)),
+        },
+        ConfigKey::Backend => parse_value::<StorageBackend>(value).map(|value| value_name(&value)),
+        ConfigKey::Output => parse_value::<OutputFormat>(value).map(|value| value_name(&value)),
+        ConfigKey::Color => parse_value::<ColorChoice>(value).map(|value| value_name(&value)),
+        ConfigKey::DateFormat => parse_date_format(value),
+        ConfigKey::List => parse_list_name(value),
+        ConfigKey::Remote => parse_remote_url(value),
+    }
+}
+
+fn parse_value<T: ValueEnum>(value: &str) -> Result<T, String> {
+    T::from_str(value.trim(), true).map_err(|_| {
+        let names: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|variant| variant.to_possible_value())
+            .map(|possible| String::from(possible.get_name()))
+            .collect();
+        format!(
+            "invalid value '{}', expected one of {}",
+            value,
+            names.join(", ")
+        )
+    })
+}
+
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map_or_else(String::new, |possible| String::from(possible.get_name()))
+}
+
+/// Checks a strftime format such as `%d.%m.%Y` before it is used to print dates.
+pub fn parse_date_format(value: &str) -> Result<String, String> {
+    if value.trim().is_empty() {
+        return Err(String::from("date format cannot be empty"));
+    }
+    if StrftimeItems::new(value).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid date format '{}'", value));
+    }
+    Ok(String::from(value))
+}
+
+// A leading `~` in a configured path stands for the home directory.
+fn expand_home(path: &str, home: Option<&Path>) -> String {
+    match (path.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+        _ => String::from(path),
+    }
+}
+
+/// Where the value of a setting came from, highest precedence first.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingSource {
+    Flag,
+    Env,
+    Config,
+    Default,
+}
+
+impl fmt::Display for SettingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SettingSource::Flag => "flag",
+            SettingSource::Env => "env",
+            SettingSource::Config => "config",
+            SettingSource::Default => "default",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: SettingSource,
+}
+
+impl<T> Setting<T> {
+    // The first value given, checked in order of precedence.
+    fn first(
+        flag: Option<T>,
+        env: Option<T>,
+        config: Option<T>,
+        default: impl FnOnce() -> T,
+    ) -> Self {
+        let (value, source) = match (flag, env, config) {
+            (Some(value), _, _) => (value, SettingSource::Flag),
+            (None, Some(value), _) => (value, SettingSource::Env),
+            (None, None, Some(value)) => (value, SettingSource::Config),
+            (None, None, None) => (default(), SettingSource::Default),
+        };
+        Self { value, source }
+    }
+}
+
+/// One resolved setting as `config get` and `config show` print it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SettingEntry {
+    pub key: &'static str,
+    pub value: String,
+    pub source: SettingSource,
+}
+
+/// Every setting the CLI runs with, each taken from the first of its flag, environment
+/// variable, config file entry or built-in default that is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub file: Setting<String>,
+    pub backend: Setting<StorageBackend>,
+    pub output: Setting<OutputFormat>,
+    pub color: Setting<ColorChoice>,
+    pub date_format: Setting<String>,
+    /// `None` leaves the choice to the default list stored with the todos.
+    pub list: Setting<Option<String>>,
+    /// Todo server used instead of `file` when set.
+    pub remote: Setting<Option<String>>,
+}
+
+impl Settings {
+    pub fn resolve(
+        cli: &TodoCli,
+        config: &Config,
+        env: impl Fn(&str) -> Option<String>,
+        home: Option<&Path>,
+    ) -> Result<Self, TodoErrors> {
+        let env = |name: &str| env(name).filter(|value| !value.trim().is_empty());
+        let env_value = |name: &str, key: ConfigKey| {
+            env(name)
+                .map(|value| check_value(key, &value))
+                .transpose()
+                .map_err(|e| TodoErrors::Validation(format!("{}: {}", name, e)))
+        };
+        // Values in the file were checked by `Config::parse`.
+        let configured = |key: ConfigKey| config.get(key).map(String::from);
+
+        let backend = Setting::first(
+            cli.backend,
+            None,
+            configured(ConfigKey::Backend).and_then(|value| parse_value(&value).ok()),
+            || StorageBackend::Json,
+        );
+        let default_file = home.map(|home| {
+            let default_file = home.join("tmp").join(backend.value.default_file_name());
+            default_file.to_string_lossy().into_owned()
+        });
+        let file = Setting::first(
+            cli.file.clone(),
+            env_value(FILE_ENV, ConfigKey::File)?,
+            configured(ConfigKey::File).map(|file| expand_home(&file, home)),
+            || default_file.unwrap_or_default(),
+        );
+        if file.value.is_empty() {
+            return Err(TodoErrors::Io(format!(
+                "Could not find home directory, pass --file or set {}",
+                FILE_ENV
+            )));
+        }
+        // Without a chosen backend, the data file's extension picks one.
+        let backend = match (backend.source, file.source) {
+            (SettingSource::Default, source) if source != SettingSource::Default => Setting {
+                value: StorageBackend::from_path(&file.value),
+                source: SettingSource::Default,
+            },
+            _ => backend,
+        };
+        let output = Setting::first(
+            cli.template
+                .as_ref()
+                .map(|_| OutputFormat::Template)
+                .or(cli.output),
+            env_value(OUTPUT_ENV, ConfigKey::Output)?.and_then(|value| parse_value(&value).ok()),
+            configured(ConfigKey::Output).and_then(|value| parse_value(&value).ok()),
+            || OutputFormat::Text,
+        );
+        let color = Setting::first(
+            cli.color,
+            None,
+            configured(ConfigKey::Color).and_then(|value| parse_value(&value).ok()),
+            || ColorChoice::Auto,
+        );
+        let date_format = Setting::first(None, None, configured(ConfigKey::DateFormat), || {
+            String::from(DEFAULT_DATE_FORMAT)
+        });
+        let list = Setting::first(
+            cli.list.clone().map(Some),
+            None,
+            configured(ConfigKey::List).map(Some),
+            || None,
+        );
+        // A data file given for this run wins over a server from the config file.
+        let file_given = matches!(file.source, SettingSource::Flag | SettingSource::Env);
+        let configured_remote = configured(ConfigKey::Remote)
+            .filter(|_| !file_given)
+            .and_then(|url| parse_remote_url(&url).ok());
+        let remote = Setting::first(
+            cli.remote.clone().map(Some),
+            None,
+            configured_remote.map(Some),
+            || None,
+        );
+        Ok(Self {
+            file,
+            backend,
+            output,
+            color,
+            date_format,
+            list,
+            remote,
+        })
+    }
+
+    pub fn entry(&self, key: ConfigKey) -> SettingEntry {
+        let (value, source) = match key {
+            ConfigKey::File => (self.file.value.clone(), self.file.source),
+            ConfigKey::Backend => (value_name(&self.backend.value), self.backend.source),
+            ConfigKey::Output => (value_name(&self.output.value), self.output.source),
+            ConfigKey::Color => (value_name(&self.color.value), self.color.source),
+            ConfigKey::DateFormat => (self.date_format.value.clone(), self.date_format.source),
+            ConfigKey::List => (
+                self.list.value.clone().unwrap_or_default(),
+                self.list.source,
+            ),
+            ConfigKey::Remote => (
+                self.remote.value.clone().unwrap_or_default(),
+                self.remote.source,
+            ),
+        };
+        SettingEntry {
+            key: key.as_str(),
+            value,
+            source,
+        }
+    }
+
+    pub fn entries(&self) -> Vec<SettingEntry> {
+        ConfigKey::value_variants()
+            .iter()
+            .map(|key| self.entry(*key))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::collections::HashMap;
+
+    fn cli(args: &[&str]) -> TodoCli {
+        TodoCli::parse_from([&["todo"], args, &["get", "all"]].concat())
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (String::from(*name), String::from(*value)))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn should_take_flag_over_env_over_config_over_default() {
+        let config = Config::parse("output = \"yaml\"\nfile = \"~/todos.db\"\n").unwrap();
+        let home = Path::new("/home/me");
+        let resolve = |args: &[&str], vars: &[(&str, &str)]| {
+            Settings::resolve(&cli(args), &config, env(vars), Some(home)).unwrap()
+        };
+
+        let from_config = resolve(&[], &[]);
+        assert_eq!(OutputFormat::Yaml, from_config.output.value);
+        assert_eq!(SettingSource::Config, from_config.output.source);
+        assert_eq!("/home/me/todos.db", from_config.file.value);
+        assert_eq!(StorageBackend::Sqlite, from_config.backend.value);
+
+        let from_env = resolve(&[], &[("TODO_OUTPUT", "csv"), ("TODO_FILE", "/tmp/t.json")]);
+        assert_eq!(
+            (OutputFormat::Csv, SettingSource::Env),
+            (from_env.output.value, from_env.output.source)
+        );
+        assert_eq!(StorageBackend::Json, from_env.backend.value);
+
+        let from_flag = resolve(&["-o", "json"], &[("TODO_OUTPUT", "csv")]);
+        assert_eq!(
+            (OutputFormat::Json, SettingSource::Flag),
+            (from_flag.output.value, from_flag.output.source)
+        );
+
+        let defaults = Settings::resolve(&cli(&[]), &Config::default(), env(&[]), Some(home));
+        let defaults = defaults.unwrap();
+        assert_eq!("/home/me/tmp/todo.json", defaults.file.value);
+        assert_eq!(SettingSource::Default, defaults.date_format.source);
+        assert_eq!(None, defaults.list.value);
+    }
+
+    #[test]
+    fn should_use_configured_remote_unless_file_is_given() {
+        let config = Config::parse("remote = \"http://todo.lan:8080/\"\n").unwrap();
+        let resolve = |args: &[&str], vars: &[(&str, &str)]| {
+            Settings::resolve(&cli(args), &config, env(vars), Some(Path::new("/home/me")))
+                .unwrap()
+                .remote
+                .value
+        };
+        assert_eq!(
+            Some(String::from("http://todo.lan:8080")),
+            resolve(&[], &[])
+        );
+        assert_eq!(None, resolve(&["--file", "todo.json"], &[]));
+        assert_eq!(None, resolve(&[], &[("TODO_FILE", "todo.json")]));
+        assert_eq!(
+            Some(String::from("http://other:1")),
+            resolve(
+                &["--remote", "http://other:1"],
+                &[("TODO_FILE", "todo.json")]
+            )
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_values_naming_their_origin() {
+        assert!(matches!(
+            Config::parse("output = \"xml\""),
+            Err(TodoErrors::ParseError { ref message, .. }) if message.starts_with("output: invalid value 'xml'")
+        ));
+        assert!(matches!(
+            Config::parse("\ncolour = \"never\""),
+            Err(TodoErrors::ParseError {
+                line: Some(2),
+                column: Some(1),
+                ..
+            })
+        ));
+        assert!(matches!(
+            Settings::resolve(
+                &cli(&[]),
+                &Config::default(),
+                env(&[("TODO_OUTPUT", "xml")]),
+                Some(Path::new("/home/me"))
+            ),
+            Err(TodoErrors::Validation(ref message)) if message.starts_with("TODO_OUTPUT:")
+        ));
+        let mut config = Config::default();
+        assert!(config.set(ConfigKey::DateFormat, "%Q").is_err());
+        assert_eq!("table", config.set(ConfigKey::Output, "TABLE").unwrap());
+    }
+
+    #[test]
+    fn should_round_trip_config_file_and_find_it_under_xdg_config_home() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = Config::default_path(
+            env(&[("XDG_CONFIG_HOME", tempdir.path().to_str().unwrap())]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(tempdir.path().join("todo/config.toml"), path);
+        assert_eq!(Config::default(), Config::load(&path).unwrap());
+
+        let mut config = Config::default();
+        config.set(ConfigKey::List, " work ").unwrap();
+        config.set(ConfigKey::DateFormat, "%d.%m.%Y").unwrap();
+        config.save(&path).unwrap();
+        let loaded = Config::load(&path).unwrap();
+        assert_eq!(Some("work"), loaded.get(ConfigKey::List));
+        assert_eq!(config, loaded);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::todo_repo::{Priority, Recurrence, Todo, TodoErrors};
+use chrono::{DateTime, Utc};
+use csv::{ReaderBuilder, Writer};
+use serde::{Deserialize, Serialize};
+
+// Tags and blockers are single comma separated columns so the file stays one row per todo.
+#[derive(Serialize, Deserialize)]
+struct CsvRecord {
+    id: String,
+    name: String,
+    description: String,
+    completed: bool,
+    due: Option<DateTime<Utc>>,
+    priority: Option<Priority>,
+    tags: String,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    // Files exported before recurring todos and subtasks have no such columns.
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    #[serde(default)]
+    parent_id: Option<String>,
+    #[serde(default)]
+    blocked_by: String,
+}
+
+pub fn export(todos: &[Todo]) -> Result<String, TodoErrors> {
+    let mut writer = Writer::from_writer(Vec::new());
+    for todo in todos {
+        writer.serialize(CsvRecord {
+            id: todo.id.clone(),
+            name: todo.name.clone(),
+            description: todo.description.clone(),
+            completed: todo.completed,
+            due: todo.due,
+            priority: todo.priority,
+            tags: todo.tags.join(","),
+            created_at: todo.created_at,
+            updated_at: todo.updated_at,
+            completed_at: todo.completed_at,
+            recurrence: todo.recurrence.clone(),
+            parent_id: todo.parent_id.clone(),
+            blocked_by: todo.blocked_by.join(","),
+        })?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| TodoErrors::from(e.into_error()))?;
+    String::from_utf8(bytes).map_err(|e| TodoErrors::Io(e.to_string()))
+}
+
+fn split_list(column: &str) -> Vec<String> {
+    column
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| String::from(item.trim()))
+        .collect()
+}
+
+pub fn import(input: &str) -> Result<Vec<Todo>, TodoErrors> {
+    ReaderBuilder::new()
+        .trim(csv::Trim::Fields)
+        .from_reader(input.as_bytes())
+        .deserialize::<CsvRecord>()
+        .enumerate()
+        .map(|(index, record)| {
+            // Line 1 is the header.
+            let record = record.map_err(|e| super::line_error(index + 2, e))?;
+            Ok(Todo {
+                id: record.id,
+                name: record.name,
+                description: record.description,
+                completed: record.completed,
+                due: record.due,
+                priority: record.priority,
+                tags: split_list(&record.tags),
+                created_at: record.created_at,
+                updated_at: record.updated_at,
+                completed_at: record.completed_at,
+                deleted_at: None,
+                recurrence: record.recurrence,
+                parent_id: record.parent_id,
+                blocked_by: split_list(&record.blocked_by),
+                list: None,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,132 @@
+use crate::exchange::{join_text, line_error, split_text};
+use crate::todo_repo::{Priority, Recurrence, Todo, TodoErrors};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string};
+
+const METADATA_START: &str = "<!-- todo ";
+const METADATA_END: &str = " -->";
+/// Written for `-->` in the text, which would close an HTML comment around it.
+const ESCAPED_COMMENT_END: &str = "--\\>";
+
+/// Fields without a visible place in the checklist, kept in an HTML comment that renders as
+/// nothing once pasted into a PR description.
+#[derive(Serialize, Deserialize, Default)]
+struct Metadata {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recurrence: Option<Recurrence>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    blocked_by: Vec<String>,
+}
+
+pub fn export(todos: &[Todo]) -> Result<String, TodoErrors> {
+    todos
+        .iter()
+        .map(|todo| {
+            let metadata = Metadata {
+                id: todo.id.clone(),
+                due: todo.due,
+                priority: todo.priority,
+                tags: todo.tags.clone(),
+                created_at: todo.created_at,
+                updated_at: todo.updated_at,
+                completed_at: todo.completed_at,
+                recurrence: todo.recurrence.clone(),
+                parent_id: todo.parent_id.clone(),
+                blocked_by: todo.blocked_by.clone(),
+            };
+            Ok(format!(
+                "- [{}] {} {}{}{}\n",
+                if todo.completed { "x" } else { " " },
+                join_text(todo).replace("-->", ESCAPED_COMMENT_END),
+                METADATA_START,
+                to_string(&metadata)?,
+                METADATA_END
+            ))
+        })
+        .collect()
+}
+
+/// Reads every `- [ ]` / `- [x]` item (also with `*` bullets); other lines are skipped.
+pub fn import(input: &str) -> Result<Vec<Todo>, TodoErrors> {
+    let mut todos = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let item = line.trim_start();
+        let Some(item) = item.strip_prefix("- ").or_else(|| item.strip_prefix("* ")) else {
+            continue;
+        };
+        let (completed, text) = if let Some(text) = item.strip_prefix("[ ]") {
+            (false, text)
+        } else if let Some(text) = item
+            .strip_prefix("[x]")
+            .or_else(|| item.strip_prefix("[X]"))
+        {
+            (true, text)
+        } else {
+            continue;
+        };
+        let (text, metadata) = match text.trim_end().strip_suffix(METADATA_END.trim_start()) {
+            Some(rest) => match rest.rsplit_once(METADATA_START.trim_end()) {
+                Some((text, metadata)) => (
+                    text,
+                    from_str::<Metadata>(metadata.trim()).map_err(|e| line_error(index + 1, e))?,
+                ),
+                None => (text, Metadata::default()),
+            },
+            None => (text, Metadata::default()),
+        };
+        let (name, description) = split_text(text);
+        todos.push(Todo {
+            id: metadata.id,
+            name,
+            description,
+            completed,
+            due: metadata.due,
+            priority: metadata.priority,
+            tags: metadata.tags,
+            created_at: metadata.created_at,
+            updated_at: metadata.updated_at,
+            completed_at: metadata.completed_at,
+            deleted_at: None,
+            recurrence: metadata.recurrence,
+            parent_id: metadata.parent_id,
+            blocked_by: metadata.blocked_by,
+            list: None,
+        });
+    }
+    Ok(todos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_import_plain_checklist_without_metadata() {
+        let input = "## Release\n- [ ] Bump version\n* [X] Write changelog — for 1.2\nnot a task\n";
+        let todos = import(input).unwrap();
+        assert_eq!(2, todos.len());
+        assert_eq!("Bump version", todos[0].name);
+        assert!(!todos[0].completed);
+        assert_eq!("", todos[0].id);
+        assert_eq!("Write changelog", todos[1].name);
+        assert_eq!("for 1.2", todos[1].description);
+        assert!(todos[1].completed);
+    }
+}
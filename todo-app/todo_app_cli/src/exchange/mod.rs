@@ -0,0 +1,210 @@
+mod csv_format;
+mod markdown;
+mod todo_txt;
+
+use crate::todo_repo::{Todo, TodoErrors};
+use clap::ValueEnum;
+use serde_json::{from_str, to_string_pretty};
+
+/// Separates name and description in formats that only hold one line of text per todo.
+const DESCRIPTION_SEPARATOR: &str = " — ";
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ExchangeFormat {
+    /// One row per todo with every field as a column
+    Csv,
+    /// `- [ ]` / `- [x]` checklist, other fields kept in a trailing HTML comment
+    Markdown,
+    /// todo.txt lines with priority, dates, +tags and due:/id: keys
+    Todotxt,
+    /// Array of todo objects
+    Json,
+}
+
+pub fn export_todos(todos: &[Todo], format: ExchangeFormat) -> Result<String, TodoErrors> {
+    match format {
+        ExchangeFormat::Csv => csv_format::export(todos),
+        ExchangeFormat::Markdown => markdown::export(todos),
+        ExchangeFormat::Todotxt => Ok(todo_txt::export(todos)),
+        ExchangeFormat::Json => to_string_pretty(todos).map_err(TodoErrors::from),
+    }
+}
+
+/// Parses todos from `input`. Fields a format cannot carry are left at their defaults, and
+/// an id is empty when the input had none.
+pub fn import_todos(input: &str, format: ExchangeFormat) -> Result<Vec<Todo>, TodoErrors> {
+    match format {
+        ExchangeFormat::Csv => csv_format::import(input),
+        ExchangeFormat::Markdown => markdown::import(input),
+        ExchangeFormat::Todotxt => todo_txt::import(input),
+        ExchangeFormat::Json if input.trim().is_empty() => Ok(Vec::new()),
+        ExchangeFormat::Json => from_str(input).map_err(TodoErrors::from),
+    }
+}
+
+/// Name and description on one line, with line breaks and backslashes escaped.
+fn join_text(todo: &Todo) -> String {
+    if todo.description.is_empty() {
+        escape_text(&todo.name)
+    } else {
+        format!(
+            "{}{}{}",
+            escape_text(&todo.name),
+            DESCRIPTION_SEPARATOR,
+            escape_text(&todo.description)
+        )
+    }
+}
+
+fn split_text(text: &str) -> (String, String) {
+    match text.split_once(DESCRIPTION_SEPARATOR) {
+        Some((name, description)) => (
+            unescape_text(name.trim()),
+            unescape_text(description.trim()),
+        ),
+        None => (unescape_text(text.trim()), String::new()),
+    }
+}
+
+/// Writes `\`, `\n` and `\r` for backslashes and line breaks, which would end a record early.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Reverses `escape_text`, and the `\>` markdown writes for `>`. Other backslashes are kept, as
+/// files written by other tools use them freely.
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('>') => unescaped.push('>'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn line_error(line_number: usize, message: impl std::fmt::Display) -> TodoErrors {
+    TodoErrors::ParseError {
+        message: message.to_string(),
+        line: Some(line_number),
+        column: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_repo::Priority;
+    use chrono::{Local, TimeZone, Utc};
+
+    fn get_todo(id: &str, name: &str, description: &str) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(name),
+            description: String::from(description),
+            ..Todo::default()
+        }
+    }
+
+    fn get_todo_list() -> Vec<Todo> {
+        let created = Local.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+        let due = Local.with_ymd_and_hms(2026, 10, 20, 0, 0, 0).unwrap();
+        vec![
+            Todo {
+                priority: Some(Priority::High),
+                tags: vec![String::from("work"), String::from("finance")],
+                due: Some(due.with_timezone(&Utc)),
+                created_at: Some(created.with_timezone(&Utc)),
+                ..get_todo("a1", "File taxes", "Before the deadline")
+            },
+            Todo {
+                completed: true,
+                completed_at: Some(due.with_timezone(&Utc)),
+                created_at: Some(created.with_timezone(&Utc)),
+                ..get_todo("b2", "Buy milk", "")
+            },
+        ]
+    }
+
+    #[test]
+    fn should_round_trip_every_format() {
+        let todos = get_todo_list();
+        for format in [
+            ExchangeFormat::Csv,
+            ExchangeFormat::Markdown,
+            ExchangeFormat::Todotxt,
+            ExchangeFormat::Json,
+        ] {
+            let exported = export_todos(&todos, format).unwrap();
+            assert_eq!(
+                todos,
+                import_todos(&exported, format).unwrap(),
+                "{:?} did not round trip:\n{}",
+                format,
+                exported
+            );
+        }
+    }
+
+    #[test]
+    fn should_round_trip_multi_line_text_in_every_format() {
+        let todos = vec![get_todo(
+            "a1",
+            "Plan\r\nrelease",
+            "First line\nsecond line with C:\\dir\\n and --> arrow",
+        )];
+        for format in [
+            ExchangeFormat::Csv,
+            ExchangeFormat::Markdown,
+            ExchangeFormat::Todotxt,
+            ExchangeFormat::Json,
+        ] {
+            let exported = export_todos(&todos, format).unwrap();
+            assert_eq!(
+                todos,
+                import_todos(&exported, format).unwrap(),
+                "{:?} did not round trip:\n{}",
+                format,
+                exported
+            );
+        }
+    }
+
+    #[test]
+    fn should_keep_unknown_backslash_sequences_on_import() {
+        assert_eq!(
+            (String::from("C:\\tmp\\x"), String::new()),
+            split_text("C:\\tmp\\x")
+        );
+    }
+
+    #[test]
+    fn should_split_name_and_description_on_separator_only() {
+        assert_eq!(
+            (
+                String::from("Q3 - report"),
+                String::from("numbers — charts")
+            ),
+            split_text("Q3 - report — numbers — charts")
+        );
+        assert_eq!(
+            (String::from("Alone"), String::new()),
+            split_text(" Alone ")
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::cli::parse_date_time;
+use crate::exchange::{join_text, line_error, split_text};
+use crate::todo_repo::{Priority, Todo, TodoErrors};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// todo.txt priorities are letters, (A) being the most important.
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::Urgent => 'A',
+        Priority::High => 'B',
+        Priority::Medium => 'C',
+        Priority::Low => 'D',
+    }
+}
+
+fn letter_priority(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::Urgent),
+        'B' => Some(Priority::High),
+        'C' => Some(Priority::Medium),
+        'D'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn format_date(date_time: DateTime<Utc>) -> String {
+    date_time
+        .with_timezone(&Local)
+        .format(DATE_FORMAT)
+        .to_string()
+}
+
+// Due dates at local midnight are written as plain dates, the form other todo.txt tools expect.
+fn format_due(due: DateTime<Utc>) -> String {
+    let local = due.with_timezone(&Local);
+    if local.time() == NaiveTime::MIN {
+        format_date(due)
+    } else {
+        due.to_rfc3339()
+    }
+}
+
+fn parse_date(token: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(token, DATE_FORMAT)
+        .ok()
+        .and_then(|_| parse_date_time(token).ok())
+}
+
+pub fn export(todos: &[Todo]) -> String {
+    todos
+        .iter()
+        .map(|todo| {
+            let mut parts = Vec::<String>::new();
+            if todo.completed {
+                parts.push(String::from("x"));
+                // A creation date after "x" is only unambiguous with a completion date before it.
+                if let Some(completed_at) = todo.completed_at.or(todo.created_at) {
+                    parts.push(format_date(completed_at));
+                }
+            } else if let Some(priority) = todo.priority {
+                parts.push(format!("({})", priority_letter(priority)));
+            }
+            if let Some(created_at) = todo.created_at {
+                parts.push(format_date(created_at));
+            }
+            parts.push(join_text(todo));
+            parts.extend(
+                todo.tags
+                    .iter()
+                    .map(|tag| format!("+{}", tag.replace(' ', "_"))),
+            );
+            if let Some(priority) = todo.priority.filter(|_| todo.completed) {
+                parts.push(format!("pri:{}", priority_letter(priority)));
+            }
+            if let Some(due) = todo.due {
+                parts.push(format!("due:{}", format_due(due)));
+            }
+            if let Some(recurrence) = &todo.recurrence {
+                parts.push(format!("rec:{}", recurrence));
+            }
+            if !todo.id.is_empty() {
+                parts.push(format!("id:{}", todo.id));
+            }
+            if let Some(parent_id) = &todo.parent_id {
+                parts.push(format!("parent:{}", parent_id));
+            }
+            parts.extend(
+                todo.blocked_by
+                    .iter()
+                    .map(|blocker_id| format!("blocked_by:{}", blocker_id)),
+            );
+            format!("{}\n", parts.join(" "))
+        })
+        .collect()
+}
+
+/// Parses todo.txt lines; `+project` and `@context` both become tags and blank lines are skipped.
+pub fn import(input: &str) -> Result<Vec<Todo>, TodoErrors> {
+    let mut todos = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut todo = Todo::default();
+        let mut tokens = line.split_whitespace().peekable();
+        if tokens.peek() == Some(&"x") {
+            tokens.next();
+            todo.completed = true;
+            todo.completed_at = tokens.peek().and_then(|token| parse_date(token));
+            if todo.completed_at.is_some() {
+                tokens.next();
+            }
+        }
+        if let Some(letter) = tokens.peek().and_then(|token| {
+            let mut chars = token.strip_prefix('(')?.strip_suffix(')')?.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        }) {
+            todo.priority = letter_priority(letter);
+            tokens.next();
+        }
+        todo.created_at = tokens.peek().and_then(|token| parse_date(token));
+        if todo.created_at.is_some() {
+            tokens.next();
+        }
+
+        let mut words = Vec::<&str>::new();
+        for token in tokens {
+            if let Some(tag) = token
+                .strip_prefix('+')
+                .or_else(|| token.strip_prefix('@'))
+                .filter(|tag| !tag.is_empty())
+            {
+                todo.tags.push(String::from(tag));
+            } else if let Some(value) = token.strip_prefix("due:") {
+                todo.due = Some(parse_date_time(value).map_err(|e| line_error(index + 1, e))?);
+            } else if let Some(value) = token.strip_prefix("rec:") {
+                todo.recurrence = Some(value.parse().map_err(|e| line_error(index + 1, e))?);
+            } else if let Some(value) = token.strip_prefix("id:") {
+                todo.id = String::from(value);
+            } else if let Some(value) = token.strip_prefix("parent:") {
+                todo.parent_id = Some(String::from(value));
+            } else if let Some(value) = token.strip_prefix("blocked_by:") {
+                todo.blocked_by.push(String::from(value));
+            } else if let Some(letter) = token.strip_prefix("pri:").and_then(|v| v.chars().next()) {
+                todo.priority = letter_priority(letter);
+            } else {
+                words.push(token);
+            }
+        }
+        (todo.name, todo.description) = split_text(&words.join(" "));
+        if todo.name.is_empty() {
+            return Err(line_error(index + 1, "todo has no text"));
+        }
+        todos.push(todo);
+    }
+    Ok(todos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_import_standard_todo_txt_line() {
+        let input = "x 2026-10-02 2026-10-01 Call mom +family @phone due:2026-10-05 https://x.y\n\n(B) Review PR";
+        let todos = import(input).unwrap();
+        assert_eq!(2, todos.len());
+        assert!(todos[0].completed);
+        assert_eq!(parse_date("2026-10-02"), todos[0].completed_at);
+        assert_eq!(parse_date("2026-10-01"), todos[0].created_at);
+        assert_eq!("Call mom https://x.y", todos[0].name);
+        assert_eq!(vec!["family", "phone"], todos[0].tags);
+        assert_eq!(parse_date("2026-10-05"), todos[0].due);
+        assert_eq!(Some(Priority::High), todos[1].priority);
+        assert_eq!("Review PR", todos[1].name);
+    }
+
+    #[test]
+    fn should_report_line_of_invalid_due_date() {
+        let result = import("Ok task\nBad task due:someday");
+        assert!(matches!(
+            result,
+            Err(TodoErrors::ParseError { line: Some(2), .. })
+        ));
+    }
+}
@@ -1,11 +1,20 @@
 pub mod cli;
+pub mod config;
+pub mod exchange;
 pub mod printer;
+pub mod server;
+pub mod sync;
 pub mod todo_repo;
+pub mod tui;
 
 pub use cli::{
-    AddCommandArgs, Commands, CompleteCommandArgs, DeleteCommandArgs, GetCommand, OutputFormat,
-    TodoCli,
+    AddCommandArgs, Commands, CompleteCommandArgs, DeleteCommandArgs, EditCommandArgs,
+    ExportCommandArgs, GetCommand, HistoryCommandArgs, ImportCommandArgs, OutputFormat,
+    PurgeCommandArgs, RestoreCommandArgs, SelectionArgs, StorageBackend, TodoCli, TrashCommand,
+    UndoCommandArgs,
 };
 
 pub use printer::TodoPrinter;
-pub use todo_repo::{FileDataAccess, TodoRepository};
+pub use todo_repo::{
+    FileDataAccess, FileJournal, SqliteDataAccess, TodoJournal, TodoRepository, TodoStorage,
+};
@@ -1,52 +1,153 @@
 use clap::Parser;
 use todo::{
     cli::{
-        AddCommandArgs, Commands, CompleteCommandArgs, DeleteCommandArgs, GetCommand, OutputFormat,
-        TodoCli,
+        AddCommandArgs, Commands, CompleteCommandArgs, ConfigCommand, DeleteCommandArgs,
+        EditCommandArgs, ExportCommandArgs, GetCommand, GraphCommandArgs, HistoryCommandArgs,
+        ImportCommandArgs, LinkCommandArgs, ListsCommandArgs, MoveCommandArgs, OutputFormat,
+        PurgeCommandArgs, RestoreCommandArgs, SearchCommandArgs, SelectionArgs, ServeCommandArgs,
+        StorageBackend, SyncCommandArgs, TodoCli, TrashCommand, UndoCommandArgs,
+    },
+    config::{Config, SettingEntry, SettingSource, Settings},
+    exchange::{export_todos, import_todos},
+    printer::{TableStyle, TodoPrinter},
+    sync::GitSync,
+    todo_repo::{
+        BulkReport, FileDataAccess, FileJournal, RemoteDataAccess, SearchQuery, SqliteDataAccess,
+        TodoErrors, TodoQuery, TodoRepository, TodoSelection, TodoStorage,
     },
-    printer::TodoPrinter,
-    todo_repo::{FileDataAccess, TodoRepository},
 };
 
 use dirs::home_dir;
-use std::io::Stdout;
+use serde_json::json;
+use std::io::{IsTerminal, Read, Stdout};
+use std::path::PathBuf;
 use std::process;
 
 enum AppResult {
     // Exit code 0
     Success,
-    // Exit code 1, with error message
-    Error(String),
+    // Exit code of the error category, with what was being attempted and the error
+    Error(String, TodoErrors),
 }
 
 impl AppResult {
-    pub fn exit(self) -> ! {
+    fn error(context: &str, error: TodoErrors) -> Self {
+        AppResult::Error(String::from(context), error)
+    }
+
+    /// Writes errors to stderr, as a one-line JSON envelope when JSON output was requested.
+    pub fn exit(self, output_format: OutputFormat) -> ! {
         match self {
             AppResult::Success => process::exit(0),
-            AppResult::Error(msg) => {
-                eprintln!("{}", msg);
-                process::exit(1)
+            AppResult::Error(context, error) => {
+                let message = format!("{}: {}", context, error);
+                match output_format {
+                    OutputFormat::Json | OutputFormat::Ndjson => {
+                        let mut body = json!({
+                            "kind": error.kind(),
+                            "exit_code": error.exit_code(),
+                            "message": message,
+                        });
+                        if let TodoErrors::ParseError { line, column, .. } = &error {
+                            body["line"] = json!(line);
+                            body["column"] = json!(column);
+                        }
+                        eprintln!("{}", json!({ "error": body }));
+                    }
+                    _ => eprintln!("{}", message),
+                }
+                process::exit(error.exit_code())
             }
         }
     }
 }
 
+type Repository = TodoRepository<Box<dyn TodoStorage>>;
+
+fn open_storage(path: String, backend: StorageBackend) -> Result<Box<dyn TodoStorage>, TodoErrors> {
+    match backend {
+        StorageBackend::Json => Ok(Box::new(FileDataAccess::new(path))),
+        StorageBackend::Sqlite => SqliteDataAccess::open(&path)
+            .map(|data_access| Box::new(data_access) as Box<dyn TodoStorage>),
+    }
+}
+
 fn main() {
     let cli = TodoCli::parse();
-    let path = match cli.file {
-        Some(file_path) => file_path,
+    // Errors found while resolving settings use whatever output format was asked for directly.
+    let requested_format = match &cli.template {
+        Some(_) => OutputFormat::Template,
+        None => cli.output.unwrap_or(OutputFormat::Text),
+    };
+    let env = |name: &str| std::env::var(name).ok();
+    let home = home_dir();
+    let config_path = Config::default_path(env, home.as_deref());
+    let config = match config_path.as_deref().map(Config::load).transpose() {
+        Ok(config) => config.unwrap_or_default(),
+        Err(e) => AppResult::error(
+            &format!(
+                "Unable to load config file {}",
+                config_path.unwrap_or_default().display()
+            ),
+            e,
+        )
+        .exit(requested_format),
+    };
+    let settings = match Settings::resolve(&cli, &config, env, home.as_deref()) {
+        Ok(settings) => settings,
+        Err(e) => AppResult::error("Invalid settings", e).exit(requested_format),
+    };
+    let output_format = settings.output.value;
+    if let Err(e) = check_output_format(&cli.command, output_format) {
+        AppResult::error("Unsupported output format", e).exit(output_format);
+    }
+    let mut todo_printer = TodoPrinter::<Stdout>::new(std::io::stdout())
+        .with_table_style(TableStyle::detect(settings.color.value))
+        .with_date_format(settings.date_format.value.clone());
+    if let Some(template) = &cli.template {
+        todo_printer = todo_printer.with_template(template.clone());
+    }
+
+    // Settings are managed without opening the data file, which they may point elsewhere.
+    if let Commands::Config { config_command } = &cli.command {
+        handle_config_command(
+            &mut todo_printer,
+            config_command,
+            config_path,
+            config,
+            &settings,
+            output_format,
+        )
+        .exit(output_format);
+    }
+
+    let mut todo_repo = match &settings.remote.value {
+        // The server journals what remote clients change, so there is no local journal.
+        Some(url) => {
+            TodoRepository::new(Box::new(RemoteDataAccess::new(url)) as Box<dyn TodoStorage>)
+        }
         None => {
-            let mut default_path = home_dir().expect("Could not find home directory");
-            default_path.push("tmp");
-            std::fs::create_dir_all(&default_path).expect("Failed to create tmp directory in home");
-            default_path.push("todo.json");
-            String::from(default_path.to_str().unwrap())
+            if settings.file.source == SettingSource::Default
+                && let Some(default_dir) = std::path::Path::new(&settings.file.value).parent()
+            {
+                std::fs::create_dir_all(default_dir)
+                    .expect("Failed to create tmp directory in home");
+            }
+            let path = settings.file.value.clone();
+            // The journal sits next to the data file so undo history follows the file it describes.
+            let journal = FileJournal::new(format!("{}.journal", path));
+            let storage = match open_storage(path, settings.backend.value) {
+                Ok(storage) => storage,
+                Err(e) => AppResult::error("Unable to open sqlite database", e).exit(output_format),
+            };
+            TodoRepository::new(storage).with_journal(Box::new(journal))
         }
     };
-    let file_data_access = FileDataAccess::new(path);
-    let mut todo_repo = TodoRepository::new(file_data_access);
-    let mut todo_printer = TodoPrinter::<Stdout>::new(std::io::stdout());
-    let output_format = cli.output.unwrap_or(OutputFormat::Text);
+    match (&settings.list.value, settings.list.source) {
+        (Some(list), SettingSource::Flag) => todo_repo = todo_repo.with_list(list.clone()),
+        (Some(list), _) => todo_repo = todo_repo.with_default_list(list.clone()),
+        (None, _) => {}
+    }
 
     let app_result = match &cli.command {
         Commands::Get { get_command } => handle_get_command(
@@ -55,6 +156,12 @@ fn main() {
             get_command,
             output_format,
         ),
+        Commands::Search(search_args) => handle_search_command(
+            &mut todo_repo,
+            &mut todo_printer,
+            search_args,
+            output_format,
+        ),
         Commands::Add(add_args) => {
             handle_add_command(&mut todo_repo, &mut todo_printer, add_args, output_format)
         }
@@ -70,68 +177,270 @@ fn main() {
             delete_args,
             output_format,
         ),
+        Commands::Trash { trash_command } => handle_trash_command(
+            &mut todo_repo,
+            &mut todo_printer,
+            trash_command,
+            output_format,
+        ),
+        Commands::Restore(restore_args) => handle_restore_command(
+            &mut todo_repo,
+            &mut todo_printer,
+            restore_args,
+            output_format,
+        ),
+        Commands::Purge(purge_args) => {
+            handle_purge_command(&mut todo_repo, &mut todo_printer, purge_args, output_format)
+        }
+        Commands::Edit(edit_args) => {
+            handle_edit_command(&mut todo_repo, &mut todo_printer, edit_args, output_format)
+        }
+        Commands::Link(link_args) => {
+            handle_link_command(&mut todo_repo, &mut todo_printer, link_args, output_format)
+        }
+        Commands::Unlink(unlink_args) => handle_unlink_command(
+            &mut todo_repo,
+            &mut todo_printer,
+            unlink_args,
+            output_format,
+        ),
+        Commands::Move(move_args) => {
+            handle_move_command(&mut todo_repo, &mut todo_printer, move_args, output_format)
+        }
+        Commands::Lists(lists_args) => {
+            handle_lists_command(&mut todo_repo, &mut todo_printer, lists_args, output_format)
+        }
+        Commands::Graph(graph_args) => {
+            handle_graph_command(&mut todo_repo, &mut todo_printer, graph_args)
+        }
+        Commands::Export(export_args) => {
+            handle_export_command(&mut todo_repo, &mut todo_printer, export_args)
+        }
+        Commands::Import(import_args) => handle_import_command(
+            &mut todo_repo,
+            &mut todo_printer,
+            import_args,
+            output_format,
+        ),
+        Commands::Undo(undo_args) => {
+            handle_undo_command(&mut todo_repo, &mut todo_printer, undo_args, output_format)
+        }
+        Commands::Redo(redo_args) => {
+            handle_redo_command(&mut todo_repo, &mut todo_printer, redo_args, output_format)
+        }
+        Commands::History(history_args) => handle_history_command(
+            &mut todo_repo,
+            &mut todo_printer,
+            history_args,
+            output_format,
+        ),
+        Commands::Tui => handle_tui_command(&mut todo_repo),
+        Commands::Serve(serve_args) => handle_serve_command(&mut todo_repo, serve_args),
+        Commands::Sync(sync_args) => {
+            handle_sync_command(&mut todo_printer, &settings, sync_args, output_format)
+        }
+        Commands::Config { .. } => unreachable!("config commands are handled before"),
     };
 
-    app_result.exit();
+    app_result.exit(output_format);
+}
+
+/// Refuses CSV and template output for commands that print something other than todos, before
+/// the command changes anything.
+fn check_output_format(command: &Commands, output_format: OutputFormat) -> Result<(), TodoErrors> {
+    let command_name = match command {
+        Commands::History(_) => "history",
+        Commands::Undo(_) => "undo",
+        Commands::Redo(_) => "redo",
+        Commands::Lists(_) => "lists",
+        Commands::Sync(_) => "sync",
+        Commands::Config {
+            config_command: ConfigCommand::Show,
+        } => "config show",
+        _ => return Ok(()),
+    };
+    let format_name = match output_format {
+        OutputFormat::Csv => "--output csv",
+        OutputFormat::Template => "--template",
+        _ => return Ok(()),
+    };
+    Err(TodoErrors::Validation(format!(
+        "{} does not support {}, it prints no todos",
+        command_name, format_name
+    )))
 }
 
 fn handle_get_command(
-    todo_repo: &mut TodoRepository<FileDataAccess>,
+    todo_repo: &mut Repository,
     todo_printer: &mut TodoPrinter<Stdout>,
     get_command: &GetCommand,
     output_format: OutputFormat,
 ) -> AppResult {
+    match todo_repo.subtask_progress() {
+        Ok(subtask_progress) => todo_printer.set_subtask_progress(subtask_progress),
+        Err(e) => return AppResult::error("Error retrieving todos", e),
+    }
     match get_command {
         GetCommand::All => handle_get_all(todo_repo, todo_printer, output_format),
-        GetCommand::Id(todo_id_args) => handle_get_todo_by_id(
-            todo_repo,
-            todo_printer,
-            String::from(&todo_id_args.todo_id),
-            output_format,
-        ),
-        GetCommand::Name(todo_name_args) => handle_get_todo_by_name(
+        GetCommand::Id(todo_id_args) => match &todo_id_args.todo_id {
+            Some(todo_id) => handle_get_todo_by_id(
+                todo_repo,
+                todo_printer,
+                String::from(todo_id),
+                output_format,
+            ),
+            None => match read_stdin_lines("a todo id") {
+                Ok(todo_ids) => {
+                    handle_get_todos_by_ids(todo_repo, todo_printer, todo_ids, output_format)
+                }
+                Err(e) => AppResult::error("Error retrieving todo by id", e),
+            },
+        },
+        GetCommand::Name(todo_name_args) => {
+            let search_string = match &todo_name_args.search_string {
+                Some(search_string) => Ok(String::from(search_string)),
+                None => read_stdin_lines("a name to search for").and_then(|lines| {
+                    lines
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| TodoErrors::Validation(String::from("stdin is empty")))
+                }),
+            };
+            match search_string {
+                Ok(search_string) => {
+                    handle_get_todo_by_name(todo_repo, todo_printer, search_string, output_format)
+                }
+                Err(e) => AppResult::error("Error retrieving todo by name", e),
+            }
+        }
+        GetCommand::List(list_args) => handle_get_list(
             todo_repo,
             todo_printer,
-            String::from(&todo_name_args.search_string),
+            TodoQuery::from(list_args),
             output_format,
         ),
+        GetCommand::Upcoming(upcoming_args) => {
+            handle_get_upcoming(todo_repo, todo_printer, upcoming_args.count, output_format)
+        }
+        GetCommand::Ready => handle_get_ready(todo_repo, todo_printer, output_format),
     }
 }
 
 fn handle_get_all(
-    todo_repo: &mut TodoRepository<FileDataAccess>,
+    todo_repo: &mut Repository,
     todo_printer: &mut TodoPrinter<Stdout>,
     output_format: OutputFormat,
 ) -> AppResult {
     match todo_repo.get_all_todos() {
         Ok(all_todos) => {
+            let positions = all_todos
+                .iter()
+                .enumerate()
+                .map(|(index, todo)| (todo.id.clone(), index + 1))
+                .collect();
+            todo_printer.set_positions(positions);
             todo_printer.print_list_todo(all_todos, output_format);
             AppResult::Success
         }
-        Err(e) => AppResult::Error(format!("Error retrieving todos: {}", e.error_message())),
+        Err(e) => AppResult::error("Error retrieving todos", e),
+    }
+}
+
+fn handle_get_list(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    query: TodoQuery,
+    output_format: OutputFormat,
+) -> AppResult {
+    match todo_repo.query_todos(&query) {
+        Ok(found_todos) => {
+            todo_printer.print_list_todo(found_todos, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Error retrieving todos", e),
+    }
+}
+
+fn handle_get_upcoming(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    count: usize,
+    output_format: OutputFormat,
+) -> AppResult {
+    match todo_repo.upcoming_occurrences(count) {
+        Ok(occurrences) => {
+            todo_printer.print_list_todo(occurrences, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Error retrieving upcoming todos", e),
+    }
+}
+
+fn handle_get_ready(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    output_format: OutputFormat,
+) -> AppResult {
+    match todo_repo.ready_todos() {
+        Ok(ready_todos) => {
+            todo_printer.print_list_todo(ready_todos, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Error retrieving ready todos", e),
     }
 }
 
 fn handle_get_todo_by_id(
-    todo_repo: &mut TodoRepository<FileDataAccess>,
+    todo_repo: &mut Repository,
     todo_printer: &mut TodoPrinter<Stdout>,
     todo_id: String,
     output_format: OutputFormat,
 ) -> AppResult {
-    match todo_repo.get_todo_by_id(todo_id) {
+    match todo_repo
+        .resolve_id(&todo_id)
+        .and_then(|todo_id| todo_repo.get_todo_by_id(todo_id))
+    {
         Ok(todo) => {
             todo_printer.print_single_todo(todo, output_format);
             AppResult::Success
         }
-        Err(e) => AppResult::Error(format!(
-            "Error retrieving todo by id: {}",
-            e.error_message()
-        )),
+        Err(e) => AppResult::error("Error retrieving todo by id", e),
+    }
+}
+
+fn handle_get_todos_by_ids(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    todo_ids: Vec<String>,
+    output_format: OutputFormat,
+) -> AppResult {
+    if let [todo_id] = todo_ids.as_slice() {
+        return handle_get_todo_by_id(
+            todo_repo,
+            todo_printer,
+            String::from(todo_id),
+            output_format,
+        );
+    }
+    match todo_ids
+        .into_iter()
+        .map(|todo_id| {
+            todo_repo
+                .resolve_id(&todo_id)
+                .and_then(|todo_id| todo_repo.get_todo_by_id(todo_id))
+        })
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(todos) => {
+            todo_printer.print_list_todo(todos, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Error retrieving todo by id", e),
     }
 }
 
 fn handle_get_todo_by_name(
-    todo_repo: &mut TodoRepository<FileDataAccess>,
+    todo_repo: &mut Repository,
     todo_printer: &mut TodoPrinter<Stdout>,
     todo_name: String,
     output_format: OutputFormat,
@@ -141,57 +450,544 @@ fn handle_get_todo_by_name(
             todo_printer.print_list_todo(all_todo, output_format);
             AppResult::Success
         }
-        Err(e) => AppResult::Error(format!(
-            "Error retrieving todo by name: {}",
-            e.error_message()
-        )),
+        Err(e) => AppResult::error("Error retrieving todo by name", e),
+    }
+}
+
+fn handle_search_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    search_command_args: &SearchCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    let hits = SearchQuery::parse(&search_command_args.query.join(" "))
+        .and_then(|query| todo_repo.search_todos(&query));
+    match hits {
+        Ok(mut hits) => {
+            hits.truncate(search_command_args.limit.unwrap_or(usize::MAX));
+            todo_printer.print_search_hits(hits, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Error searching todos", e),
     }
 }
 
 fn handle_add_command(
-    todo_repo: &mut TodoRepository<FileDataAccess>,
+    todo_repo: &mut Repository,
     todo_printer: &mut TodoPrinter<Stdout>,
     add_command_args: &AddCommandArgs,
     output_format: OutputFormat,
 ) -> AppResult {
-    match todo_repo.add_todo(add_command_args) {
-        Ok(todo) => {
-            todo_printer.print_single_todo(todo, output_format);
+    if add_command_args.has_fields() {
+        return match todo_repo.add_todo(add_command_args) {
+            Ok(todo) => {
+                todo_printer.print_single_todo(todo, output_format);
+                AppResult::Success
+            }
+            Err(e) => AppResult::error("Unable to add a todo", e),
+        };
+    }
+    // Without flags the todos come from stdin as JSON, one object or many.
+    let piped_args = match read_stdin("--name or todo JSON")
+        .and_then(|input| AddCommandArgs::from_json(&input))
+    {
+        Ok(piped_args) => piped_args,
+        Err(e) => return AppResult::error("Unable to add a todo", e),
+    };
+    match todo_repo.add_todos(&piped_args) {
+        Ok(mut todos) if todos.len() == 1 => {
+            todo_printer.print_single_todo(todos.remove(0), output_format);
+            AppResult::Success
+        }
+        Ok(todos) => {
+            todo_printer.print_list_todo(todos, output_format);
             AppResult::Success
         }
-        Err(e) => AppResult::Error(format!("Unable to add a todo: {}", e.error_message())),
+        Err(e) => AppResult::error("Unable to add a todo", e),
+    }
+}
+
+/// Reads all of stdin, refusing to wait on an interactive terminal.
+fn read_stdin(expected: &str) -> Result<String, TodoErrors> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(TodoErrors::Validation(format!(
+            "expected {} as an argument or on stdin",
+            expected
+        )));
+    }
+    let mut input = String::new();
+    stdin
+        .read_to_string(&mut input)
+        .map_err(|e| TodoErrors::Io(format!("failed to read stdin: {}", e)))?;
+    Ok(input)
+}
+
+/// Non-blank stdin lines, trimmed.
+fn read_stdin_lines(expected: &str) -> Result<Vec<String>, TodoErrors> {
+    let lines: Vec<String> = read_stdin(expected)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect();
+    if lines.is_empty() {
+        return Err(TodoErrors::Validation(format!(
+            "expected {} on stdin, but it was empty",
+            expected
+        )));
+    }
+    Ok(lines)
+}
+
+/// Uses ids piped on stdin, one per line, when neither `--id` nor a filter was given. Ids may
+/// be any reference `resolve_id` accepts.
+fn resolve_selection(
+    todo_repo: &mut Repository,
+    selection_args: &SelectionArgs,
+) -> Result<TodoSelection, TodoErrors> {
+    let selection = if !selection_args.ids.is_empty() || selection_args.has_filter() {
+        TodoSelection::from(selection_args)
+    } else {
+        read_stdin_lines("--id, a filter, or todo ids").map(TodoSelection::Ids)?
+    };
+    match selection {
+        TodoSelection::Ids(references) => {
+            todo_repo.resolve_ids(&references).map(TodoSelection::Ids)
+        }
+        matching => Ok(matching),
+    }
+}
+
+/// Prints the todos that were changed; ids that were not found make the command fail.
+fn report_bulk_result(
+    todo_printer: &mut TodoPrinter<Stdout>,
+    report: BulkReport,
+    output_format: OutputFormat,
+    action: &str,
+) -> AppResult {
+    let not_found = report.not_found.clone();
+    todo_printer.print_bulk_report(report, output_format);
+    if not_found.is_empty() {
+        AppResult::Success
+    } else {
+        AppResult::error(
+            &format!("Unable to {}", action),
+            TodoErrors::NotFound(
+                not_found
+                    .iter()
+                    .map(|todo_id| format!("Todo by id:{} not found", todo_id))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        )
     }
 }
 
 fn handle_complete_command(
-    todo_repo: &mut TodoRepository<FileDataAccess>,
+    todo_repo: &mut Repository,
     todo_printer: &mut TodoPrinter<Stdout>,
     complete_command_args: &CompleteCommandArgs,
     output_format: OutputFormat,
 ) -> AppResult {
-    match todo_repo.mark_todo_complete(String::from(&complete_command_args.id)) {
+    let selection = match resolve_selection(todo_repo, &complete_command_args.selection) {
+        Ok(selection) => selection,
+        Err(e) => return AppResult::error("Unable to mark todo completed", e),
+    };
+    match todo_repo.complete_todos(
+        &selection,
+        complete_command_args.cascade,
+        complete_command_args.force,
+    ) {
+        Ok(report) => {
+            report_bulk_result(todo_printer, report, output_format, "mark todo completed")
+        }
+        Err(e) => AppResult::error("Unable to mark todo completed", e),
+    }
+}
+
+fn handle_delete_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    delete_command_args: &DeleteCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    let selection = match resolve_selection(todo_repo, &delete_command_args.selection) {
+        Ok(selection) => selection,
+        Err(e) => return AppResult::error("Unable to delete todo", e),
+    };
+    match todo_repo.delete_todos(&selection, delete_command_args.recursive) {
+        Ok(report) => report_bulk_result(todo_printer, report, output_format, "delete todo"),
+        Err(e) => AppResult::error("Unable to delete todo", e),
+    }
+}
+
+fn handle_trash_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    trash_command: &TrashCommand,
+    output_format: OutputFormat,
+) -> AppResult {
+    match trash_command {
+        TrashCommand::List => match todo_repo.list_trash() {
+            Ok(trashed_todos) => {
+                todo_printer.print_list_todo(trashed_todos, output_format);
+                AppResult::Success
+            }
+            Err(e) => AppResult::error("Error retrieving trash", e),
+        },
+    }
+}
+
+fn handle_restore_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    restore_command_args: &RestoreCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    let restored = todo_repo
+        .resolve_id(&restore_command_args.id)
+        .and_then(|todo_id| todo_repo.restore_todo(todo_id));
+    match restored {
         Ok(todo) => {
             todo_printer.print_single_todo(todo, output_format);
             AppResult::Success
         }
-        Err(e) => AppResult::Error(format!(
-            "Unable to mark todo completed: {}",
-            e.error_message()
-        )),
+        Err(e) => AppResult::error("Unable to restore todo", e),
     }
 }
 
-fn handle_delete_command(
-    todo_repo: &mut TodoRepository<FileDataAccess>,
+fn handle_purge_command(
+    todo_repo: &mut Repository,
     todo_printer: &mut TodoPrinter<Stdout>,
-    delete_command_args: &DeleteCommandArgs,
+    purge_command_args: &PurgeCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    match todo_repo.purge_todos(purge_command_args.older_than) {
+        Ok(purged_todos) => {
+            todo_printer.print_list_todo(purged_todos, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Unable to purge trash", e),
+    }
+}
+
+fn handle_edit_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    edit_command_args: &EditCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    let edited = todo_repo
+        .resolve_id(&edit_command_args.id)
+        .and_then(|todo_id| {
+            todo_repo.update_todo(&EditCommandArgs {
+                id: todo_id,
+                ..edit_command_args.clone()
+            })
+        });
+    match edited {
+        Ok(todo) => {
+            todo_printer.print_single_todo(todo, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Unable to edit todo", e),
+    }
+}
+
+/// The waiting todo's id and the ids it waits on, resolved like `resolve_id` does.
+fn resolve_link(
+    todo_repo: &mut Repository,
+    link_command_args: &LinkCommandArgs,
+) -> Result<(String, Vec<String>), TodoErrors> {
+    let todo_id = todo_repo.resolve_id(&link_command_args.id)?;
+    let blocked_by = link_command_args
+        .blocked_by
+        .iter()
+        .map(|reference| todo_repo.resolve_id(reference))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((todo_id, blocked_by))
+}
+
+fn handle_link_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    link_command_args: &LinkCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    let linked = resolve_link(todo_repo, link_command_args)
+        .and_then(|(todo_id, blocked_by)| todo_repo.link_todos(&todo_id, &blocked_by));
+    match linked {
+        Ok(todo) => {
+            todo_printer.print_single_todo(todo, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Unable to link todos", e),
+    }
+}
+
+fn handle_unlink_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    unlink_command_args: &LinkCommandArgs,
     output_format: OutputFormat,
 ) -> AppResult {
-    match todo_repo.delete_todo(String::from(&delete_command_args.id)) {
+    let unlinked = resolve_link(todo_repo, unlink_command_args)
+        .and_then(|(todo_id, blocked_by)| todo_repo.unlink_todos(&todo_id, &blocked_by));
+    match unlinked {
         Ok(todo) => {
             todo_printer.print_single_todo(todo, output_format);
             AppResult::Success
         }
-        Err(e) => AppResult::Error(format!("Unable to delete todo: {}", e.error_message())),
+        Err(e) => AppResult::error("Unable to unlink todos", e),
+    }
+}
+
+fn handle_move_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    move_command_args: &MoveCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    let selection = match resolve_selection(todo_repo, &move_command_args.selection) {
+        Ok(selection) => selection,
+        Err(e) => return AppResult::error("Unable to move todo", e),
+    };
+    match todo_repo.move_todos(&selection, &move_command_args.to) {
+        Ok(report) => report_bulk_result(todo_printer, report, output_format, "move todo"),
+        Err(e) => AppResult::error("Unable to move todo", e),
+    }
+}
+
+fn handle_lists_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    lists_command_args: &ListsCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    if let Some(list) = &lists_command_args.set_default
+        && let Err(e) = todo_repo.set_default_list(list)
+    {
+        return AppResult::error("Unable to set the default list", e);
+    }
+    match todo_repo.list_summaries() {
+        Ok(lists) => {
+            todo_printer.print_lists(lists, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Error retrieving lists", e),
+    }
+}
+
+fn handle_tui_command(todo_repo: &mut Repository) -> AppResult {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return AppResult::error(
+            "Unable to start the terminal interface",
+            TodoErrors::Validation(String::from("stdin and stdout must be a terminal")),
+        );
+    }
+    match todo::tui::run(todo_repo) {
+        Ok(()) => AppResult::Success,
+        Err(e) => AppResult::error("Terminal interface failed", e),
+    }
+}
+
+fn handle_serve_command(
+    todo_repo: &mut Repository,
+    serve_command_args: &ServeCommandArgs,
+) -> AppResult {
+    let address = (serve_command_args.host.as_str(), serve_command_args.port);
+    let server = match tiny_http::Server::http(address) {
+        Ok(server) => server,
+        Err(e) => {
+            return AppResult::error("Unable to start server", TodoErrors::Io(e.to_string()));
+        }
+    };
+    // The bound address goes to stdout so `--port 0` can be used by scripts.
+    match server.server_addr().to_ip() {
+        Some(address) => println!("Listening on http://{}", address),
+        None => println!("Listening on {}", server.server_addr()),
+    }
+    todo::server::serve(todo_repo, &server);
+    AppResult::Success
+}
+
+fn handle_sync_command(
+    todo_printer: &mut TodoPrinter<Stdout>,
+    settings: &Settings,
+    sync_command_args: &SyncCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    // Only the JSON file can be merged todo by todo; a server keeps its clients in step itself.
+    if settings.remote.value.is_some() || settings.backend.value != StorageBackend::Json {
+        return AppResult::error(
+            "Unable to sync",
+            TodoErrors::Validation(String::from("sync needs a JSON data file")),
+        );
+    }
+    let report = GitSync::open(
+        std::path::Path::new(&settings.file.value),
+        &sync_command_args.git_remote,
+    )
+    .and_then(|git_sync| git_sync.sync(&sync_command_args.message));
+    match report {
+        Ok(report) => {
+            todo_printer.print_sync_report(report, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Unable to sync", e),
+    }
+}
+
+fn handle_graph_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    graph_command_args: &GraphCommandArgs,
+) -> AppResult {
+    match todo_repo.get_all_todos() {
+        Ok(todos) => {
+            todo_printer.print_graph(&todos, graph_command_args.format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Error retrieving todos", e),
+    }
+}
+
+fn handle_export_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    export_command_args: &ExportCommandArgs,
+) -> AppResult {
+    let exported = todo_repo
+        .get_all_todos()
+        .and_then(|todos| export_todos(&todos, export_command_args.format));
+    match exported {
+        Ok(output) => {
+            todo_printer.print_exported(&output);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Unable to export todos", e),
+    }
+}
+
+fn handle_import_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    import_command_args: &ImportCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    let input = match &import_command_args.input {
+        Some(file_path) => std::fs::read_to_string(file_path).map_err(TodoErrors::from),
+        None => read_stdin("--input or todos"),
+    };
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => return AppResult::error("Unable to read import input", e),
+    };
+    let imported = import_todos(&input, import_command_args.format)
+        .and_then(|todos| todo_repo.import_todos(todos, import_command_args.dry_run));
+    match imported {
+        Ok(todos) => {
+            todo_printer.print_list_todo(todos, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Unable to import todos", e),
+    }
+}
+
+fn handle_undo_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    undo_command_args: &UndoCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    match todo_repo.undo(undo_command_args.count) {
+        Ok(undone) => {
+            todo_printer.print_history(undone, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Unable to undo", e),
+    }
+}
+
+fn handle_redo_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    redo_command_args: &UndoCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    match todo_repo.redo(redo_command_args.count) {
+        Ok(redone) => {
+            todo_printer.print_history(redone, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Unable to redo", e),
+    }
+}
+
+fn handle_history_command(
+    todo_repo: &mut Repository,
+    todo_printer: &mut TodoPrinter<Stdout>,
+    history_command_args: &HistoryCommandArgs,
+    output_format: OutputFormat,
+) -> AppResult {
+    match todo_repo.history(history_command_args.limit) {
+        Ok(history) => {
+            todo_printer.print_history(history, output_format);
+            AppResult::Success
+        }
+        Err(e) => AppResult::error("Error retrieving history", e),
+    }
+}
+
+fn handle_config_command(
+    todo_printer: &mut TodoPrinter<Stdout>,
+    config_command: &ConfigCommand,
+    config_path: Option<PathBuf>,
+    mut config: Config,
+    settings: &Settings,
+    output_format: OutputFormat,
+) -> AppResult {
+    let Some(config_path) = config_path else {
+        return AppResult::error(
+            "Unable to find config file",
+            TodoErrors::Io(String::from(
+                "Could not find home directory, set XDG_CONFIG_HOME",
+            )),
+        );
+    };
+    match config_command {
+        ConfigCommand::Get { key } => {
+            todo_printer.print_setting(settings.entry(*key), output_format);
+            AppResult::Success
+        }
+        ConfigCommand::Show => {
+            todo_printer.print_settings(settings.entries(), output_format);
+            AppResult::Success
+        }
+        ConfigCommand::Set { key, value } => {
+            let saved = config
+                .set(*key, value)
+                .and_then(|value| config.save(&config_path).map(|_| value));
+            match saved {
+                Ok(value) => {
+                    let entry = SettingEntry {
+                        key: key.as_str(),
+                        value,
+                        source: SettingSource::Config,
+                    };
+                    todo_printer.print_setting(entry, output_format);
+                    AppResult::Success
+                }
+                Err(e) => AppResult::error("Unable to change config", e),
+            }
+        }
+        ConfigCommand::Unset { key } => {
+            config.unset(*key);
+            match config.save(&config_path) {
+                Ok(()) => AppResult::Success,
+                Err(e) => AppResult::error("Unable to change config", e),
+            }
+        }
     }
 }
@@ -0,0 +1,92 @@
+use crate::todo_repo::{Todo, open_blockers};
+use std::collections::HashSet;
+
+/// Renders the dependencies among `todos` as a Graphviz digraph, with an edge from each
+/// blocker to the todo waiting on it. Only todos with a link to another listed todo are
+/// drawn; done ones are filled green and blocked ones outlined red.
+pub fn render_dot(todos: &[Todo]) -> String {
+    let listed: HashSet<&str> = todos.iter().map(|todo| todo.id.as_str()).collect();
+    let edges: Vec<(&str, &str)> = todos
+        .iter()
+        .flat_map(|todo| {
+            todo.blocked_by
+                .iter()
+                .filter(|blocker_id| listed.contains(blocker_id.as_str()))
+                .map(move |blocker_id| (blocker_id.as_str(), todo.id.as_str()))
+        })
+        .collect();
+    let linked: HashSet<&str> = edges.iter().flat_map(|(from, to)| [*from, *to]).collect();
+
+    let mut lines = vec![
+        String::from("digraph todos {"),
+        String::from("  rankdir=LR;"),
+        String::from("  node [shape=box];"),
+    ];
+    for todo in todos
+        .iter()
+        .filter(|todo| linked.contains(todo.id.as_str()))
+    {
+        let style = if todo.completed {
+            ", style=filled, fillcolor=palegreen"
+        } else if !open_blockers(todo, todos).is_empty() {
+            ", color=red"
+        } else {
+            ""
+        };
+        lines.push(format!(
+            "  {} [label={}{}];",
+            quote(&todo.id),
+            quote(&todo.name),
+            style
+        ));
+    }
+    for (from, to) in edges {
+        lines.push(format!("  {} -> {};", quote(from), quote(to)));
+    }
+    lines.push(String::from("}"));
+    lines.join("\n")
+}
+
+fn quote(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: &str, name: &str, blocked_by: &[&str]) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(name),
+            blocked_by: blocked_by.iter().map(|id| String::from(*id)).collect(),
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn should_render_linked_todos_with_edges_from_blocker_to_dependent() {
+        let mut todos = vec![
+            todo("a", "Buy \"oat\" milk", &[]),
+            todo("b", "Bake", &["a", "gone"]),
+            todo("c", "Alone", &[]),
+            todo("d", "Serve", &["b"]),
+        ];
+        todos[0].completed = true;
+
+        assert_eq!(
+            "digraph todos {\n  rankdir=LR;\n  node [shape=box];\n  \
+             \"a\" [label=\"Buy \\\"oat\\\" milk\", style=filled, fillcolor=palegreen];\n  \
+             \"b\" [label=\"Bake\"];\n  \
+             \"d\" [label=\"Serve\", color=red];\n  \
+             \"a\" -> \"b\";\n  \"b\" -> \"d\";\n}",
+            render_dot(&todos)
+        );
+    }
+}
@@ -1,55 +1,530 @@
-use crate::cli::OutputFormat;
-use crate::todo_repo::Todo;
-use serde_json::to_string_pretty;
+use crate::cli::{GraphFormat, OutputFormat};
+use crate::config::{DEFAULT_DATE_FORMAT, SettingEntry};
+use crate::exchange::{ExchangeFormat, export_todos};
+use crate::sync::SyncReport;
+use crate::todo_repo::{
+    BulkReport, HistoryEntry, ListSummary, MergeSide, SearchField, SearchHit, SubtaskProgress, Todo,
+};
+use chrono::Local;
+use serde::Serialize;
+use serde_json::{to_string, to_string_pretty};
+use std::collections::HashMap;
 use std::io::Write;
 
+mod graph;
+mod table;
+mod template;
+mod tree;
+
+pub use table::TableStyle;
+pub use template::TodoTemplate;
+
+const HIGHLIGHT: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
 pub struct TodoPrinter<W: Write> {
     writer: W,
+    table_style: TableStyle,
+    template: Option<TodoTemplate>,
+    subtask_progress: HashMap<String, SubtaskProgress>,
+    positions: HashMap<String, usize>,
+    date_format: String,
 }
 
 impl<W: Write> TodoPrinter<W> {
     pub fn new(writer: W) -> Self {
-        Self { writer }
+        Self {
+            writer,
+            table_style: TableStyle::default(),
+            template: None,
+            subtask_progress: HashMap::new(),
+            positions: HashMap::new(),
+            date_format: String::from(DEFAULT_DATE_FORMAT),
+        }
+    }
+
+    /// Sets the width and coloring used by the table format.
+    pub fn with_table_style(mut self, table_style: TableStyle) -> Self {
+        self.table_style = table_style;
+        self
+    }
+
+    /// Sets the strftime format of dates in the text and table formats.
+    pub fn with_date_format(mut self, date_format: String) -> Self {
+        self.date_format = date_format;
+        self
+    }
+
+    /// Sets the line format used by the template format.
+    pub fn with_template(mut self, template: TodoTemplate) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Sets the subtask counts shown next to parent todos in the text format. They are kept
+    /// apart from the printed list so a filtered list still counts every subtask.
+    pub fn set_subtask_progress(&mut self, subtask_progress: HashMap<String, SubtaskProgress>) {
+        self.subtask_progress = subtask_progress;
+    }
+
+    /// Sets the 1-based positions shown in the `#` column of the table format, which commands
+    /// accept in place of an id. Only the full list sets them, as a filtered list numbers
+    /// differently.
+    pub fn set_positions(&mut self, positions: HashMap<String, usize>) {
+        self.positions = positions;
     }
 
     pub fn print_single_todo(&mut self, todo: Todo, format: OutputFormat) {
         let output_str = match format {
-            OutputFormat::Text => Self::get_todo_text_format(todo),
             OutputFormat::Json => to_string_pretty(&todo).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&todo),
+            _ => self.format_todo_lines(&[todo], format),
         };
         writeln!(self.writer, "{}", output_str).unwrap();
     }
 
     pub fn print_list_todo(&mut self, todo_list: Vec<Todo>, format: OutputFormat) {
         if todo_list.is_empty() {
-            writeln!(self.writer, "No Todos found").unwrap();
+            // Line-oriented formats print nothing so a pipe sees no records rather than a message.
+            if !Self::is_line_format(&format) {
+                writeln!(self.writer, "No Todos found").unwrap();
+            }
             return;
         }
         let output_str = match format {
-            OutputFormat::Text => todo_list
-                .into_iter()
-                .map(|todo| Self::get_todo_text_format(todo))
+            OutputFormat::Json => to_string_pretty(&todo_list).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&todo_list),
+            _ => self.format_todo_lines(&todo_list, format),
+        };
+        writeln!(self.writer, "{}", output_str).unwrap();
+    }
+
+    /// Prints search results in rank order. Text output marks the matched words, in color when
+    /// the table style allows it and in brackets otherwise; JSON and YAML include the scores.
+    pub fn print_search_hits(&mut self, hits: Vec<SearchHit>, format: OutputFormat) {
+        if hits.is_empty() {
+            if !Self::is_line_format(&format) {
+                writeln!(self.writer, "No Todos found").unwrap();
+            }
+            return;
+        }
+        let output_str = match format {
+            OutputFormat::Json => to_string_pretty(&hits).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&hits),
+            OutputFormat::Ndjson => hits
+                .iter()
+                .map(|hit| to_string(hit).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            OutputFormat::Text => hits
+                .iter()
+                .map(|hit| self.get_search_hit_text_format(hit))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Template => {
+                let todos = hits.into_iter().map(|hit| hit.todo).collect::<Vec<_>>();
+                self.format_todo_lines(&todos, format)
+            }
+        };
+        writeln!(self.writer, "{}", output_str).unwrap();
+    }
+
+    /// Prints the changed todos followed by a summary line, or the whole report as JSON or YAML.
+    /// Line-oriented formats print only the changed todos; misses are reported on stderr.
+    pub fn print_bulk_report(&mut self, report: BulkReport, format: OutputFormat) {
+        let output_str = match format {
+            OutputFormat::Text | OutputFormat::Table => {
+                let mut lines = Vec::new();
+                if !report.succeeded.is_empty() {
+                    lines.push(self.format_todo_lines(&report.succeeded, format));
+                }
+                let mut summary = format!(
+                    "{} succeeded, {} not found",
+                    report.succeeded.len(),
+                    report.not_found.len()
+                );
+                if !report.not_found.is_empty() {
+                    summary.push_str(&format!(": {}", report.not_found.join(",")));
+                }
+                if !report.created.is_empty() {
+                    summary.push_str(&format!(
+                        "\n{} next occurrence(s) created",
+                        report.created.len()
+                    ));
+                }
+                lines.push(summary);
+                if !report.created.is_empty() {
+                    lines.push(self.format_todo_lines(&report.created, format));
+                }
+                lines.join("\n")
+            }
+            OutputFormat::Json => to_string_pretty(&report).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&report),
+            OutputFormat::Ndjson | OutputFormat::Csv | OutputFormat::Template => {
+                if report.succeeded.is_empty() {
+                    return;
+                }
+                self.format_todo_lines(&report.succeeded, format)
+            }
+        };
+        writeln!(self.writer, "{}", output_str).unwrap();
+    }
+
+    pub fn print_history(&mut self, history: Vec<HistoryEntry>, format: OutputFormat) {
+        if history.is_empty() {
+            if !Self::is_line_format(&format) {
+                writeln!(self.writer, "No history found").unwrap();
+            }
+            return;
+        }
+        let output_str = match format {
+            OutputFormat::Json => to_string_pretty(&history).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&history),
+            OutputFormat::Ndjson => history
+                .iter()
+                .map(|entry| to_string(entry).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            // History has no table layout of its own and reuses the text lines; CSV and template
+            // output are refused before the command runs.
+            OutputFormat::Text
+            | OutputFormat::Table
+            | OutputFormat::Csv
+            | OutputFormat::Template => history
+                .iter()
+                .map(Self::get_history_text_format)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        writeln!(self.writer, "{}", output_str).unwrap();
+    }
+
+    pub fn print_lists(&mut self, lists: Vec<ListSummary>, format: OutputFormat) {
+        let output_str = match format {
+            OutputFormat::Json => to_string_pretty(&lists).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&lists),
+            OutputFormat::Ndjson => lists
+                .iter()
+                .map(|list| to_string(list).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            // Lists have no table layout of their own and reuse the text lines; CSV and template
+            // output are refused before the command runs.
+            OutputFormat::Text
+            | OutputFormat::Table
+            | OutputFormat::Csv
+            | OutputFormat::Template => lists
+                .iter()
+                .map(Self::get_list_text_format)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        writeln!(self.writer, "{}", output_str).unwrap();
+    }
+
+    /// Prints one line per step `sync` took, followed by one line per merge conflict.
+    pub fn print_sync_report(&mut self, report: SyncReport, format: OutputFormat) {
+        let output_str = match format {
+            OutputFormat::Json => to_string_pretty(&report).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&report),
+            OutputFormat::Ndjson => to_string(&report).unwrap(),
+            // CSV and template output are refused before the command runs.
+            OutputFormat::Text
+            | OutputFormat::Table
+            | OutputFormat::Csv
+            | OutputFormat::Template => Self::get_sync_text_format(&report),
+        };
+        writeln!(self.writer, "{}", output_str).unwrap();
+    }
+
+    /// Prints the value alone in line-based formats, so scripts can use it as is.
+    pub fn print_setting(&mut self, entry: SettingEntry, format: OutputFormat) {
+        let output_str = match format {
+            OutputFormat::Json => to_string_pretty(&entry).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&entry),
+            OutputFormat::Ndjson => to_string(&entry).unwrap(),
+            OutputFormat::Text
+            | OutputFormat::Table
+            | OutputFormat::Csv
+            | OutputFormat::Template => entry.value,
+        };
+        writeln!(self.writer, "{}", output_str).unwrap();
+    }
+
+    pub fn print_settings(&mut self, entries: Vec<SettingEntry>, format: OutputFormat) {
+        let output_str = match format {
+            OutputFormat::Json => to_string_pretty(&entries).unwrap(),
+            OutputFormat::Yaml => Self::to_yaml(&entries),
+            OutputFormat::Ndjson => entries
+                .iter()
+                .map(|entry| to_string(entry).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            // Settings have no table layout of their own and reuse the text lines; CSV and template
+            // output are refused before the command runs.
+            OutputFormat::Text
+            | OutputFormat::Table
+            | OutputFormat::Csv
+            | OutputFormat::Template => entries
+                .iter()
+                .map(|entry| format!("{:<12} {} ({})", entry.key, entry.value, entry.source))
                 .collect::<Vec<_>>()
                 .join("\n"),
-            OutputFormat::Json => to_string_pretty(&todo_list).unwrap(),
         };
         writeln!(self.writer, "{}", output_str).unwrap();
     }
 
-    fn first_10_chars(s: &String) -> String {
+    pub fn print_graph(&mut self, todos: &[Todo], format: GraphFormat) {
+        let output_str = match format {
+            GraphFormat::Dot => graph::render_dot(todos),
+        };
+        writeln!(self.writer, "{}", output_str).unwrap();
+    }
+
+    /// Prints exported todos as the exchange format laid them out, without adding a line break.
+    pub fn print_exported(&mut self, exported: &str) {
+        write!(self.writer, "{}", exported).unwrap();
+    }
+
+    fn is_line_format(format: &OutputFormat) -> bool {
+        matches!(
+            format,
+            OutputFormat::Ndjson | OutputFormat::Csv | OutputFormat::Template
+        )
+    }
+
+    // Formats that print a list the same way regardless of how many todos it holds.
+    fn format_todo_lines(&self, todos: &[Todo], format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Table => {
+                table::render_table(todos, &self.positions, self.table_style, &self.date_format)
+            }
+            OutputFormat::Ndjson => todos
+                .iter()
+                .map(|todo| to_string(todo).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            OutputFormat::Csv => export_todos(todos, ExchangeFormat::Csv)
+                .unwrap()
+                .trim_end()
+                .to_string(),
+            OutputFormat::Template => match &self.template {
+                Some(template) => todos
+                    .iter()
+                    .map(|todo| template.render(todo))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                None => self.format_todo_lines(todos, OutputFormat::Text),
+            },
+            OutputFormat::Text | OutputFormat::Json | OutputFormat::Yaml => tree::tree_order(todos)
+                .into_iter()
+                .map(|(todo, guide)| {
+                    format!(
+                        "{}{}",
+                        guide,
+                        self.get_todo_text_format(todo, guide.is_empty())
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    fn to_yaml<T: Serialize + ?Sized>(value: &T) -> String {
+        serde_yaml::to_string(value).unwrap().trim_end().to_string()
+    }
+
+    fn get_history_text_format(entry: &HistoryEntry) -> String {
+        // expected format
+        // #seq timestamp operation ids [undone]
+        let todo_ids = entry
+            .changes
+            .iter()
+            .map(|change| change.todo_id())
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut line = format!(
+            "{:<5} {} {:<8} {}",
+            format!("#{}", entry.seq),
+            entry
+                .timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            entry.operation,
+            todo_ids
+        );
+        if entry.undone {
+            line.push_str(" undone");
+        }
+        line
+    }
+
+    fn get_list_text_format(list: &ListSummary) -> String {
+        // expected format
+        // name pending=N done=N [default]
+        let mut line = format!(
+            "{:<10} pending={} done={}",
+            list.name, list.pending, list.done
+        );
+        if list.default {
+            line.push_str(" default");
+        }
+        line
+    }
+
+    fn get_search_hit_text_format(&self, hit: &SearchHit) -> String {
+        // expected format
+        // id done name description [tags=..] score=N
+        let highlight = |field: SearchField, tag: Option<usize>, text: &str| {
+            let ranges = hit
+                .matches
+                .iter()
+                .filter(|m| m.field == field && m.tag == tag)
+                .map(|m| (m.start, m.end));
+            self.highlight(text, ranges)
+        };
+        let todo = &hit.todo;
+        let mut line = format!(
+            "{:<8} {:<5} {} {}",
+            todo.id,
+            todo.completed,
+            highlight(SearchField::Name, None, &todo.name),
+            highlight(SearchField::Description, None, &todo.description)
+        );
+        if !todo.tags.is_empty() {
+            let tags = todo
+                .tags
+                .iter()
+                .enumerate()
+                .map(|(index, tag)| highlight(SearchField::Tags, Some(index), tag))
+                .collect::<Vec<_>>()
+                .join(",");
+            line.push_str(&format!(" tags={}", tags));
+        }
+        line.push_str(&format!(" score={:.2}", hit.score));
+        line
+    }
+
+    /// Marks the byte `ranges` of `text`, which are sorted and may overlap.
+    fn highlight(&self, text: &str, ranges: impl Iterator<Item = (usize, usize)>) -> String {
+        let (open, close) = match self.table_style.color {
+            true => (HIGHLIGHT, RESET),
+            false => ("[", "]"),
+        };
+        let mut marked = String::new();
+        let mut written = 0;
+        for (start, end) in ranges {
+            if end <= written {
+                continue;
+            }
+            let start = start.max(written);
+            marked.push_str(&text[written..start]);
+            marked.push_str(open);
+            marked.push_str(&text[start..end]);
+            marked.push_str(close);
+            written = end;
+        }
+        marked.push_str(&text[written..]);
+        marked
+    }
+
+    fn get_sync_text_format(report: &SyncReport) -> String {
+        let upstream = format!("{}/{}", report.remote, report.branch);
+        let mut lines = Vec::new();
+        if report.committed {
+            lines.push(String::from("Committed local changes"));
+        }
+        if report.fast_forwarded {
+            lines.push(format!("Fast-forwarded to {}", upstream));
+        }
+        if report.merged {
+            lines.push(format!(
+                "Merged {} with {} conflict(s)",
+                upstream,
+                report.conflicts.len()
+            ));
+        }
+        // expected format
+        // conflict id field: kept ours|theirs value over value
+        for conflict in &report.conflicts {
+            let (side, kept, other) = match conflict.kept {
+                MergeSide::Ours => ("ours", &conflict.ours, &conflict.theirs),
+                MergeSide::Theirs => ("theirs", &conflict.theirs, &conflict.ours),
+            };
+            lines.push(format!(
+                "conflict {} {}: kept {} {} over {}",
+                conflict.id, conflict.field, side, kept, other
+            ));
+        }
+        if report.pushed {
+            lines.push(format!("Pushed to {}", upstream));
+        }
+        if lines.is_empty() {
+            lines.push(format!("Already in sync with {}", upstream));
+        }
+        lines.join("\n")
+    }
+
+    fn first_10_chars(s: &str) -> String {
         s.chars().take(10).collect()
     }
 
-    fn get_todo_text_format(todo: Todo) -> String {
+    // Subtasks listed under their parent are drawn as a tree; `top_level` lines name their parent.
+    fn get_todo_text_format(&self, todo: &Todo, top_level: bool) -> String {
         // expected format
-        // id done title description
-        format!(
+        // id done title description [due=.. priority=.. tags=..]
+        let mut line = format!(
             "{:<8} {:<5} {:<12} {}",
             todo.id,
             todo.completed,
             Self::first_10_chars(&todo.name),
             todo.description
-        )
+        );
+        for (key, value) in self.get_todo_attributes(todo) {
+            line.push_str(&format!(" {}={}", key, value));
+        }
+        if let Some(parent_id) = todo.parent_id.as_ref().filter(|_| top_level) {
+            line.push_str(&format!(" parent={}", parent_id));
+        }
+        if let Some(progress) = self.subtask_progress.get(&todo.id) {
+            line.push_str(&format!(" subtasks={}", progress));
+        }
+        line
+    }
+
+    // Optional attributes are appended as key=value pairs so the leading columns stay stable.
+    fn get_todo_attributes(&self, todo: &Todo) -> Vec<(&'static str, String)> {
+        let mut attributes = Vec::new();
+        if let Some(due) = todo.due {
+            attributes.push((
+                "due",
+                due.with_timezone(&Local)
+                    .format(&self.date_format)
+                    .to_string(),
+            ));
+        }
+        if let Some(priority) = todo.priority {
+            attributes.push(("priority", String::from(priority.as_str())));
+        }
+        if !todo.tags.is_empty() {
+            attributes.push(("tags", todo.tags.join(",")));
+        }
+        if let Some(recurrence) = &todo.recurrence {
+            attributes.push(("repeat", recurrence.to_string()));
+        }
+        if !todo.blocked_by.is_empty() {
+            attributes.push(("blocked_by", todo.blocked_by.join(",")));
+        }
+        if let Some(deleted_at) = todo.deleted_at {
+            attributes.push((
+                "deleted",
+                deleted_at
+                    .with_timezone(&Local)
+                    .format(&self.date_format)
+                    .to_string(),
+            ));
+        }
+        attributes
     }
 
     #[cfg(test)]
@@ -61,7 +536,10 @@ impl<W: Write> TodoPrinter<W> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::todo_repo::{MergeConflict, Priority, SearchMatch, TodoChange};
+    use chrono::TimeZone;
     use nanoid::nanoid;
+    use serde_json::json;
     use std::io::Cursor;
 
     const ID_LENGTH: usize = 7;
@@ -72,6 +550,7 @@ mod tests {
             name: String::from("First Todo Long Title"),
             description: String::from("First Todo very long description"),
             completed: false,
+            ..Todo::default()
         }
     }
 
@@ -82,18 +561,21 @@ mod tests {
                 name: String::from("First Todo"),
                 description: String::from("First Todo description"),
                 completed: false,
+                ..Todo::default()
             },
             Todo {
                 id: nanoid!(ID_LENGTH),
                 name: String::from("Second Todo"),
                 description: String::from("First Todo description"),
                 completed: false,
+                ..Todo::default()
             },
             Todo {
                 id: nanoid!(ID_LENGTH),
                 name: String::from("Third Todo"),
                 description: String::from("First Todo description"),
                 completed: false,
+                ..Todo::default()
             },
         ]
     }
@@ -102,7 +584,7 @@ mod tests {
         Cursor::new(Vec::<u8>::new())
     }
 
-    fn first_10_chars(s: &String) -> String {
+    fn first_10_chars(s: &str) -> String {
         s.chars().take(10).collect()
     }
 
@@ -163,7 +645,7 @@ mod tests {
 
         let list_output = list_todo
             .into_iter()
-            .map(|todo| get_expected_text_format(todo))
+            .map(get_expected_text_format)
             .collect::<Vec<_>>()
             .join("\n");
         let expected_output = format!("{}\n", list_output);
@@ -186,4 +668,357 @@ mod tests {
 
         assert_eq!(expected_output, output_str);
     }
+
+    #[test]
+    fn should_append_due_priority_and_tags_in_text_format() {
+        let output_cur = setup();
+        let mut printer = TodoPrinter::new(output_cur);
+        let due = Local.with_ymd_and_hms(2026, 10, 20, 17, 30, 0).unwrap();
+        let single_todo = Todo {
+            due: Some(due.with_timezone(&chrono::Utc)),
+            priority: Some(Priority::High),
+            tags: vec![String::from("work"), String::from("home")],
+            ..get_todo_single()
+        };
+        printer.print_single_todo(single_todo.clone(), OutputFormat::Text);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        let expected_output = format!(
+            "{} due=2026-10-20 17:30 priority=high tags=work,home\n",
+            get_expected_text_format(single_todo)
+        );
+        assert_eq!(expected_output, output_str);
+    }
+
+    #[test]
+    fn should_print_dates_with_configured_format() {
+        let output_cur = setup();
+        let mut printer = TodoPrinter::new(output_cur).with_date_format(String::from("%d.%m.%Y"));
+        let due = Local.with_ymd_and_hms(2026, 10, 20, 17, 30, 0).unwrap();
+        let single_todo = Todo {
+            due: Some(due.with_timezone(&chrono::Utc)),
+            ..get_todo_single()
+        };
+        printer.print_single_todo(single_todo.clone(), OutputFormat::Text);
+        printer.print_single_todo(single_todo, OutputFormat::Table);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        assert_eq!(2, output_str.matches("20.10.2026").count());
+    }
+
+    #[test]
+    fn should_print_history_with_undone_marker_in_text_format() {
+        let output_cur = setup();
+        let mut printer = TodoPrinter::new(output_cur);
+        let timestamp = Local.with_ymd_and_hms(2026, 10, 20, 17, 30, 5).unwrap();
+        let todo = get_todo_single();
+        let entry = HistoryEntry {
+            seq: 3,
+            timestamp: timestamp.with_timezone(&chrono::Utc),
+            operation: String::from("delete"),
+            changes: vec![TodoChange {
+                before: Some(todo.clone()),
+                after: None,
+            }],
+            undone: true,
+        };
+        printer.print_history(vec![entry], OutputFormat::Text);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        let expected_output = format!("#3    2026-10-20 17:30:05 delete   {} undone\n", todo.id);
+        assert_eq!(expected_output, output_str);
+    }
+
+    #[test]
+    fn should_print_lists_with_counts_and_default_marker_in_text_format() {
+        let output_cur = setup();
+        let mut printer = TodoPrinter::new(output_cur);
+        let lists = vec![
+            ListSummary {
+                name: String::from("inbox"),
+                pending: 2,
+                done: 1,
+                default: true,
+            },
+            ListSummary {
+                name: String::from("work"),
+                pending: 0,
+                done: 4,
+                default: false,
+            },
+        ];
+        printer.print_lists(lists, OutputFormat::Text);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        let expected_output = "inbox      pending=2 done=1 default\nwork       pending=0 done=4\n";
+        assert_eq!(expected_output, output_str);
+    }
+
+    #[test]
+    fn should_print_search_hits_with_marked_matches_in_text_format() {
+        let todo = Todo {
+            id: String::from("abc1234"),
+            name: String::from("Oat milk"),
+            description: String::from("two milk cartons"),
+            tags: vec![String::from("shop"), String::from("milky")],
+            ..Todo::default()
+        };
+        let matches = [
+            (SearchField::Name, None, 4, 8),
+            (SearchField::Description, None, 4, 8),
+            (SearchField::Tags, Some(1), 0, 5),
+        ];
+        let hit = SearchHit {
+            score: 4.6,
+            todo,
+            matches: matches
+                .into_iter()
+                .map(|(field, tag, start, end)| SearchMatch {
+                    field,
+                    tag,
+                    start,
+                    end,
+                })
+                .collect(),
+        };
+
+        let mut printer = TodoPrinter::new(setup());
+        printer.print_search_hits(vec![hit.clone()], OutputFormat::Text);
+        let output_str = String::from_utf8(printer.into_writer().into_inner()).unwrap();
+        assert_eq!(
+            "abc1234  false Oat [milk] two [milk] cartons tags=shop,[milky] score=4.60\n",
+            output_str
+        );
+
+        let mut printer = TodoPrinter::new(setup()).with_table_style(TableStyle {
+            width: None,
+            color: true,
+        });
+        printer.print_search_hits(vec![hit], OutputFormat::Text);
+        let output_str = String::from_utf8(printer.into_writer().into_inner()).unwrap();
+        assert!(output_str.starts_with("abc1234  false Oat \x1b[1;33mmilk\x1b[0m two"));
+
+        let mut printer = TodoPrinter::new(setup());
+        printer.print_search_hits(Vec::new(), OutputFormat::Ndjson);
+        assert!(printer.into_writer().into_inner().is_empty());
+    }
+
+    #[test]
+    fn should_print_sync_steps_and_conflicts_in_text_format() {
+        let output_cur = setup();
+        let mut printer = TodoPrinter::new(output_cur);
+        let report = SyncReport {
+            remote: String::from("origin"),
+            branch: String::from("main"),
+            committed: true,
+            merged: true,
+            pushed: true,
+            conflicts: vec![MergeConflict {
+                id: String::from("abc1234"),
+                name: String::from("Milk"),
+                field: String::from("name"),
+                ours: json!("Whole milk"),
+                theirs: json!("Oat milk"),
+                kept: MergeSide::Theirs,
+            }],
+            ..SyncReport::default()
+        };
+        printer.print_sync_report(report, OutputFormat::Text);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        let expected_output = "Committed local changes\n\
+            Merged origin/main with 1 conflict(s)\n\
+            conflict abc1234 name: kept theirs \"Oat milk\" over \"Whole milk\"\n\
+            Pushed to origin/main\n";
+        assert_eq!(expected_output, output_str);
+
+        let mut printer = TodoPrinter::new(setup());
+        printer.print_sync_report(
+            SyncReport {
+                remote: String::from("origin"),
+                branch: String::from("main"),
+                ..SyncReport::default()
+            },
+            OutputFormat::Text,
+        );
+        let output_str = String::from_utf8(printer.into_writer().into_inner()).unwrap();
+        assert_eq!("Already in sync with origin/main\n", output_str);
+    }
+
+    #[test]
+    fn should_print_bulk_report_with_summary_in_text_format() {
+        let output_cur = setup();
+        let mut printer = TodoPrinter::new(output_cur);
+        let todo = get_todo_single();
+        let next = Todo {
+            id: nanoid!(),
+            ..todo.clone()
+        };
+        let report = BulkReport {
+            succeeded: vec![todo.clone()],
+            not_found: vec![String::from("abc"), String::from("def")],
+            created: vec![next.clone()],
+        };
+        printer.print_bulk_report(report, OutputFormat::Text);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        let expected_output = format!(
+            "{}\n1 succeeded, 2 not found: abc,def\n1 next occurrence(s) created\n{}\n",
+            get_expected_text_format(todo),
+            get_expected_text_format(next)
+        );
+        assert_eq!(expected_output, output_str);
+    }
+
+    #[test]
+    fn should_print_list_of_todos_in_ndjson_format() {
+        let output_cur = setup();
+        let mut printer = TodoPrinter::new(output_cur);
+        let list_todo = get_todo_list();
+        printer.print_list_todo(list_todo.clone(), OutputFormat::Ndjson);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        let parsed: Vec<Todo> = output_str
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(list_todo, parsed);
+    }
+
+    #[test]
+    fn should_print_single_todo_in_csv_format_with_header() {
+        let output_cur = setup();
+        let mut printer = TodoPrinter::new(output_cur);
+        let single_todo = Todo {
+            tags: vec![String::from("work"), String::from("home")],
+            ..get_todo_single()
+        };
+        printer.print_single_todo(single_todo.clone(), OutputFormat::Csv);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        let expected_output = format!(
+            "id,name,description,completed,due,priority,tags,created_at,updated_at,completed_at,recurrence,parent_id,blocked_by\n\
+             {},First Todo Long Title,First Todo very long description,false,,,\"work,home\",,,,,,\n",
+            single_todo.id
+        );
+        assert_eq!(expected_output, output_str);
+    }
+
+    #[test]
+    fn should_print_single_todo_and_list_in_yaml_format() {
+        let single_todo = get_todo_single();
+        let mut printer = TodoPrinter::new(setup());
+        printer.print_single_todo(single_todo.clone(), OutputFormat::Yaml);
+        let output_str = String::from_utf8(printer.into_writer().into_inner()).unwrap();
+        assert!(output_str.starts_with(&format!(
+            "id: {}\nname: First Todo Long Title\n",
+            single_todo.id
+        )));
+        assert_eq!(
+            single_todo,
+            serde_yaml::from_str::<Todo>(&output_str).unwrap()
+        );
+
+        let list_todo = get_todo_list();
+        let mut printer = TodoPrinter::new(setup());
+        printer.print_list_todo(list_todo.clone(), OutputFormat::Yaml);
+        let output_str = String::from_utf8(printer.into_writer().into_inner()).unwrap();
+        assert_eq!(
+            list_todo,
+            serde_yaml::from_str::<Vec<Todo>>(&output_str).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_print_list_of_todos_with_template() {
+        let template = TodoTemplate::parse(r"{id}\t{name}").unwrap();
+        let mut printer = TodoPrinter::new(setup()).with_template(template);
+        let list_todo = get_todo_list();
+        printer.print_list_todo(list_todo.clone(), OutputFormat::Template);
+
+        let output_bytes = printer.into_writer().into_inner();
+        let output_str = String::from_utf8(output_bytes).unwrap();
+
+        let expected_output = list_todo
+            .iter()
+            .map(|todo| format!("{}\t{}\n", todo.id, todo.name))
+            .collect::<String>();
+        assert_eq!(expected_output, output_str);
+    }
+
+    #[test]
+    fn should_print_nothing_for_empty_list_in_line_formats() {
+        for format in [OutputFormat::Ndjson, OutputFormat::Csv] {
+            let mut printer = TodoPrinter::new(setup());
+            printer.print_list_todo(Vec::new(), format);
+            assert!(printer.into_writer().into_inner().is_empty());
+        }
+    }
+
+    #[test]
+    fn should_print_exported_todos_unchanged() {
+        let mut printer = TodoPrinter::new(setup());
+        printer.print_exported("- [ ] Milk\n- [x] Bread\n");
+        let output_str = String::from_utf8(printer.into_writer().into_inner()).unwrap();
+        assert_eq!("- [ ] Milk\n- [x] Bread\n", output_str);
+    }
+
+    #[test]
+    fn should_print_subtasks_as_tree_with_progress_in_text_format() {
+        let mut todos = get_todo_list();
+        todos[0].parent_id = Some(todos[2].id.clone());
+        todos[0].completed = true;
+        todos[1].parent_id = Some(nanoid!(ID_LENGTH));
+        let mut printer = TodoPrinter::new(setup());
+        printer.set_subtask_progress(crate::todo_repo::subtask_progress(&todos));
+        printer.print_list_todo(todos.clone(), OutputFormat::Text);
+
+        let output_str = String::from_utf8(printer.into_writer().into_inner()).unwrap();
+
+        let expected_output = format!(
+            "{} parent={}\n{} subtasks=1/1\n└─ {}\n",
+            get_expected_text_format(todos[1].clone()),
+            todos[1].parent_id.as_ref().unwrap(),
+            get_expected_text_format(todos[2].clone()),
+            get_expected_text_format(todos[0].clone())
+        );
+        assert_eq!(expected_output, output_str);
+    }
+
+    #[test]
+    fn should_print_positions_in_table_format_only() {
+        let todos = get_todo_list();
+        let mut printer = TodoPrinter::new(setup());
+        printer.set_positions(HashMap::from([(todos[1].id.clone(), 2)]));
+        printer.print_list_todo(todos[..2].to_vec(), OutputFormat::Text);
+        printer.print_list_todo(todos[..2].to_vec(), OutputFormat::Table);
+
+        let output_str = String::from_utf8(printer.into_writer().into_inner()).unwrap();
+
+        let text_output = format!(
+            "{}\n{}\n",
+            get_expected_text_format(todos[0].clone()),
+            get_expected_text_format(todos[1].clone())
+        );
+        assert!(output_str.starts_with(&text_output));
+        let table_lines: Vec<&str> = output_str[text_output.len()..].lines().collect();
+        assert!(table_lines[0].starts_with("#  ID"));
+        assert!(table_lines[2].starts_with(&format!("2  {}", todos[1].id)));
+    }
 }
@@ -0,0 +1,344 @@
+use crate::cli::ColorChoice;
+use crate::todo_repo::Todo;
+use chrono::Local;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use terminal_size::{Width, terminal_size};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const BOLD: &str = "\x1b[1m";
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+const COLUMN_GAP: &str = "  ";
+const MAX_NAME_WIDTH: usize = 30;
+const MAX_TAGS_WIDTH: usize = 20;
+const MIN_DESCRIPTION_WIDTH: usize = 10;
+
+/// How the table renderer may use the terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TableStyle {
+    /// Total line width to fit into; `None` prints every cell in full.
+    pub width: Option<usize>,
+    /// Emit ANSI colors and a bold header.
+    pub color: bool,
+}
+
+impl TableStyle {
+    /// Width limits apply only when stdout is a terminal. With `ColorChoice::Auto` so do
+    /// colors, unless `NO_COLOR` is set.
+    pub fn detect(color: ColorChoice) -> Self {
+        let terminal = std::io::stdout().is_terminal();
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let width = terminal
+            .then(|| {
+                terminal_size()
+                    .map(|(Width(width), _)| usize::from(width))
+                    .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+            })
+            .flatten();
+        Self {
+            width,
+            color: match color {
+                ColorChoice::Auto => terminal && !no_color,
+                ColorChoice::Always => true,
+                ColorChoice::Never => false,
+            },
+        }
+    }
+}
+
+struct Column {
+    header: &'static str,
+    cells: Vec<String>,
+    width: usize,
+}
+
+impl Column {
+    fn new(header: &'static str, cells: Vec<String>, max_width: Option<usize>) -> Self {
+        let widest = cells
+            .iter()
+            .map(|cell| cell.width())
+            .chain([header.width()])
+            .max()
+            .unwrap_or(0);
+        let width = max_width.map_or(widest, |max_width| widest.min(max_width));
+        Self {
+            header,
+            cells,
+            width,
+        }
+    }
+}
+
+/// Renders `todos` as an aligned table with a header row, one line per todo. A leading `#`
+/// column shows the `positions` of todos that have one.
+pub fn render_table(
+    todos: &[Todo],
+    positions: &HashMap<String, usize>,
+    style: TableStyle,
+    date_format: &str,
+) -> String {
+    let limit = |max_width| style.width.map(|_| max_width);
+    let mut columns = Vec::new();
+    if todos.iter().any(|todo| positions.contains_key(&todo.id)) {
+        let cells = todos
+            .iter()
+            .map(|todo| {
+                positions
+                    .get(&todo.id)
+                    .map_or_else(String::new, |position| position.to_string())
+            })
+            .collect();
+        columns.push(Column::new("#", cells, None));
+    }
+    columns.extend([
+        Column::new(
+            "ID",
+            todos.iter().map(|todo| todo.id.clone()).collect(),
+            None,
+        ),
+        Column::new(
+            "STATUS",
+            todos
+                .iter()
+                .map(|todo| String::from(if todo.completed { "✓" } else { "✗" }))
+                .collect(),
+            None,
+        ),
+        Column::new(
+            "NAME",
+            todos.iter().map(|todo| single_line(&todo.name)).collect(),
+            limit(MAX_NAME_WIDTH),
+        ),
+    ]);
+    // Optional columns appear only when at least one todo has a value for them.
+    if todos.iter().any(|todo| todo.due.is_some()) {
+        let cells = todos
+            .iter()
+            .map(|todo| {
+                todo.due.map_or_else(String::new, |due| {
+                    due.with_timezone(&Local).format(date_format).to_string()
+                })
+            })
+            .collect();
+        columns.push(Column::new("DUE", cells, None));
+    }
+    if todos.iter().any(|todo| todo.priority.is_some()) {
+        let cells = todos
+            .iter()
+            .map(|todo| {
+                todo.priority
+                    .map_or_else(String::new, |priority| String::from(priority.as_str()))
+            })
+            .collect();
+        columns.push(Column::new("PRIORITY", cells, None));
+    }
+    if todos.iter().any(|todo| !todo.tags.is_empty()) {
+        let cells = todos.iter().map(|todo| todo.tags.join(",")).collect();
+        columns.push(Column::new("TAGS", cells, limit(MAX_TAGS_WIDTH)));
+    }
+    if todos.iter().any(|todo| todo.recurrence.is_some()) {
+        let cells = todos
+            .iter()
+            .map(|todo| {
+                todo.recurrence
+                    .as_ref()
+                    .map_or_else(String::new, |recurrence| recurrence.to_string())
+            })
+            .collect();
+        columns.push(Column::new("REPEAT", cells, None));
+    }
+    let fixed_width: usize = columns
+        .iter()
+        .map(|column| column.width + COLUMN_GAP.len())
+        .sum();
+    let description_width = style
+        .width
+        .map(|width| width.saturating_sub(fixed_width).max(MIN_DESCRIPTION_WIDTH));
+    columns.push(Column::new(
+        "DESCRIPTION",
+        todos
+            .iter()
+            .map(|todo| single_line(&todo.description))
+            .collect(),
+        description_width,
+    ));
+
+    let mut lines = Vec::with_capacity(todos.len() + 1);
+    let header = render_row(&columns, |column| String::from(column.header));
+    lines.push(if style.color {
+        format!("{}{}{}", BOLD, header, RESET)
+    } else {
+        header
+    });
+    for (row, todo) in todos.iter().enumerate() {
+        lines.push(render_row(&columns, |column| {
+            let cell = fit(&column.cells[row], column.width);
+            match column.header {
+                "STATUS" if style.color => {
+                    let color = if todo.completed { GREEN } else { RED };
+                    format!("{}{}{}", color, cell, RESET)
+                }
+                _ => cell,
+            }
+        }));
+    }
+    lines.join("\n")
+}
+
+// Trailing padding is trimmed so lines never end in spaces.
+fn render_row(columns: &[Column], cell: impl Fn(&Column) -> String) -> String {
+    columns
+        .iter()
+        .map(|column| pad(cell(column), column))
+        .collect::<Vec<_>>()
+        .join(COLUMN_GAP)
+        .trim_end()
+        .to_string()
+}
+
+fn pad(text: String, column: &Column) -> String {
+    let visible_width = strip_ansi(&text).width();
+    format!(
+        "{}{}",
+        text,
+        " ".repeat(column.width.saturating_sub(visible_width))
+    )
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if !in_escape => plain.push(c),
+            _ => {}
+        }
+    }
+    plain
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Shortens `text` to `width` display columns, marking the cut with an ellipsis.
+fn fit(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return String::from(text);
+    }
+    let mut fitted = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        fitted.push(c);
+        used += char_width;
+    }
+    fitted.push('…');
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_repo::Priority;
+
+    fn get_todo(id: &str, name: &str, description: &str, completed: bool) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(name),
+            description: String::from(description),
+            completed,
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn should_align_columns_under_header_without_color() {
+        let todos = vec![
+            get_todo("a1", "Buy milk", "From the corner shop", false),
+            get_todo("b22", "Taxes", "", true),
+        ];
+        let expected = [
+            "ID   STATUS  NAME      DESCRIPTION",
+            "a1   ✗       Buy milk  From the corner shop",
+            "b22  ✓       Taxes",
+        ]
+        .join("\n");
+        assert_eq!(
+            expected,
+            render_table(
+                &todos,
+                &HashMap::new(),
+                TableStyle::default(),
+                "%Y-%m-%d %H:%M"
+            )
+        );
+    }
+
+    #[test]
+    fn should_ellipsize_description_to_terminal_width() {
+        let todos = vec![get_todo(
+            "a1",
+            "Report",
+            "A description much longer than the space left",
+            false,
+        )];
+        let style = TableStyle {
+            width: Some(40),
+            color: false,
+        };
+        let table = render_table(&todos, &HashMap::new(), style, "%Y-%m-%d %H:%M");
+        let row = table.lines().nth(1).unwrap();
+        assert_eq!(40, row.width());
+        assert!(row.ends_with('…'));
+    }
+
+    #[test]
+    fn should_color_status_and_show_optional_columns_when_present() {
+        let todos = vec![
+            Todo {
+                priority: Some(Priority::High),
+                tags: vec![String::from("work")],
+                ..get_todo("a1", "Pending", "", false)
+            },
+            get_todo("b2", "Done", "", true),
+        ];
+        let style = TableStyle {
+            width: None,
+            color: true,
+        };
+        let table = render_table(&todos, &HashMap::new(), style, "%Y-%m-%d %H:%M");
+        assert!(table.starts_with(&format!("{}ID", BOLD)));
+        assert!(table.contains("PRIORITY  TAGS"));
+        assert!(!table.contains("DUE"));
+        assert!(table.contains(&format!("{}✗{}", RED, RESET)));
+        assert!(table.contains(&format!("{}✓{}", GREEN, RESET)));
+    }
+
+    #[test]
+    fn should_number_rows_in_leading_column_when_positions_are_set() {
+        let todos = vec![
+            get_todo("a1", "Buy milk", "", false),
+            get_todo("b22", "Taxes", "", true),
+        ];
+        let positions = HashMap::from([(String::from("a1"), 1), (String::from("b22"), 2)]);
+        let expected = [
+            "#  ID   STATUS  NAME      DESCRIPTION",
+            "1  a1   ✗       Buy milk",
+            "2  b22  ✓       Taxes",
+        ]
+        .join("\n");
+        assert_eq!(
+            expected,
+            render_table(&todos, &positions, TableStyle::default(), "%Y-%m-%d %H:%M")
+        );
+    }
+}
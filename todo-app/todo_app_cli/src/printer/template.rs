@@ -0,0 +1,183 @@
+use crate::todo_repo::Todo;
+use chrono::{DateTime, Utc};
+
+const FIELDS: [&str; 15] = [
+    "id",
+    "name",
+    "description",
+    "completed",
+    "status",
+    "due",
+    "priority",
+    "tags",
+    "recurrence",
+    "parent_id",
+    "blocked_by",
+    "list",
+    "created_at",
+    "updated_at",
+    "completed_at",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field(&'static str),
+}
+
+/// A line format such as `{id}\t{name}`, rendered once per todo.
+///
+/// `{field}` is replaced by the field value, `{{` and `}}` print a brace, and `\t`, `\n` and
+/// `\\` are unescaped so templates can be written in single-quoted shell strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TodoTemplate {
+    segments: Vec<Segment>,
+}
+
+impl TodoTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed '{{{}' in template", name)),
+                        }
+                    }
+                    let field = FIELDS
+                        .iter()
+                        .find(|field| **field == name.trim())
+                        .ok_or_else(|| {
+                            format!(
+                                "unknown template field '{}', expected one of {}",
+                                name,
+                                FIELDS.join(", ")
+                            )
+                        })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                '}' => return Err(String::from("unmatched '}' in template, use '}}'")),
+                '\\' => match chars.peek() {
+                    Some('t') => {
+                        chars.next();
+                        literal.push('\t');
+                    }
+                    Some('n') => {
+                        chars.next();
+                        literal.push('\n');
+                    }
+                    Some('\\') => {
+                        chars.next();
+                        literal.push('\\');
+                    }
+                    _ => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, todo: &Todo) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(text) => text.clone(),
+                Segment::Field(field) => Self::field_value(todo, field),
+            })
+            .collect()
+    }
+
+    // Missing optional values render as an empty string; timestamps use RFC 3339.
+    fn field_value(todo: &Todo, field: &str) -> String {
+        let timestamp = |value: Option<DateTime<Utc>>| {
+            value.map_or_else(String::new, |value| value.to_rfc3339())
+        };
+        match field {
+            "id" => todo.id.clone(),
+            "name" => todo.name.clone(),
+            "description" => todo.description.clone(),
+            "completed" => todo.completed.to_string(),
+            "status" => String::from(if todo.completed { "done" } else { "pending" }),
+            "due" => timestamp(todo.due),
+            "priority" => todo
+                .priority
+                .map_or_else(String::new, |priority| String::from(priority.as_str())),
+            "tags" => todo.tags.join(","),
+            "recurrence" => todo
+                .recurrence
+                .as_ref()
+                .map_or_else(String::new, |recurrence| recurrence.to_string()),
+            "parent_id" => todo.parent_id.clone().unwrap_or_default(),
+            "blocked_by" => todo.blocked_by.join(","),
+            "list" => String::from(todo.list_name()),
+            "created_at" => timestamp(todo.created_at),
+            "updated_at" => timestamp(todo.updated_at),
+            "completed_at" => timestamp(todo.completed_at),
+            _ => unreachable!("template fields are checked by parse"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_repo::Priority;
+    use chrono::TimeZone;
+
+    #[test]
+    fn should_render_fields_escapes_and_braces() {
+        let todo = Todo {
+            id: String::from("a1"),
+            name: String::from("Taxes"),
+            completed: true,
+            priority: Some(Priority::High),
+            tags: vec![String::from("work"), String::from("finance")],
+            due: Some(Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap()),
+            ..Todo::default()
+        };
+        let template = TodoTemplate::parse(
+            r"{id}\t{{{status}}} {name}\n{priority}|{tags}|{due}|{description}",
+        )
+        .unwrap();
+        assert_eq!(
+            "a1\t{done} Taxes\nhigh|work,finance|2026-10-01T09:00:00+00:00|",
+            template.render(&todo)
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_unclosed_and_unmatched_fields() {
+        assert!(
+            TodoTemplate::parse("{title}")
+                .unwrap_err()
+                .contains("unknown template field 'title'")
+        );
+        assert!(TodoTemplate::parse("{id").unwrap_err().contains("unclosed"));
+        assert!(
+            TodoTemplate::parse("id}")
+                .unwrap_err()
+                .contains("unmatched")
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::todo_repo::Todo;
+use std::collections::{HashMap, HashSet};
+
+/// Orders `todos` so every subtask follows its parent, each paired with the guide drawn before
+/// its line. Todos whose parent is not in the list start a tree of their own with an empty
+/// guide; otherwise the list order is kept.
+pub fn tree_order<'t>(todos: &'t [Todo]) -> Vec<(&'t Todo, String)> {
+    let listed: HashSet<&str> = todos.iter().map(|todo| todo.id.as_str()).collect();
+    let parent_of = |todo: &'t Todo| -> Option<&'t str> {
+        todo.parent_id
+            .as_deref()
+            .filter(|parent_id| *parent_id != todo.id && listed.contains(parent_id))
+    };
+    let mut children = HashMap::<&str, Vec<&Todo>>::new();
+    for todo in todos {
+        if let Some(parent_id) = parent_of(todo) {
+            children.entry(parent_id).or_default().push(todo);
+        }
+    }
+    let mut ordered = Vec::with_capacity(todos.len());
+    let mut visited = HashSet::new();
+    for todo in todos.iter().filter(|todo| parent_of(todo).is_none()) {
+        visit(
+            todo,
+            String::new(),
+            "",
+            &children,
+            &mut visited,
+            &mut ordered,
+        );
+    }
+    // Todos in a parent cycle have no root above them; show them rather than drop them.
+    for todo in todos {
+        visit(
+            todo,
+            String::new(),
+            "",
+            &children,
+            &mut visited,
+            &mut ordered,
+        );
+    }
+    ordered
+}
+
+fn visit<'t>(
+    todo: &'t Todo,
+    guide: String,
+    indent: &str,
+    children: &HashMap<&str, Vec<&'t Todo>>,
+    visited: &mut HashSet<&'t str>,
+    ordered: &mut Vec<(&'t Todo, String)>,
+) {
+    if !visited.insert(&todo.id) {
+        return;
+    }
+    ordered.push((todo, guide));
+    let subtasks = children
+        .get(todo.id.as_str())
+        .map_or(&[][..], Vec::as_slice);
+    for (index, subtask) in subtasks.iter().enumerate() {
+        let last = index + 1 == subtasks.len();
+        let branch = if last { "└─ " } else { "├─ " };
+        let child_indent = format!("{}{}", indent, if last { "   " } else { "│  " });
+        visit(
+            subtask,
+            format!("{}{}", indent, branch),
+            &child_indent,
+            children,
+            visited,
+            ordered,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: &str, parent_id: Option<&str>) -> Todo {
+        Todo {
+            id: String::from(id),
+            parent_id: parent_id.map(String::from),
+            ..Todo::default()
+        }
+    }
+
+    fn render(todos: &[Todo]) -> Vec<String> {
+        tree_order(todos)
+            .into_iter()
+            .map(|(todo, guide)| format!("{}{}", guide, todo.id))
+            .collect()
+    }
+
+    #[test]
+    fn should_nest_subtasks_under_parents_with_guides() {
+        let todos = [
+            todo("c", Some("a")),
+            todo("a", None),
+            todo("e", Some("c")),
+            todo("b", None),
+            todo("d", Some("a")),
+            todo("f", Some("gone")),
+        ];
+        assert_eq!(
+            vec!["a", "├─ c", "│  └─ e", "└─ d", "b", "f"],
+            render(&todos)
+        );
+    }
+
+    #[test]
+    fn should_still_show_todos_in_a_parent_cycle() {
+        let todos = [todo("x", Some("y")), todo("y", Some("x"))];
+        assert_eq!(vec!["x", "└─ y"], render(&todos));
+    }
+}
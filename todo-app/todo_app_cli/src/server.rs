@@ -0,0 +1,434 @@
+use crate::cli::{AddCommandArgs, EditCommandArgs, parse_date_time};
+use crate::todo_repo::{
+    Priority, Recurrence, RemoteChanges, TodoErrors, TodoRepository, TodoStorage,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Value, json};
+use std::io::ErrorKind;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Status and JSON body of an answered request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+    /// Methods the path accepts, sent with `405 Method Not Allowed`.
+    pub allow: Option<&'static str>,
+}
+
+impl ApiResponse {
+    fn json(status: u16, body: impl Serialize) -> Result<Self, TodoErrors> {
+        Ok(Self {
+            status,
+            body: serde_json::to_value(body)?,
+            allow: None,
+        })
+    }
+
+    /// The same error envelope the CLI prints with `--output json`, without the exit code.
+    fn error(error: &TodoErrors) -> Self {
+        let mut body = json!({
+            "kind": error.kind(),
+            "message": error.error_message(),
+        });
+        if let TodoErrors::ParseError { line, column, .. } = error {
+            body["line"] = json!(line);
+            body["column"] = json!(column);
+        }
+        Self {
+            status: error.http_status(),
+            body: json!({ "error": body }),
+            allow: None,
+        }
+    }
+
+    fn method_not_allowed(allow: &'static str) -> Self {
+        Self {
+            status: 405,
+            body: json!({
+                "error": {
+                    "kind": "method_not_allowed",
+                    "message": format!("Allowed methods: {}", allow),
+                }
+            }),
+            allow: Some(allow),
+        }
+    }
+}
+
+/// Fields a `PATCH` may change. A `null` clears an optional field.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TodoPatch {
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(deserialize_with = "present_date")]
+    due: Option<Option<DateTime<Utc>>>,
+    #[serde(deserialize_with = "present")]
+    priority: Option<Option<Priority>>,
+    /// Replaces all tags.
+    tags: Option<Vec<String>>,
+    #[serde(deserialize_with = "present")]
+    recurrence: Option<Option<Recurrence>>,
+    completed: Option<bool>,
+}
+
+// Tells a field set to `null` apart from a missing one, which stays `None`.
+fn present<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// Dates accept the same forms as the --due flag.
+fn present_date<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<DateTime<Utc>>>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse_date_time(&value).map_err(serde::de::Error::custom))
+        .transpose()
+        .map(Some)
+}
+
+/// Answers one request. Todos are listed from the current list; ids reach every list, as
+/// on the command line.
+pub fn route<S: TodoStorage>(
+    repo: &mut TodoRepository<S>,
+    method: &Method,
+    url: &str,
+    body: &str,
+) -> ApiResponse {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments = match path
+        .trim_matches('/')
+        .split('/')
+        .map(percent_decode)
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(segments) => segments,
+        Err(e) => return ApiResponse::error(&e),
+    };
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let result = match (method, segments.as_slice()) {
+        (Method::Get, ["todos"]) => repo
+            .get_all_todos()
+            .and_then(|todos| ApiResponse::json(200, todos)),
+        (Method::Post, ["todos"]) => add_todo(repo, body),
+        (Method::Get, ["todos", id]) => repo
+            .get_todo_by_id(String::from(*id))
+            .and_then(|todo| ApiResponse::json(200, todo)),
+        (Method::Patch, ["todos", id]) => patch_todo(repo, id, body),
+        (Method::Delete, ["todos", id]) => repo
+            .delete_todo(String::from(*id))
+            .and_then(|todo| ApiResponse::json(200, todo)),
+        (Method::Post, ["todos", id, "complete"]) => repo
+            .mark_todo_complete(String::from(*id))
+            .and_then(|todo| ApiResponse::json(200, todo)),
+        // Record-level access for `--remote` clients, see `RemoteDataAccess`.
+        (Method::Get, ["storage"]) => repo
+            .storage_document()
+            .and_then(|document| ApiResponse::json(200, serde_json::from_str::<Value>(&document)?)),
+        (Method::Post, ["storage"]) => apply_remote_changes(repo, body),
+        (_, ["todos"] | ["storage"]) => Ok(ApiResponse::method_not_allowed("GET, POST")),
+        (_, ["todos", _]) => Ok(ApiResponse::method_not_allowed("GET, PATCH, DELETE")),
+        (_, ["todos", _, "complete"]) => Ok(ApiResponse::method_not_allowed("POST")),
+        _ => Err(TodoErrors::NotFound(format!("No such path: {}", path))),
+    };
+    result.unwrap_or_else(|e| ApiResponse::error(&e))
+}
+
+/// Decodes the `%XX` escapes of one path segment, so ids may hold any character.
+fn percent_decode(segment: &str) -> Result<String, TodoErrors> {
+    let invalid = || TodoErrors::parse_error(format!("invalid escape in path segment {}", segment));
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+        // `from_str_radix` alone would also take a sign, as in `%+f`.
+        let hex = rest
+            .get(..2)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .ok_or_else(invalid)?;
+        let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+        bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+        rest = &rest[2..];
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, TodoErrors> {
+    serde_json::from_str(body).map_err(|e| match TodoErrors::from(e) {
+        TodoErrors::ParseError {
+            message,
+            line,
+            column,
+        } => TodoErrors::ParseError {
+            message: format!("invalid todo JSON: {}", message),
+            line,
+            column,
+        },
+        other => other,
+    })
+}
+
+fn add_todo<S: TodoStorage>(
+    repo: &mut TodoRepository<S>,
+    body: &str,
+) -> Result<ApiResponse, TodoErrors> {
+    let add_args: AddCommandArgs = parse_body(body)?;
+    let todo = repo.add_todo(&add_args)?;
+    ApiResponse::json(201, todo)
+}
+
+fn patch_todo<S: TodoStorage>(
+    repo: &mut TodoRepository<S>,
+    todo_id: &str,
+    body: &str,
+) -> Result<ApiResponse, TodoErrors> {
+    let patch: TodoPatch = parse_body(body)?;
+    if patch.completed == Some(true) {
+        return Err(TodoErrors::Validation(format!(
+            "completed can only be cleared, use POST /todos/{}/complete",
+            todo_id
+        )));
+    }
+    let mut edit_args = EditCommandArgs {
+        id: String::from(todo_id),
+        name: patch.name,
+        description: patch.description,
+        reopen: patch.completed == Some(false),
+        ..EditCommandArgs::default()
+    };
+    match patch.due {
+        Some(Some(due)) => edit_args.due = Some(due),
+        Some(None) => edit_args.clear_due = true,
+        None => {}
+    }
+    match patch.priority {
+        Some(Some(priority)) => edit_args.priority = Some(priority),
+        Some(None) => edit_args.clear_priority = true,
+        None => {}
+    }
+    match patch.recurrence {
+        Some(Some(recurrence)) => edit_args.recurrence = Some(recurrence),
+        Some(None) => edit_args.clear_repeat = true,
+        None => {}
+    }
+    if let Some(tags) = patch.tags {
+        edit_args.remove_tag = repo.get_todo_by_id(String::from(todo_id))?.tags;
+        edit_args.add_tag = tags;
+    }
+    let todo = repo.update_todo(&edit_args)?;
+    ApiResponse::json(200, todo)
+}
+
+fn apply_remote_changes<S: TodoStorage>(
+    repo: &mut TodoRepository<S>,
+    body: &str,
+) -> Result<ApiResponse, TodoErrors> {
+    let remote_changes: RemoteChanges = parse_body(body)?;
+    repo.apply_remote_changes(&remote_changes)?;
+    ApiResponse::json(200, json!({ "applied": remote_changes.changes.len() }))
+}
+
+/// Answers requests one at a time until the server is dropped, logging each to stderr.
+pub fn serve<S: TodoStorage>(repo: &mut TodoRepository<S>, server: &Server) {
+    for mut request in server.incoming_requests() {
+        let response = match read_body(&mut request) {
+            Ok(body) => route(repo, request.method(), request.url(), &body),
+            Err(e) => ApiResponse::error(&e),
+        };
+        eprintln!("{} {} {}", request.method(), request.url(), response.status);
+        // A client that went away must not stop the server.
+        let _ = request.respond(into_http(response));
+    }
+}
+
+fn read_body(request: &mut Request) -> Result<String, TodoErrors> {
+    let mut body = String::new();
+    match request.as_reader().read_to_string(&mut body) {
+        Ok(_) => Ok(body),
+        Err(e) if e.kind() == ErrorKind::InvalidData => {
+            Err(TodoErrors::parse_error("request body is not valid UTF-8"))
+        }
+        Err(e) => Err(TodoErrors::from(e)),
+    }
+}
+
+fn into_http(response: ApiResponse) -> Response<std::io::Cursor<Vec<u8>>> {
+    let header = |name: &str, value: &str| {
+        Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+    };
+    let mut http = Response::from_string(response.body.to_string())
+        .with_status_code(response.status)
+        .with_header(header("Content-Type", "application/json"));
+    if let Some(allow) = response.allow {
+        http.add_header(header("Allow", allow));
+    }
+    http
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_repo::{SqliteDataAccess, Todo};
+
+    fn get_repository() -> TodoRepository<SqliteDataAccess> {
+        TodoRepository::new(SqliteDataAccess::open_in_memory().unwrap())
+    }
+
+    fn add(repo: &mut TodoRepository<SqliteDataAccess>, body: &str) -> String {
+        let response = route(repo, &Method::Post, "/todos", body);
+        assert_eq!(201, response.status, "{}", response.body);
+        String::from(response.body["id"].as_str().unwrap())
+    }
+
+    #[test]
+    fn should_add_get_and_list_todos_in_todo_shape() {
+        let mut repo = get_repository();
+        let id = add(
+            &mut repo,
+            r#"{"name": "Milk", "description": "2l", "priority": "high", "tags": ["shop"]}"#,
+        );
+
+        let response = route(&mut repo, &Method::Get, &format!("/todos/{}", id), "");
+        assert_eq!(200, response.status);
+        assert_eq!("Milk", response.body["name"]);
+        assert_eq!("high", response.body["priority"]);
+        assert_eq!(json!(["shop"]), response.body["tags"]);
+        assert_eq!(false, response.body["completed"]);
+
+        let response = route(&mut repo, &Method::Get, "/todos?ignored=1", "");
+        assert_eq!(200, response.status);
+        assert_eq!(1, response.body.as_array().unwrap().len());
+    }
+
+    #[test]
+    fn should_patch_only_given_fields_and_clear_nulls() {
+        let mut repo = get_repository();
+        let id = add(
+            &mut repo,
+            r#"{"name": "Milk", "due": "2026-10-20", "priority": "low", "tags": ["a", "b"]}"#,
+        );
+        let path = format!("/todos/{}", id);
+
+        let response = route(
+            &mut repo,
+            &Method::Patch,
+            &path,
+            r#"{"name": "Oat milk", "due": null, "tags": ["c"]}"#,
+        );
+        assert_eq!(200, response.status, "{}", response.body);
+        assert_eq!("Oat milk", response.body["name"]);
+        assert_eq!(Value::Null, response.body["due"]);
+        assert_eq!("low", response.body["priority"]);
+        let response = route(&mut repo, &Method::Patch, &path, r#"{"due": "2026-11-01"}"#);
+        assert_eq!(
+            json!(parse_date_time("2026-11-01").unwrap()),
+            response.body["due"]
+        );
+        assert_eq!(json!(["c"]), response.body["tags"]);
+
+        let response = route(&mut repo, &Method::Patch, &path, r#"{"id": "x"}"#);
+        assert_eq!(400, response.status);
+        assert_eq!("parse_error", response.body["error"]["kind"]);
+        let response = route(&mut repo, &Method::Patch, &path, "{}");
+        assert_eq!(422, response.status);
+    }
+
+    #[test]
+    fn should_complete_reopen_and_delete() {
+        let mut repo = get_repository();
+        let id = add(&mut repo, r#"{"name": "Milk"}"#);
+        let path = format!("/todos/{}", id);
+
+        let response = route(&mut repo, &Method::Post, &format!("{}/complete", path), "");
+        assert_eq!(200, response.status);
+        assert_eq!(true, response.body["completed"]);
+        let response = route(&mut repo, &Method::Patch, &path, r#"{"completed": true}"#);
+        assert_eq!(422, response.status);
+        let response = route(&mut repo, &Method::Patch, &path, r#"{"completed": false}"#);
+        assert_eq!(false, response.body["completed"]);
+
+        let response = route(&mut repo, &Method::Delete, &path, "");
+        assert_eq!(200, response.status);
+        assert!(response.body["deleted_at"].is_string());
+        let response = route(&mut repo, &Method::Get, &path, "");
+        assert_eq!(404, response.status);
+        assert_eq!("not_found", response.body["error"]["kind"]);
+    }
+
+    #[test]
+    fn should_map_errors_and_unknown_routes_to_statuses() {
+        let mut repo = get_repository();
+        let response = route(&mut repo, &Method::Post, "/todos", r#"{"name": "#);
+        assert_eq!(400, response.status);
+        assert_eq!(1, response.body["error"]["line"]);
+        let response = route(&mut repo, &Method::Post, "/todos", r#"{"name": " "}"#);
+        assert_eq!(422, response.status);
+        let response = route(
+            &mut repo,
+            &Method::Post,
+            "/todos",
+            r#"{"name": "Milk", "id": "x", "completed": true}"#,
+        );
+        assert_eq!(400, response.status);
+        assert_eq!("parse_error", response.body["error"]["kind"]);
+        assert!(repo.get_all_todos().unwrap().is_empty());
+        let response = route(&mut repo, &Method::Get, "/lists", "");
+        assert_eq!(404, response.status);
+        let response = route(&mut repo, &Method::Put, "/todos/abc", "");
+        assert_eq!(405, response.status);
+        assert_eq!(Some("GET, PATCH, DELETE"), response.allow);
+    }
+
+    #[test]
+    fn should_percent_decode_ids_in_paths() {
+        let mut storage = SqliteDataAccess::open_in_memory().unwrap();
+        let todo = Todo {
+            id: String::from("a b/ü%"),
+            name: String::from("Milk"),
+            ..Todo::default()
+        };
+        storage.insert(&todo).unwrap();
+        let mut repo = TodoRepository::new(storage);
+        let path = "/todos/a%20b%2F%C3%BC%25";
+
+        let response = route(&mut repo, &Method::Get, path, "");
+        assert_eq!(200, response.status, "{}", response.body);
+        assert_eq!("a b/ü%", response.body["id"]);
+        let response = route(&mut repo, &Method::Post, &format!("{}/complete", path), "");
+        assert_eq!(true, response.body["completed"]);
+        let response = route(&mut repo, &Method::Delete, path, "");
+        assert_eq!(200, response.status);
+
+        for path in [
+            "/todos/a%2",
+            "/todos/a%zz",
+            "/todos/%C3",
+            "/todos/%+f",
+            "/todos/%-1",
+        ] {
+            let response = route(&mut repo, &Method::Get, path, "");
+            assert_eq!(400, response.status, "{}", path);
+        }
+    }
+
+    #[test]
+    fn should_take_exact_ids_only() {
+        let mut repo = get_repository();
+        let id = add(&mut repo, r#"{"name": "Milk"}"#);
+
+        for path in ["/todos/1", "/todos/@last", &format!("/todos/{}", &id[..3])] {
+            let response = route(&mut repo, &Method::Delete, path, "");
+            assert_eq!(404, response.status, "{}", path);
+        }
+        let response = route(&mut repo, &Method::Get, &format!("/todos/{}", id), "");
+        assert!(response.body["deleted_at"].is_null());
+    }
+}
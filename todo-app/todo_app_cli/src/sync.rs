@@ -0,0 +1,382 @@
+use crate::todo_repo::{MergeConflict, TodoErrors, merge_documents};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// What `sync` did, in the order it does it.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    pub remote: String,
+    pub branch: String,
+    /// Local changes to the data file were committed.
+    pub committed: bool,
+    /// The branch was behind its upstream and moved forward to it.
+    pub fast_forwarded: bool,
+    /// Both sides had new commits and the data files were merged todo by todo.
+    pub merged: bool,
+    pub pushed: bool,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Synchronizes a JSON data file tracked in a git work tree with a remote of that repository.
+///
+/// Runs the `git` executable, so remotes, credentials and hooks work as they do on the command
+/// line. Only the data file is committed; other changes in the work tree are left alone.
+pub struct GitSync {
+    path: PathBuf,
+    dir: PathBuf,
+    /// Path of the data file relative to the top of the work tree, as git names it.
+    file: String,
+    remote: String,
+}
+
+impl GitSync {
+    pub fn open(data_file: &Path, remote: &str) -> Result<Self, TodoErrors> {
+        let file_name = data_file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                TodoErrors::Validation(format!("{} is not a file", data_file.display()))
+            })?;
+        let dir = match data_file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let mut sync = Self {
+            path: data_file.to_path_buf(),
+            dir,
+            file: String::from(file_name),
+            remote: String::from(remote),
+        };
+        let prefix = sync.git(&["rev-parse", "--show-prefix"]).map_err(|_| {
+            TodoErrors::Validation(format!(
+                "{} is not inside a git work tree",
+                data_file.display()
+            ))
+        })?;
+        sync.file = format!("{}{}", prefix.trim(), file_name);
+        Ok(sync)
+    }
+
+    /// Commits the data file, merges in the upstream branch and pushes the result.
+    pub fn sync(&self, message: &str) -> Result<SyncReport, TodoErrors> {
+        let branch = self
+            .git(&["symbolic-ref", "--short", "HEAD"])
+            .map_err(|_| TodoErrors::Validation(String::from("HEAD is not on a branch")))?;
+        let branch = String::from(branch.trim());
+        let mut report = SyncReport {
+            remote: self.remote.clone(),
+            branch: branch.clone(),
+            ..SyncReport::default()
+        };
+
+        report.committed = self.commit_data_file(message)?;
+        self.git(&["fetch", &self.remote])?;
+        let upstream = format!("{}/{}", self.remote, branch);
+        let upstream_ref = format!("refs/remotes/{}", upstream);
+        let has_upstream = self.succeeds(&["rev-parse", "--verify", "--quiet", &upstream_ref])?;
+        let has_head = self.succeeds(&["rev-parse", "--verify", "--quiet", "HEAD"])?;
+        if !has_head && !has_upstream {
+            return Ok(report);
+        }
+
+        // Upstream has commits HEAD lacks: move forward to them, or merge when both moved on.
+        if has_upstream && !self.is_ancestor(&upstream, "HEAD")? {
+            if !has_head || self.is_ancestor("HEAD", &upstream)? {
+                self.git(&["merge", "--ff-only", &upstream])?;
+                report.fast_forwarded = true;
+            } else {
+                report.conflicts = self.merge(&upstream)?;
+                report.merged = true;
+            }
+        }
+
+        let ahead = !has_upstream || !self.is_ancestor("HEAD", &upstream)?;
+        if has_head && ahead {
+            self.git(&["push", "--quiet", "--set-upstream", &self.remote, &branch])
+                .map_err(|e| {
+                    TodoErrors::Conflict(format!(
+                        "Push to {} was rejected, sync again to merge the newer changes: {}",
+                        upstream,
+                        e.error_message()
+                    ))
+                })?;
+            report.pushed = true;
+        }
+        Ok(report)
+    }
+
+    /// Returns whether there was anything to commit.
+    fn commit_data_file(&self, message: &str) -> Result<bool, TodoErrors> {
+        if !self.path.exists() {
+            return Ok(false);
+        }
+        let file = format!(":/{}", self.file);
+        self.git(&["add", "--", &file])?;
+        if self.succeeds(&["diff", "--cached", "--quiet", "--", &file])? {
+            return Ok(false);
+        }
+        self.git(&["commit", "--quiet", "-m", message, "--", &file])?;
+        Ok(true)
+    }
+
+    /// Lets git merge the branches, then replaces the data file with a merge of its todos,
+    /// which also settles any line conflicts git found in it.
+    fn merge(&self, upstream: &str) -> Result<Vec<MergeConflict>, TodoErrors> {
+        let merge_base = self.git(&["merge-base", "HEAD", upstream])?;
+        let base = self.read_file(merge_base.trim())?;
+        let ours = self.read_file("HEAD")?;
+        let theirs = self.read_file(upstream)?;
+        let (merged, conflicts) = merge_documents(&base, &ours, &theirs)?;
+
+        // Exits non-zero when the data file has line conflicts, which are resolved below.
+        let output = self.run(&["merge", "--no-ff", "--no-commit", upstream])?;
+        if !self.succeeds(&["rev-parse", "--verify", "--quiet", "MERGE_HEAD"])? {
+            return Err(TodoErrors::Io(format!(
+                "git merge failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        let unmerged = self.git(&["diff", "--name-only", "--diff-filter=U"])?;
+        let others = unmerged
+            .lines()
+            .filter(|path| *path != self.file)
+            .collect::<Vec<_>>();
+        if !others.is_empty() {
+            let _ = self.run(&["merge", "--abort"]);
+            return Err(TodoErrors::Conflict(format!(
+                "Merging {} conflicts in files other than the todos: {}",
+                upstream,
+                others.join(", ")
+            )));
+        }
+
+        std::fs::write(&self.path, merged).map_err(|e| {
+            TodoErrors::Io(format!("Unable to write {}: {}", self.path.display(), e))
+        })?;
+        let file = format!(":/{}", self.file);
+        self.git(&["add", "--", &file])?;
+        self.git(&[
+            "commit",
+            "--quiet",
+            "--no-edit",
+            "-m",
+            &format!("Merge todos from {}", upstream),
+        ])?;
+        Ok(conflicts)
+    }
+
+    /// The data file as of `revision`, empty when it did not exist yet.
+    fn read_file(&self, revision: &str) -> Result<String, TodoErrors> {
+        let object = format!("{}:{}", revision, self.file);
+        if !self.succeeds(&["cat-file", "-e", &object])? {
+            return Ok(String::new());
+        }
+        self.git(&["show", &object])
+    }
+
+    fn run(&self, args: &[&str]) -> Result<Output, TodoErrors> {
+        Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .output()
+            .map_err(|e| TodoErrors::Io(format!("Unable to run git: {}", e)))
+    }
+
+    fn is_ancestor(&self, ancestor: &str, revision: &str) -> Result<bool, TodoErrors> {
+        self.succeeds(&["merge-base", "--is-ancestor", ancestor, revision])
+    }
+
+    /// Runs a command whose exit status is the answer, such as `merge-base --is-ancestor`.
+    fn succeeds(&self, args: &[&str]) -> Result<bool, TodoErrors> {
+        Ok(self.run(args)?.status.success())
+    }
+
+    /// Runs a command that has to succeed and returns its stdout.
+    fn git(&self, args: &[&str]) -> Result<String, TodoErrors> {
+        let output = self.run(args)?;
+        if !output.status.success() {
+            return Err(TodoErrors::Io(format!(
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_repo::MergeSide;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// A bare repository with two clones that commit as a local test user.
+    fn get_clones() -> (TempDir, PathBuf, PathBuf) {
+        let root = TempDir::new().unwrap();
+        let origin = root.path().join("origin.git");
+        git(
+            root.path(),
+            &[
+                "init",
+                "--quiet",
+                "--bare",
+                "-b",
+                "main",
+                origin.to_str().unwrap(),
+            ],
+        );
+        let clone = |name: &str| {
+            let path = root.path().join(name);
+            git(
+                root.path(),
+                &["clone", "--quiet", origin.to_str().unwrap(), name],
+            );
+            git(&path, &["checkout", "--quiet", "-B", "main"]);
+            git(&path, &["config", "user.name", name]);
+            git(&path, &["config", "user.email", "test@example.com"]);
+            path
+        };
+        let (alice, bob) = (clone("alice"), clone("bob"));
+        (root, alice, bob)
+    }
+
+    fn write_todos(dir: &Path, todos: &str) -> PathBuf {
+        let path = dir.join("todo.json");
+        std::fs::write(&path, format!(r#"{{"version": 7, "todos": {}}}"#, todos)).unwrap();
+        path
+    }
+
+    fn todo(id: &str, name: &str, completed: bool, updated: &str) -> String {
+        format!(
+            r#"{{"id": "{}", "name": "{}", "description": "", "completed": {}, "updated_at": "{}"}}"#,
+            id, name, completed, updated
+        )
+    }
+
+    #[test]
+    fn should_push_first_commit_and_fast_forward_other_clone() {
+        let (_root, alice, bob) = get_clones();
+        let path = write_todos(
+            &alice,
+            &format!("[{}]", todo("a", "Milk", false, "2025-01-01T00:00:00Z")),
+        );
+
+        let report = GitSync::open(&path, "origin")
+            .unwrap()
+            .sync("Update todos")
+            .unwrap();
+        assert!(report.committed && report.pushed);
+        assert!(!report.fast_forwarded && !report.merged);
+
+        let bob_path = bob.join("todo.json");
+        let report = GitSync::open(&bob_path, "origin")
+            .unwrap()
+            .sync("Update todos")
+            .unwrap();
+        assert!(report.fast_forwarded);
+        assert!(!report.committed && !report.pushed);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            std::fs::read_to_string(&bob_path).unwrap()
+        );
+
+        let report = GitSync::open(&bob_path, "origin")
+            .unwrap()
+            .sync("Update todos")
+            .unwrap();
+        assert_eq!(
+            SyncReport {
+                remote: String::from("origin"),
+                branch: String::from("main"),
+                ..SyncReport::default()
+            },
+            report
+        );
+    }
+
+    #[test]
+    fn should_merge_diverged_clones_by_todo() {
+        let (_root, alice, bob) = get_clones();
+        let base = format!(
+            "[{}, {}]",
+            todo("a", "Milk", false, "2025-01-01T00:00:00Z"),
+            todo("b", "Bread", false, "2025-01-01T00:00:00Z")
+        );
+        let alice_path = write_todos(&alice, &base);
+        GitSync::open(&alice_path, "origin")
+            .unwrap()
+            .sync("Update todos")
+            .unwrap();
+        let bob_path = bob.join("todo.json");
+        GitSync::open(&bob_path, "origin")
+            .unwrap()
+            .sync("Update todos")
+            .unwrap();
+
+        write_todos(
+            &alice,
+            &format!(
+                "[{}, {}]",
+                todo("a", "Whole milk", true, "2025-01-02T00:00:00Z"),
+                todo("b", "Bread", false, "2025-01-01T00:00:00Z")
+            ),
+        );
+        GitSync::open(&alice_path, "origin")
+            .unwrap()
+            .sync("Update todos")
+            .unwrap();
+        write_todos(
+            &bob,
+            &format!(
+                "[{}, {}, {}]",
+                todo("a", "Oat milk", false, "2025-01-03T00:00:00Z"),
+                todo("b", "Bread", true, "2025-01-03T00:00:00Z"),
+                todo("c", "Eggs", false, "2025-01-03T00:00:00Z")
+            ),
+        );
+
+        let report = GitSync::open(&bob_path, "origin")
+            .unwrap()
+            .sync("Update todos")
+            .unwrap();
+        assert!(report.committed && report.merged && report.pushed);
+        assert_eq!(1, report.conflicts.len());
+        assert_eq!("name", report.conflicts[0].field);
+        assert_eq!(MergeSide::Ours, report.conflicts[0].kept);
+
+        let report = GitSync::open(&alice_path, "origin")
+            .unwrap()
+            .sync("Update todos")
+            .unwrap();
+        assert!(report.fast_forwarded);
+        let merged: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&alice_path).unwrap()).unwrap();
+        let todos = merged["todos"].as_array().unwrap();
+        assert_eq!(3, todos.len());
+        assert_eq!("Oat milk", todos[0]["name"]);
+        assert_eq!(true, todos[0]["completed"]);
+        assert_eq!(true, todos[1]["completed"]);
+        assert_eq!("Eggs", todos[2]["name"]);
+    }
+
+    #[test]
+    fn should_refuse_file_outside_work_tree() {
+        let dir = TempDir::new().unwrap();
+        let result = GitSync::open(&dir.path().join("todo.json"), "origin");
+        assert!(matches!(result, Err(TodoErrors::Validation(_))));
+    }
+}
@@ -0,0 +1,21 @@
+use crate::todo_repo::{Todo, TodoQuery};
+use serde::Serialize;
+
+/// The todos a bulk operation applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TodoSelection {
+    /// Explicit ids; blank and repeated ids are ignored.
+    Ids(Vec<String>),
+    /// Every todo outside the trash matching the query.
+    Matching(TodoQuery),
+}
+
+/// Outcome of a bulk operation, in the order the ids were selected.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct BulkReport {
+    pub succeeded: Vec<Todo>,
+    pub not_found: Vec<String>,
+    /// Todos added by the operation, such as the next occurrences of completed recurring todos.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub created: Vec<Todo>,
+}
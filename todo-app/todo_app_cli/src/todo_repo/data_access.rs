@@ -1,49 +1,182 @@
-use crate::todo_repo::TodoErrors;
-use std::fs::OpenOptions;
-use std::io::{BufReader, BufWriter, Read, Write};
-pub trait TodoDataAccess {
-    fn read_all(&mut self) -> Result<String, TodoErrors>;
-    fn write_all(&mut self, data: String) -> Result<(), TodoErrors>;
-}
+use crate::todo_repo::storage::{JsonDocument, TodoFilter, TodoStorage};
+use crate::todo_repo::{Todo, TodoErrors};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
 
+/// JSON file backend.
+///
+/// Readers take a shared advisory lock and writers an exclusive one on a sidecar
+/// `<file>.lock`, since the data file itself is replaced on every save. Saves go
+/// through a temp file that is fsynced and renamed over the data file, so a crash
+/// leaves either the old or the new list, never a truncated one.
 pub struct FileDataAccess {
     file_path: String,
+    transaction: Option<FileTransaction>,
+}
+
+struct FileTransaction {
+    // Keeps the exclusive lock until the transaction ends and the file is closed.
+    _lock_file: File,
+    document: JsonDocument,
+    dirty: bool,
 }
 
 impl FileDataAccess {
     pub fn new(file_path: String) -> Self {
         Self {
             file_path: file_path.to_string(),
+            transaction: None,
         }
     }
-}
 
-impl TodoDataAccess for FileDataAccess {
-    fn read_all(&mut self) -> Result<String, TodoErrors> {
-        let file = OpenOptions::new()
+    fn lock_path(&self) -> PathBuf {
+        PathBuf::from(format!("{}.lock", self.file_path))
+    }
+
+    fn open_lock_file(&self) -> Result<File, TodoErrors> {
+        OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
-            .open(&self.file_path)
-            .map_err(|e| TodoErrors::TodoGetError(e.to_string()))?;
-        let mut reader = BufReader::new(file);
+            .truncate(false)
+            .open(self.lock_path())
+            .map_err(TodoErrors::from)
+    }
+
+    fn read_document(&self) -> Result<JsonDocument, TodoErrors> {
         let mut input = String::new();
-        reader
-            .read_to_string(&mut input)
-            .map_err(|e| TodoErrors::TodoGetError(e.to_string()))?;
-        Ok(input)
+        match File::open(&self.file_path) {
+            Ok(mut file) => {
+                file.read_to_string(&mut input)?;
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(TodoErrors::from(e)),
+        }
+        JsonDocument::parse(&input)
     }
 
-    fn write_all(&mut self, data: String) -> Result<(), TodoErrors> {
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&self.file_path)
-            .map_err(|e| TodoErrors::TodoSaveError(e.to_string()))?;
-        let mut writer = BufWriter::new(file);
-        writer
-            .write_all(data.as_bytes())
-            .map_err(|e| TodoErrors::TodoSaveError(e.to_string()))
+    fn write_document(&self, document: &JsonDocument) -> Result<(), TodoErrors> {
+        let data = document.to_json()?;
+        let path = Path::new(&self.file_path);
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut temp_file = NamedTempFile::new_in(dir)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            temp_file
+                .as_file()
+                .set_permissions(metadata.permissions())?;
+        }
+        temp_file.write_all(data.as_bytes())?;
+        temp_file.as_file().sync_all()?;
+        temp_file
+            .persist(path)
+            .map_err(|e| TodoErrors::from(e.error))?;
+        // Make the rename itself durable; not every platform can fsync a directory.
+        if let Ok(dir_handle) = File::open(dir) {
+            let _ = dir_handle.sync_all();
+        }
+        Ok(())
+    }
+
+    fn read<T>(&mut self, reader: impl FnOnce(&JsonDocument) -> T) -> Result<T, TodoErrors> {
+        if let Some(transaction) = &self.transaction {
+            return Ok(reader(&transaction.document));
+        }
+        let lock_file = self.open_lock_file()?;
+        lock_file.lock_shared()?;
+        let document = self.read_document()?;
+        Ok(reader(&document))
+    }
+
+    fn modify<T>(
+        &mut self,
+        modifier: impl FnOnce(&mut JsonDocument) -> Result<T, TodoErrors>,
+    ) -> Result<T, TodoErrors> {
+        let owns_transaction = self.transaction.is_none();
+        if owns_transaction {
+            self.begin_write()?;
+        }
+        let transaction = self
+            .transaction
+            .as_mut()
+            .expect("write transaction started");
+        let result = modifier(&mut transaction.document);
+        if result.is_ok() {
+            transaction.dirty = true;
+        }
+        if owns_transaction {
+            match &result {
+                Ok(_) => self.commit()?,
+                Err(_) => self.rollback(),
+            }
+        }
+        result
+    }
+}
+
+impl TodoStorage for FileDataAccess {
+    fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        self.read(|document| document.get(todo_id))
+    }
+
+    fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        self.modify(|document| document.insert(todo))
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        self.modify(|document| document.update(todo))
+    }
+
+    fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        self.modify(|document| Ok(document.remove(todo_id)))
+    }
+
+    fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors> {
+        self.read(|document| document.scan(filter))
+    }
+
+    fn default_list(&mut self) -> Result<Option<String>, TodoErrors> {
+        self.read(|document| document.default_list.clone())
+    }
+
+    fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+        self.modify(|document| {
+            document.default_list = Some(String::from(list));
+            Ok(())
+        })
+    }
+
+    fn begin_write(&mut self) -> Result<(), TodoErrors> {
+        if self.transaction.is_some() {
+            return Err(TodoErrors::Conflict(String::from(
+                "A write transaction is already in progress",
+            )));
+        }
+        let lock_file = self.open_lock_file()?;
+        lock_file.lock()?;
+        let document = self.read_document()?;
+        self.transaction = Some(FileTransaction {
+            _lock_file: lock_file,
+            document,
+            dirty: false,
+        });
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), TodoErrors> {
+        match self.transaction.take() {
+            Some(transaction) if transaction.dirty => self.write_document(&transaction.document),
+            _ => Ok(()),
+        }
+    }
+
+    fn rollback(&mut self) {
+        self.transaction = None;
     }
 }
 
@@ -53,29 +186,74 @@ use std::io::Cursor;
 pub struct CursorDataAccess {
     pub reader: Cursor<String>,
     pub writer: Cursor<Vec<u8>>,
+    document: Option<JsonDocument>,
 }
 
 #[cfg(test)]
 impl CursorDataAccess {
     pub fn new(reader: Cursor<String>, writer: Cursor<Vec<u8>>) -> Self {
-        Self { reader, writer }
+        Self {
+            reader,
+            writer,
+            document: None,
+        }
     }
-}
 
-#[cfg(test)]
-impl TodoDataAccess for CursorDataAccess {
-    fn read_all(&mut self) -> Result<String, TodoErrors> {
-        let mut input = String::new();
-        self.reader
-            .read_to_string(&mut input)
-            .map_err(|e| TodoErrors::TodoGetError(e.to_string()))?;
-        Ok(input)
+    fn document(&mut self) -> Result<&mut JsonDocument, TodoErrors> {
+        if self.document.is_none() {
+            let mut input = String::new();
+            self.reader.read_to_string(&mut input)?;
+            self.document = Some(JsonDocument::parse(&input)?);
+        }
+        Ok(self.document.get_or_insert_with(JsonDocument::default))
     }
 
-    fn write_all(&mut self, data: String) -> Result<(), TodoErrors> {
+    // The writer always holds the latest full document, as the data file would.
+    fn flush(&mut self) -> Result<(), TodoErrors> {
+        let data = self.document()?.to_json()?;
+        self.writer.get_mut().clear();
+        self.writer.set_position(0);
         self.writer
             .write_all(data.as_bytes())
-            .map_err(|e| TodoErrors::TodoSaveError(e.to_string()))
+            .map_err(TodoErrors::from)
+    }
+}
+
+#[cfg(test)]
+impl TodoStorage for CursorDataAccess {
+    fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        Ok(self.document()?.get(todo_id))
+    }
+
+    fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        self.document()?.insert(todo)?;
+        self.flush()
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        self.document()?.update(todo)?;
+        self.flush()
+    }
+
+    fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        let removed = self.document()?.remove(todo_id);
+        if removed.is_some() {
+            self.flush()?;
+        }
+        Ok(removed)
+    }
+
+    fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors> {
+        Ok(self.document()?.scan(filter))
+    }
+
+    fn default_list(&mut self) -> Result<Option<String>, TodoErrors> {
+        Ok(self.document()?.default_list.clone())
+    }
+
+    fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+        self.document()?.default_list = Some(String::from(list));
+        self.flush()
     }
 }
 
@@ -89,22 +267,153 @@ impl FailingDataAccess {
     pub fn new(reader: Cursor<String>) -> Self {
         Self { reader }
     }
+
+    fn load(&mut self) -> Result<JsonDocument, TodoErrors> {
+        let mut input = String::new();
+        self.reader.read_to_string(&mut input)?;
+        self.reader.set_position(0);
+        JsonDocument::parse(&input)
+    }
+
+    fn fail_write(todo_id: &str) -> TodoErrors {
+        TodoErrors::Io(format!("Simulated write error for todo: {}", todo_id))
+    }
 }
 
 #[cfg(test)]
-impl TodoDataAccess for FailingDataAccess {
-    fn read_all(&mut self) -> Result<String, TodoErrors> {
-        let mut input = String::new();
-        self.reader
-            .read_to_string(&mut input)
-            .map_err(|e| TodoErrors::TodoGetError(e.to_string()))?;
-        Ok(input)
+impl TodoStorage for FailingDataAccess {
+    fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        Ok(self.load()?.get(todo_id))
+    }
+
+    fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        Err(Self::fail_write(&todo.id))
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        Err(Self::fail_write(&todo.id))
+    }
+
+    fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        Err(Self::fail_write(todo_id))
+    }
+
+    fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors> {
+        Ok(self.load()?.scan(filter))
     }
 
-    fn write_all(&mut self, data: String) -> Result<(), TodoErrors> {
-        Err(TodoErrors::TodoSaveError(String::from(format!(
-            "Simulated write error for data: {}",
-            data
-        ))))
+    fn default_list(&mut self) -> Result<Option<String>, TodoErrors> {
+        Ok(self.load()?.default_list)
+    }
+
+    fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+        Err(Self::fail_write(list))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_todo(id: &str, name: &str) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(name),
+            description: format!("{} description", name),
+            completed: false,
+            ..Todo::default()
+        }
+    }
+
+    fn get_file_data_access(tempfile: &NamedTempFile) -> FileDataAccess {
+        FileDataAccess::new(String::from(tempfile.path().to_str().unwrap()))
+    }
+
+    #[test]
+    fn should_persist_records_across_file_data_access_instances() {
+        let tempfile = NamedTempFile::new().unwrap();
+        let mut data_access = get_file_data_access(&tempfile);
+        let first = get_todo("a", "First");
+        let mut second = get_todo("b", "Second");
+        data_access.insert(&first).unwrap();
+        data_access.insert(&second).unwrap();
+        second.completed = true;
+        data_access.update(&second).unwrap();
+        data_access.remove("a").unwrap();
+
+        let mut reopened = get_file_data_access(&tempfile);
+        assert_eq!(vec![second], reopened.scan(&TodoFilter::all()).unwrap());
+        assert_eq!(None, reopened.get("a").unwrap());
+    }
+
+    #[test]
+    fn should_scan_file_with_filter() {
+        let tempfile = NamedTempFile::new().unwrap();
+        let mut data_access = get_file_data_access(&tempfile);
+        let todos = [get_todo("a", "Buy milk"), get_todo("b", "Walk dog")];
+        todos
+            .iter()
+            .for_each(|todo| data_access.insert(todo).unwrap());
+        let filter = TodoFilter {
+            name_contains: Some(String::from("MILK")),
+            ..TodoFilter::default()
+        };
+        assert_eq!(vec![todos[0].clone()], data_access.scan(&filter).unwrap());
+    }
+
+    #[test]
+    fn should_return_err_for_malformed_data_file() {
+        let tempfile = NamedTempFile::new().unwrap();
+        std::fs::write(tempfile.path(), "not json").unwrap();
+        let mut data_access = get_file_data_access(&tempfile);
+        assert!(matches!(
+            data_access.scan(&TodoFilter::all()),
+            Err(TodoErrors::ParseError { line: Some(1), .. })
+        ));
+    }
+
+    #[test]
+    fn should_write_data_file_only_on_commit() {
+        let tempfile = NamedTempFile::new().unwrap();
+        let mut data_access = get_file_data_access(&tempfile);
+        data_access.begin_write().unwrap();
+        data_access.insert(&get_todo("a", "First")).unwrap();
+        data_access.insert(&get_todo("b", "Second")).unwrap();
+        assert_eq!("", std::fs::read_to_string(tempfile.path()).unwrap());
+
+        data_access.commit().unwrap();
+        let mut reopened = get_file_data_access(&tempfile);
+        assert_eq!(2, reopened.scan(&TodoFilter::all()).unwrap().len());
+    }
+
+    #[test]
+    fn should_leave_data_file_untouched_on_rollback() {
+        let tempfile = NamedTempFile::new().unwrap();
+        let mut data_access = get_file_data_access(&tempfile);
+        let first = get_todo("a", "First");
+        data_access.insert(&first).unwrap();
+        let saved = std::fs::read_to_string(tempfile.path()).unwrap();
+
+        data_access.begin_write().unwrap();
+        data_access.remove("a").unwrap();
+        data_access.rollback();
+
+        assert_eq!(saved, std::fs::read_to_string(tempfile.path()).unwrap());
+        assert_eq!(Some(first), data_access.get("a").unwrap());
+    }
+
+    #[test]
+    fn should_not_leave_temp_files_behind_after_save() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let data_path = tempdir.path().join("todo.json");
+        let mut data_access = FileDataAccess::new(String::from(data_path.to_str().unwrap()));
+        data_access.insert(&get_todo("a", "First")).unwrap();
+
+        let mut entries: Vec<String> = std::fs::read_dir(tempdir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        entries.sort();
+        assert_eq!(vec!["todo.json", "todo.json.lock"], entries);
     }
 }
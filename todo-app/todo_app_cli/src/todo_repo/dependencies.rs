@@ -0,0 +1,103 @@
+use crate::todo_repo::Todo;
+use std::collections::HashSet;
+
+/// The todos among `todos` that `todo` waits on and that are still pending. Blockers that
+/// are done, trashed or gone no longer hold anything up.
+pub fn open_blockers<'t>(todo: &Todo, todos: &'t [Todo]) -> Vec<&'t Todo> {
+    todos
+        .iter()
+        .filter(|blocker| {
+            !blocker.completed
+                && blocker.deleted_at.is_none()
+                && todo.blocked_by.contains(&blocker.id)
+        })
+        .collect()
+}
+
+/// The chain of ids `todo_id` would wait on in a circle if it were blocked by `blocker_id`,
+/// starting and ending with `todo_id`, or `None` when the link is safe.
+pub(crate) fn find_cycle(todos: &[Todo], todo_id: &str, blocker_id: &str) -> Option<Vec<String>> {
+    let mut seen = HashSet::new();
+    let mut path = vec![String::from(todo_id)];
+    if walk(todos, blocker_id, todo_id, &mut seen, &mut path) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+// Depth-first along `blocked_by`, leaving `path` at the chain that reached `target`.
+fn walk<'t>(
+    todos: &'t [Todo],
+    from: &'t str,
+    target: &str,
+    seen: &mut HashSet<&'t str>,
+    path: &mut Vec<String>,
+) -> bool {
+    path.push(String::from(from));
+    if from == target {
+        return true;
+    }
+    if seen.insert(from)
+        && let Some(todo) = todos.iter().find(|todo| todo.id == from)
+    {
+        for next in &todo.blocked_by {
+            if walk(todos, next, target, seen, path) {
+                return true;
+            }
+        }
+    }
+    path.pop();
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn todo(id: &str, blocked_by: &[&str]) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(id),
+            blocked_by: blocked_by.iter().map(|id| String::from(*id)).collect(),
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn should_only_count_pending_blockers_outside_trash() {
+        let mut todos = vec![
+            todo("a", &["b", "c", "d", "gone"]),
+            todo("b", &[]),
+            todo("c", &[]),
+            todo("d", &[]),
+        ];
+        todos[2].completed = true;
+        todos[3].deleted_at = Some(Utc::now());
+        let blockers = open_blockers(&todos[0], &todos);
+        assert_eq!(
+            vec!["b"],
+            blockers.iter().map(|t| &t.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn should_report_cycle_path_when_link_would_close_a_loop() {
+        let todos = [todo("a", &["b"]), todo("b", &["c"]), todo("c", &[])];
+        assert_eq!(
+            Some(vec![
+                String::from("c"),
+                String::from("a"),
+                String::from("b"),
+                String::from("c")
+            ]),
+            find_cycle(&todos, "c", "a")
+        );
+        assert_eq!(
+            Some(vec![String::from("a"), String::from("a")]),
+            find_cycle(&todos, "a", "a")
+        );
+        assert_eq!(None, find_cycle(&todos, "a", "c"));
+    }
+}
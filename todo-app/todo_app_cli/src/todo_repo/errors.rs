@@ -0,0 +1,224 @@
+use std::fmt;
+
+/// Errors from storage, the repository and import/export, grouped by what a caller can do
+/// about them. Each category exits the CLI with its own code, see [`TodoErrors::exit_code`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TodoErrors {
+    /// A todo or operation that was asked for does not exist.
+    NotFound(String),
+    /// Input or a data file is not in the expected format. `line` and `column` are 1-based.
+    ParseError {
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    /// A file or database could not be read or written.
+    Io(String),
+    /// The data no longer matches what the request expects, such as an id that is taken.
+    Conflict(String),
+    /// The request itself is invalid, such as an empty todo name.
+    Validation(String),
+}
+
+impl TodoErrors {
+    pub fn parse_error(message: impl Into<String>) -> Self {
+        TodoErrors::ParseError {
+            message: message.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    /// The message without the position of a parse error.
+    pub fn error_message(&self) -> &str {
+        match self {
+            TodoErrors::NotFound(msg)
+            | TodoErrors::ParseError { message: msg, .. }
+            | TodoErrors::Io(msg)
+            | TodoErrors::Conflict(msg)
+            | TodoErrors::Validation(msg) => msg,
+        }
+    }
+
+    /// Stable snake_case name of the category, used in the JSON error envelope.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TodoErrors::NotFound(_) => "not_found",
+            TodoErrors::ParseError { .. } => "parse_error",
+            TodoErrors::Io(_) => "io",
+            TodoErrors::Conflict(_) => "conflict",
+            TodoErrors::Validation(_) => "validation",
+        }
+    }
+
+    /// Process exit code for the category. 2 is left to invalid command-line arguments.
+    ///
+    /// | code | category     |
+    /// |------|--------------|
+    /// | 3    | `NotFound`   |
+    /// | 4    | `Validation` |
+    /// | 5    | `Conflict`   |
+    /// | 6    | `ParseError` |
+    /// | 7    | `Io`         |
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TodoErrors::NotFound(_) => 3,
+            TodoErrors::Validation(_) => 4,
+            TodoErrors::Conflict(_) => 5,
+            TodoErrors::ParseError { .. } => 6,
+            TodoErrors::Io(_) => 7,
+        }
+    }
+
+    /// HTTP status the server answers with for the category.
+    ///
+    /// | status | category     |
+    /// |--------|--------------|
+    /// | 400    | `ParseError` |
+    /// | 404    | `NotFound`   |
+    /// | 409    | `Conflict`   |
+    /// | 422    | `Validation` |
+    /// | 500    | `Io`         |
+    pub fn http_status(&self) -> u16 {
+        match self {
+            TodoErrors::ParseError { .. } => 400,
+            TodoErrors::NotFound(_) => 404,
+            TodoErrors::Conflict(_) => 409,
+            TodoErrors::Validation(_) => 422,
+            TodoErrors::Io(_) => 500,
+        }
+    }
+}
+
+impl fmt::Display for TodoErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TodoErrors::ParseError {
+                message,
+                line: Some(line),
+                column: Some(column),
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            TodoErrors::ParseError {
+                message,
+                line: Some(line),
+                column: None,
+            } => write!(f, "line {}: {}", line, message),
+            _ => write!(f, "{}", self.error_message()),
+        }
+    }
+}
+
+impl std::error::Error for TodoErrors {}
+
+impl From<std::io::Error> for TodoErrors {
+    fn from(e: std::io::Error) -> Self {
+        TodoErrors::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for TodoErrors {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            return TodoErrors::Io(e.to_string());
+        }
+        // serde_json appends the position to its message; it is kept in the fields instead.
+        let message = e.to_string();
+        let position = format!(" at line {} column {}", e.line(), e.column());
+        let has_position = e.line() > 0;
+        TodoErrors::ParseError {
+            message: String::from(message.strip_suffix(&position).unwrap_or(&message)),
+            line: has_position.then_some(e.line()),
+            column: has_position.then_some(e.column()),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for TodoErrors {
+    fn from(e: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+        match &e {
+            rusqlite::Error::SqliteFailure(failure, _) => match failure.code {
+                ErrorCode::ConstraintViolation => TodoErrors::Conflict(e.to_string()),
+                ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt => {
+                    TodoErrors::parse_error(e.to_string())
+                }
+                _ => TodoErrors::Io(e.to_string()),
+            },
+            rusqlite::Error::FromSqlConversionFailure(..)
+            | rusqlite::Error::InvalidColumnType(..) => TodoErrors::parse_error(e.to_string()),
+            _ => TodoErrors::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<csv::Error> for TodoErrors {
+    fn from(e: csv::Error) -> Self {
+        if e.is_io_error() {
+            return TodoErrors::Io(e.to_string());
+        }
+        TodoErrors::ParseError {
+            line: e.position().map(|position| position.line() as usize),
+            column: None,
+            message: e.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_json_position_in_fields_and_display() {
+        let error = TodoErrors::from(serde_json::from_str::<u32>("\n  x").unwrap_err());
+        assert_eq!(
+            TodoErrors::ParseError {
+                message: String::from("expected value"),
+                line: Some(2),
+                column: Some(3),
+            },
+            error
+        );
+        assert_eq!("line 2, column 3: expected value", error.to_string());
+        assert_eq!(6, error.exit_code());
+    }
+
+    #[test]
+    fn should_give_each_category_its_own_kind_and_exit_code() {
+        let errors = [
+            TodoErrors::NotFound(String::from("a")),
+            TodoErrors::parse_error("b"),
+            TodoErrors::Io(String::from("c")),
+            TodoErrors::Conflict(String::from("d")),
+            TodoErrors::Validation(String::from("e")),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(TodoErrors::exit_code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(errors.len(), codes.len());
+        assert!(!codes.contains(&0) && !codes.contains(&1) && !codes.contains(&2));
+        assert_eq!(
+            vec!["not_found", "parse_error", "io", "conflict", "validation"],
+            errors.iter().map(TodoErrors::kind).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![404, 400, 500, 409, 422],
+            errors
+                .iter()
+                .map(TodoErrors::http_status)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn should_map_sqlite_constraint_violation_to_conflict() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch("CREATE TABLE t (id TEXT PRIMARY KEY); INSERT INTO t VALUES ('a');")
+            .unwrap();
+        let error = connection
+            .execute("INSERT INTO t VALUES ('a')", [])
+            .unwrap_err();
+        assert!(matches!(TodoErrors::from(error), TodoErrors::Conflict(_)));
+    }
+}
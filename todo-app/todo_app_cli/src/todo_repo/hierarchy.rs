@@ -0,0 +1,109 @@
+use crate::todo_repo::Todo;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// How many direct subtasks of a todo are done.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubtaskProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl fmt::Display for SubtaskProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.done, self.total)
+    }
+}
+
+/// Subtask progress of every todo in `todos` that has subtasks among them, by id.
+pub fn subtask_progress(todos: &[Todo]) -> HashMap<String, SubtaskProgress> {
+    let mut progress = HashMap::<String, SubtaskProgress>::new();
+    for todo in todos {
+        if let Some(parent_id) = &todo.parent_id {
+            let entry = progress.entry(parent_id.clone()).or_default();
+            entry.total += 1;
+            if todo.completed {
+                entry.done += 1;
+            }
+        }
+    }
+    progress
+}
+
+/// Every subtask of `todo_id` among `todos`, each followed by its own subtasks.
+pub(crate) fn descendants<'t>(todos: &'t [Todo], todo_id: &str) -> Vec<&'t Todo> {
+    // Reversed so popping the stack visits siblings in list order.
+    fn children<'t>(todos: &'t [Todo], parent_id: &str) -> impl Iterator<Item = &'t Todo> {
+        todos
+            .iter()
+            .rev()
+            .filter(move |todo| todo.parent_id.as_deref() == Some(parent_id))
+    }
+    let mut found = Vec::new();
+    // Ids already walked, so a parent cycle in imported data cannot loop forever.
+    let mut seen = HashSet::from([todo_id]);
+    let mut stack: Vec<&Todo> = children(todos, todo_id).collect();
+    while let Some(todo) = stack.pop() {
+        if seen.insert(&todo.id) {
+            found.push(todo);
+            stack.extend(children(todos, &todo.id));
+        }
+    }
+    found
+}
+
+/// How a bulk operation treats the subtasks of the todos it changes.
+#[derive(Clone, Copy)]
+pub(crate) struct SubtaskRule {
+    /// Subtasks the operation has not reached yet, such as pending ones when completing.
+    pub unfinished: fn(&Todo) -> bool,
+    /// Apply the operation to unfinished subtasks as well instead of refusing.
+    pub cascade: bool,
+    /// The command-line flag that sets `cascade`, named in the error.
+    pub flag: &'static str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: &str, parent_id: Option<&str>, completed: bool) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(id),
+            parent_id: parent_id.map(String::from),
+            completed,
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn should_count_direct_subtasks_only() {
+        let todos = [
+            todo("a", None, false),
+            todo("b", Some("a"), true),
+            todo("c", Some("a"), false),
+            todo("d", Some("c"), true),
+        ];
+        let progress = subtask_progress(&todos);
+        assert_eq!("1/2", progress["a"].to_string());
+        assert_eq!(SubtaskProgress { done: 1, total: 1 }, progress["c"]);
+        assert!(!progress.contains_key("b"));
+    }
+
+    #[test]
+    fn should_find_nested_subtasks_and_stop_at_cycles() {
+        let todos = [
+            todo("a", None, false),
+            todo("b", Some("a"), false),
+            todo("c", Some("b"), false),
+            todo("d", Some("a"), false),
+            todo("x", Some("y"), false),
+            todo("y", Some("x"), false),
+        ];
+        let ids = |found: Vec<&Todo>| found.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+        assert_eq!(vec!["b", "c", "d"], ids(descendants(&todos, "a")));
+        assert_eq!(vec!["y"], ids(descendants(&todos, "x")));
+        assert!(descendants(&todos, "c").is_empty());
+    }
+}
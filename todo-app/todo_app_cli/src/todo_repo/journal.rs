@@ -0,0 +1,478 @@
+use crate::todo_repo::{Todo, TodoErrors, TodoFilter, TodoStorage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+
+/// Entries a file journal keeps by default; older ones are dropped and can no longer be undone.
+const DEFAULT_RETAINED_ENTRIES: usize = 1000;
+/// Bytes read at a time while looking for the last line of a file journal.
+const TAIL_CHUNK: u64 = 4096;
+
+/// State of one todo before and after an operation; `None` means it did not exist.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TodoChange {
+    pub before: Option<Todo>,
+    pub after: Option<Todo>,
+}
+
+impl TodoChange {
+    pub fn todo_id(&self) -> &str {
+        self.after
+            .as_ref()
+            .or(self.before.as_ref())
+            .map(|todo| todo.id.as_str())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JournalRecord {
+    Operation {
+        operation: String,
+        changes: Vec<TodoChange>,
+    },
+    Undo {
+        target: u64,
+    },
+    Redo {
+        target: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub record: JournalRecord,
+}
+
+/// Append-only log of repository operations, used to undo and redo them.
+pub trait TodoJournal {
+    fn read_entries(&mut self) -> Result<Vec<JournalEntry>, TodoErrors>;
+    /// Appends `record` with the next sequence number and returns the stored entry.
+    fn append(&mut self, record: JournalRecord) -> Result<JournalEntry, TodoErrors>;
+}
+
+/// One operation in the journal and whether it is currently undone.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub operation: String,
+    pub changes: Vec<TodoChange>,
+    pub undone: bool,
+}
+
+/// The operations that `undo` and `redo` would act on next, rebuilt by replaying the journal.
+#[derive(Debug, Default)]
+pub struct JournalState {
+    operations: Vec<HistoryEntry>,
+    applied: Vec<u64>,
+    undone: Vec<u64>,
+}
+
+impl JournalState {
+    pub fn replay(entries: Vec<JournalEntry>) -> Self {
+        let mut state = Self::default();
+        for entry in entries {
+            match entry.record {
+                JournalRecord::Operation { operation, changes } => {
+                    state.applied.push(entry.seq);
+                    state.undone.clear();
+                    state.operations.push(HistoryEntry {
+                        seq: entry.seq,
+                        timestamp: entry.timestamp,
+                        operation,
+                        changes,
+                        undone: false,
+                    });
+                }
+                JournalRecord::Undo { target } => {
+                    if state.applied.last() == Some(&target) {
+                        state.applied.pop();
+                        state.undone.push(target);
+                    }
+                }
+                JournalRecord::Redo { target } => {
+                    if state.undone.last() == Some(&target) {
+                        state.undone.pop();
+                        state.applied.push(target);
+                    }
+                }
+            }
+        }
+        for operation in state.operations.iter_mut() {
+            operation.undone = state.undone.contains(&operation.seq);
+        }
+        state
+    }
+
+    pub fn next_undo(&self) -> Option<&HistoryEntry> {
+        self.applied.last().and_then(|seq| self.find(*seq))
+    }
+
+    pub fn next_redo(&self) -> Option<&HistoryEntry> {
+        self.undone.last().and_then(|seq| self.find(*seq))
+    }
+
+    /// Operations newest first.
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        self.operations.iter().rev().cloned().collect()
+    }
+
+    fn find(&self, seq: u64) -> Option<&HistoryEntry> {
+        self.operations
+            .iter()
+            .find(|operation| operation.seq == seq)
+    }
+}
+
+/// Storage wrapper used inside a write transaction that remembers every change it passes on,
+/// keeping only the first `before` and last `after` per todo.
+pub struct RecordingStorage<'a, S: TodoStorage> {
+    storage: &'a mut S,
+    changes: Vec<TodoChange>,
+}
+
+impl<'a, S: TodoStorage> RecordingStorage<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        Self {
+            storage,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn into_changes(self) -> Vec<TodoChange> {
+        self.changes
+            .into_iter()
+            .filter(|change| change.before != change.after)
+            .collect()
+    }
+
+    fn record(&mut self, before: Option<Todo>, after: Option<Todo>) {
+        let change = TodoChange { before, after };
+        let todo_id = String::from(change.todo_id());
+        match self
+            .changes
+            .iter_mut()
+            .find(|existing| existing.todo_id() == todo_id)
+        {
+            Some(existing) => existing.after = change.after,
+            None => self.changes.push(change),
+        }
+    }
+}
+
+impl<S: TodoStorage> TodoStorage for RecordingStorage<'_, S> {
+    fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        self.storage.get(todo_id)
+    }
+
+    fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        self.storage.insert(todo)?;
+        self.record(None, Some(todo.clone()));
+        Ok(())
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        let before = self.storage.get(&todo.id)?;
+        self.storage.update(todo)?;
+        self.record(before, Some(todo.clone()));
+        Ok(())
+    }
+
+    fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        let removed = self.storage.remove(todo_id)?;
+        if let Some(todo) = &removed {
+            self.record(Some(todo.clone()), None);
+        }
+        Ok(removed)
+    }
+
+    fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors> {
+        self.storage.scan(filter)
+    }
+
+    fn default_list(&mut self) -> Result<Option<String>, TodoErrors> {
+        self.storage.default_list()
+    }
+
+    // Only todo changes are journaled; the default list is a setting.
+    fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+        self.storage.set_default_list(list)
+    }
+}
+
+/// Journal stored as one JSON entry per line in a file next to the data file.
+///
+/// Appends read only the last line. Every `retained_entries` appends the file is cut down to
+/// the newest `retained_entries` entries, so it holds at most twice that many.
+pub struct FileJournal {
+    file_path: String,
+    retained_entries: usize,
+}
+
+impl FileJournal {
+    pub fn new(file_path: String) -> Self {
+        Self {
+            file_path,
+            retained_entries: DEFAULT_RETAINED_ENTRIES,
+        }
+    }
+
+    pub fn with_retention(mut self, retained_entries: usize) -> Self {
+        self.retained_entries = retained_entries.max(1);
+        self
+    }
+
+    /// Sequence number of the last entry, 0 for an empty journal.
+    fn last_seq(file: &mut File) -> Result<u64, TodoErrors> {
+        #[derive(Deserialize)]
+        struct Seq {
+            seq: u64,
+        }
+
+        let mut position = file.seek(SeekFrom::End(0))?;
+        let mut tail = Vec::new();
+        let line_start = loop {
+            let content = tail.trim_ascii_end();
+            if let Some(newline) = content.iter().rposition(|byte| *byte == b'\n') {
+                break newline + 1;
+            }
+            if position == 0 {
+                break 0;
+            }
+            let chunk_size = position.min(TAIL_CHUNK);
+            position -= chunk_size;
+            file.seek(SeekFrom::Start(position))?;
+            let mut chunk = vec![0; chunk_size as usize];
+            file.read_exact(&mut chunk)?;
+            chunk.extend_from_slice(&tail);
+            tail = chunk;
+        };
+        let line = tail[line_start..].trim_ascii();
+        if line.is_empty() {
+            return Ok(0);
+        }
+        let last: Seq = serde_json::from_slice(line)
+            .map_err(|e| TodoErrors::parse_error(format!("Invalid last journal entry: {}", e)))?;
+        Ok(last.seq)
+    }
+
+    /// Rewrites the locked `file` with only its newest `retained_entries` entries.
+    fn compact(&self, file: &mut File) -> Result<(), TodoErrors> {
+        file.seek(SeekFrom::Start(0))?;
+        let entries = Self::read_from(file)?;
+        let dropped = entries.len().saturating_sub(self.retained_entries);
+        if dropped == 0 {
+            return Ok(());
+        }
+        let mut retained = String::new();
+        for entry in &entries[dropped..] {
+            retained.push_str(&to_string(entry)?);
+            retained.push('\n');
+        }
+        file.set_len(0)?;
+        file.write_all(retained.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    fn read_from(file: &File) -> Result<Vec<JournalEntry>, TodoErrors> {
+        let mut entries = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // Positions from serde refer to the single line, so the file line is put in.
+            let entry = from_str(&line).map_err(|e| match TodoErrors::from(e) {
+                TodoErrors::ParseError {
+                    message, column, ..
+                } => TodoErrors::ParseError {
+                    message: format!("Invalid journal entry: {}", message),
+                    line: Some(index + 1),
+                    column,
+                },
+                other => other,
+            })?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+impl TodoJournal for FileJournal {
+    fn read_entries(&mut self) -> Result<Vec<JournalEntry>, TodoErrors> {
+        match File::open(&self.file_path) {
+            Ok(file) => {
+                file.lock_shared()?;
+                Self::read_from(&file)
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(TodoErrors::from(e)),
+        }
+    }
+
+    fn append(&mut self, record: JournalRecord) -> Result<JournalEntry, TodoErrors> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.file_path)?;
+        // Held while the next sequence number is picked so concurrent appends never share one.
+        file.lock()?;
+        let entry = JournalEntry {
+            seq: Self::last_seq(&mut file)? + 1,
+            timestamp: Utc::now(),
+            record,
+        };
+        let line = to_string(&entry)?;
+        writeln!(file, "{}", line)?;
+        file.sync_all()?;
+        if entry.seq.is_multiple_of(self.retained_entries as u64) {
+            self.compact(&mut file)?;
+        }
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryJournal {
+    pub entries: Vec<JournalEntry>,
+    /// Makes every append fail, as a full disk would.
+    pub fail_appends: bool,
+}
+
+#[cfg(test)]
+impl TodoJournal for MemoryJournal {
+    fn read_entries(&mut self) -> Result<Vec<JournalEntry>, TodoErrors> {
+        Ok(self.entries.clone())
+    }
+
+    fn append(&mut self, record: JournalRecord) -> Result<JournalEntry, TodoErrors> {
+        if self.fail_appends {
+            return Err(TodoErrors::Io(String::from("journal is not writable")));
+        }
+        let entry = JournalEntry {
+            seq: self.entries.len() as u64 + 1,
+            timestamp: Utc::now(),
+            record,
+        };
+        self.entries.push(entry.clone());
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation(name: &str) -> JournalRecord {
+        JournalRecord::Operation {
+            operation: String::from(name),
+            changes: Vec::new(),
+        }
+    }
+
+    fn replay(records: Vec<JournalRecord>) -> JournalState {
+        let mut journal = MemoryJournal::default();
+        records.into_iter().for_each(|record| {
+            journal.append(record).unwrap();
+        });
+        JournalState::replay(journal.read_entries().unwrap())
+    }
+
+    #[test]
+    fn should_undo_latest_operations_first() {
+        let state = replay(vec![
+            operation("add"),
+            operation("edit"),
+            JournalRecord::Undo { target: 2 },
+        ]);
+        assert_eq!(Some(1), state.next_undo().map(|entry| entry.seq));
+        assert_eq!(Some(2), state.next_redo().map(|entry| entry.seq));
+        assert_eq!(
+            vec![(2, true), (1, false)],
+            state
+                .history()
+                .iter()
+                .map(|entry| (entry.seq, entry.undone))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn should_drop_redo_stack_after_new_operation() {
+        let state = replay(vec![
+            operation("add"),
+            JournalRecord::Undo { target: 1 },
+            operation("add"),
+        ]);
+        assert_eq!(None, state.next_redo());
+        assert_eq!(Some(3), state.next_undo().map(|entry| entry.seq));
+    }
+
+    #[test]
+    fn should_append_and_read_file_journal_with_increasing_seq() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("todo.json.journal");
+        let mut journal = FileJournal::new(String::from(path.to_str().unwrap()));
+        assert_eq!(Vec::<JournalEntry>::new(), journal.read_entries().unwrap());
+
+        journal.append(operation("add")).unwrap();
+        journal.append(JournalRecord::Undo { target: 1 }).unwrap();
+
+        let mut reopened = FileJournal::new(String::from(path.to_str().unwrap()));
+        let entries = reopened.read_entries().unwrap();
+        assert_eq!(
+            vec![1, 2],
+            entries.iter().map(|e| e.seq).collect::<Vec<_>>()
+        );
+        assert_eq!(JournalRecord::Undo { target: 1 }, entries[1].record);
+    }
+
+    #[test]
+    fn should_continue_seq_after_blank_lines_and_long_entries() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("todo.json.journal");
+        let mut journal = FileJournal::new(String::from(path.to_str().unwrap()));
+        let long_name = "x".repeat(3 * TAIL_CHUNK as usize);
+        journal.append(operation(&long_name)).unwrap();
+        journal.append(operation(&long_name)).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"\n\n")
+            .unwrap();
+
+        assert_eq!(3, journal.append(operation("add")).unwrap().seq);
+    }
+
+    #[test]
+    fn should_keep_only_newest_entries_of_file_journal() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("todo.json.journal");
+        let mut journal = FileJournal::new(String::from(path.to_str().unwrap())).with_retention(3);
+
+        for _ in 0..7 {
+            journal.append(operation("add")).unwrap();
+        }
+
+        let entries = journal.read_entries().unwrap();
+        assert_eq!(
+            vec![4, 5, 6, 7],
+            entries.iter().map(|e| e.seq).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(7),
+            JournalState::replay(entries).next_undo().map(|e| e.seq)
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use crate::todo_repo::Todo;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The list todos belong to when none was ever chosen, and the default list until another
+/// one is set.
+pub const DEFAULT_LIST: &str = "inbox";
+
+/// How many todos outside the trash a list holds.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListSummary {
+    pub name: String,
+    pub pending: usize,
+    pub done: usize,
+    /// Set on the list used when no `--list` is given.
+    pub default: bool,
+}
+
+/// One summary per list among `todos`, sorted by name. `default_list` is included even
+/// while it holds no todos.
+pub(crate) fn summarize(todos: &[Todo], default_list: &str) -> Vec<ListSummary> {
+    let mut lists = BTreeMap::<&str, ListSummary>::new();
+    lists.insert(default_list, ListSummary::default());
+    for todo in todos {
+        let summary = lists.entry(todo.list_name()).or_default();
+        if todo.completed {
+            summary.done += 1;
+        } else {
+            summary.pending += 1;
+        }
+    }
+    lists
+        .into_iter()
+        .map(|(name, summary)| ListSummary {
+            name: String::from(name),
+            default: name == default_list,
+            ..summary
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(list: Option<&str>, completed: bool) -> Todo {
+        Todo {
+            list: list.map(String::from),
+            completed,
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn should_count_todos_per_list_and_keep_empty_default() {
+        let todos = [
+            todo(Some("work"), false),
+            todo(None, true),
+            todo(Some("work"), true),
+            todo(Some("inbox"), false),
+        ];
+        let summary = |name: &str, pending, done, default| ListSummary {
+            name: String::from(name),
+            pending,
+            done,
+            default,
+        };
+        assert_eq!(
+            vec![summary("inbox", 1, 1, false), summary("work", 1, 1, true)],
+            summarize(&todos, "work")
+        );
+        assert_eq!(
+            vec![
+                summary("home", 0, 0, true),
+                summary("inbox", 1, 1, false),
+                summary("work", 1, 1, false)
+            ],
+            summarize(&todos, "home")
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use crate::todo_repo::storage::JsonDocument;
+use crate::todo_repo::{Todo, TodoErrors, TodoFilter};
+use serde::Serialize;
+use serde_json::{Map, Value, from_value, to_value};
+use std::collections::HashSet;
+
+/// Which copy of a data file a merged value was taken from.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeSide {
+    Ours,
+    Theirs,
+}
+
+/// A field both copies changed to different values. The value of the todo updated last is
+/// kept; a field of `removed` means one copy deleted the todo while the other changed it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    pub id: String,
+    pub name: String,
+    pub field: String,
+    pub ours: Value,
+    pub theirs: Value,
+    pub kept: MergeSide,
+}
+
+/// Merges two JSON data files that both started from `base`, matching todos by id.
+///
+/// Fields changed on one side only are taken from that side. When both sides changed a field,
+/// the side whose todo has the later `updated_at` wins, ours on a tie, and the clash is
+/// reported. Todos keep the order of `ours`, followed by the ones only `theirs` has.
+pub fn merge_documents(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+) -> Result<(String, Vec<MergeConflict>), TodoErrors> {
+    let base = JsonDocument::parse(base)?;
+    let ours = JsonDocument::parse(ours)?;
+    let theirs = JsonDocument::parse(theirs)?;
+    let our_todos = ours.scan(&TodoFilter::all());
+    let their_todos = theirs.scan(&TodoFilter::all());
+
+    let mut conflicts = Vec::new();
+    let mut merged = Vec::new();
+    let our_ids = our_todos
+        .iter()
+        .map(|todo| todo.id.as_str())
+        .collect::<HashSet<_>>();
+    for our_todo in &our_todos {
+        let base_todo = base.get(&our_todo.id);
+        match theirs.get(&our_todo.id) {
+            Some(their_todo) => merged.push(merge_todo(
+                base_todo.as_ref(),
+                our_todo,
+                &their_todo,
+                &mut conflicts,
+            )?),
+            None => {
+                if let Some(kept) =
+                    merge_removed(base_todo, our_todo, MergeSide::Ours, &mut conflicts)
+                {
+                    merged.push(kept);
+                }
+            }
+        }
+    }
+    for their_todo in their_todos
+        .iter()
+        .filter(|todo| !our_ids.contains(todo.id.as_str()))
+    {
+        if let Some(kept) = merge_removed(
+            base.get(&their_todo.id),
+            their_todo,
+            MergeSide::Theirs,
+            &mut conflicts,
+        ) {
+            merged.push(kept);
+        }
+    }
+
+    // A default list both sides changed keeps ours; it is a preference rather than a todo.
+    let default_list = if ours.default_list == base.default_list {
+        theirs.default_list
+    } else {
+        ours.default_list
+    };
+    let document = JsonDocument::new(merged, default_list).to_json()?;
+    Ok((document, conflicts))
+}
+
+/// Keeps a todo only one side still has if that side changed it since `base`.
+/// A todo without a base copy was added on that side and is kept as well.
+fn merge_removed(
+    base: Option<Todo>,
+    todo: &Todo,
+    side: MergeSide,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<Todo> {
+    let base = match base {
+        Some(base) => base,
+        None => return Some(todo.clone()),
+    };
+    if base == *todo {
+        return None;
+    }
+    let (ours, theirs) = match side {
+        MergeSide::Ours => (false, true),
+        MergeSide::Theirs => (true, false),
+    };
+    conflicts.push(MergeConflict {
+        id: todo.id.clone(),
+        name: todo.name.clone(),
+        field: String::from("removed"),
+        ours: Value::Bool(ours),
+        theirs: Value::Bool(theirs),
+        kept: side,
+    });
+    Some(todo.clone())
+}
+
+fn merge_todo(
+    base: Option<&Todo>,
+    ours: &Todo,
+    theirs: &Todo,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Result<Todo, TodoErrors> {
+    if ours == theirs {
+        return Ok(ours.clone());
+    }
+    let winner = match theirs.updated_at > ours.updated_at {
+        true => MergeSide::Theirs,
+        false => MergeSide::Ours,
+    };
+    let base_fields = match base {
+        Some(base) => to_fields(base)?,
+        None => Map::new(),
+    };
+    let our_fields = to_fields(ours)?;
+    let their_fields = to_fields(theirs)?;
+
+    let mut merged = Map::new();
+    for (field, our_value) in &our_fields {
+        let their_value = their_fields.get(field).unwrap_or(&Value::Null);
+        let base_value = base_fields.get(field);
+        let value = if our_value == their_value || field == "updated_at" {
+            our_value.clone()
+        } else if base_value == Some(our_value) {
+            their_value.clone()
+        } else if base_value == Some(their_value) {
+            our_value.clone()
+        } else {
+            conflicts.push(MergeConflict {
+                id: ours.id.clone(),
+                name: ours.name.clone(),
+                field: field.clone(),
+                ours: our_value.clone(),
+                theirs: their_value.clone(),
+                kept: winner,
+            });
+            match winner {
+                MergeSide::Ours => our_value.clone(),
+                MergeSide::Theirs => their_value.clone(),
+            }
+        };
+        merged.insert(field.clone(), value);
+    }
+    let mut todo: Todo = from_value(Value::Object(merged))?;
+    todo.updated_at = ours.updated_at.max(theirs.updated_at);
+    Ok(todo)
+}
+
+fn to_fields(todo: &Todo) -> Result<Map<String, Value>, TodoErrors> {
+    match to_value(todo)? {
+        Value::Object(fields) => Ok(fields),
+        _ => unreachable!("a todo serializes to an object"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    fn todo(id: &str, name: &str, updated_hour: u32) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(name),
+            description: String::from(""),
+            updated_at: Some(
+                Utc.with_ymd_and_hms(2025, 1, 1, updated_hour, 0, 0)
+                    .unwrap(),
+            ),
+            ..Todo::default()
+        }
+    }
+
+    fn document(todos: &[Todo]) -> String {
+        JsonDocument::new(todos.to_vec(), None).to_json().unwrap()
+    }
+
+    fn merged_todos(merged: &str) -> Vec<Todo> {
+        JsonDocument::parse(merged)
+            .unwrap()
+            .scan(&TodoFilter::all())
+    }
+
+    #[test]
+    fn should_combine_fields_changed_on_different_sides() {
+        let base = todo("a", "Milk", 1);
+        let mut ours = base.clone();
+        ours.completed = true;
+        ours.updated_at = todo("a", "", 2).updated_at;
+        let mut theirs = base.clone();
+        theirs.description = String::from("Oat");
+        theirs.updated_at = todo("a", "", 3).updated_at;
+
+        let (merged, conflicts) =
+            merge_documents(&document(&[base]), &document(&[ours]), &document(&[theirs])).unwrap();
+
+        assert!(conflicts.is_empty());
+        let merged = merged_todos(&merged);
+        assert_eq!(1, merged.len());
+        assert!(merged[0].completed);
+        assert_eq!("Oat", merged[0].description);
+        assert_eq!(todo("a", "", 3).updated_at, merged[0].updated_at);
+    }
+
+    #[test]
+    fn should_keep_newer_value_and_report_conflicting_field() {
+        let base = todo("a", "Milk", 1);
+        let ours = todo("a", "Whole milk", 3);
+        let theirs = todo("a", "Oat milk", 2);
+
+        let (merged, conflicts) =
+            merge_documents(&document(&[base]), &document(&[ours]), &document(&[theirs])).unwrap();
+
+        assert_eq!("Whole milk", merged_todos(&merged)[0].name);
+        assert_eq!(
+            vec![MergeConflict {
+                id: String::from("a"),
+                name: String::from("Whole milk"),
+                field: String::from("name"),
+                ours: json!("Whole milk"),
+                theirs: json!("Oat milk"),
+                kept: MergeSide::Ours,
+            }],
+            conflicts
+        );
+
+        let (merged, conflicts) = merge_documents(
+            &document(&[todo("a", "Milk", 1)]),
+            &document(&[todo("a", "Whole milk", 2)]),
+            &document(&[todo("a", "Oat milk", 3)]),
+        )
+        .unwrap();
+        assert_eq!("Oat milk", merged_todos(&merged)[0].name);
+        assert_eq!(MergeSide::Theirs, conflicts[0].kept);
+    }
+
+    #[test]
+    fn should_add_and_remove_todos_from_both_sides() {
+        let milk = todo("a", "Milk", 1);
+        let bread = todo("b", "Bread", 1);
+        let eggs = todo("c", "Eggs", 2);
+        let jam = todo("d", "Jam", 2);
+
+        let (merged, conflicts) = merge_documents(
+            &document(&[milk.clone(), bread.clone()]),
+            &document(&[milk.clone(), eggs.clone()]),
+            &document(&[bread, jam.clone()]),
+        )
+        .unwrap();
+
+        assert!(conflicts.is_empty());
+        assert_eq!(vec![eggs, jam], merged_todos(&merged));
+    }
+
+    #[test]
+    fn should_keep_todo_changed_on_one_side_and_removed_on_the_other() {
+        let base = todo("a", "Milk", 1);
+        let theirs = todo("a", "Oat milk", 2);
+
+        let (merged, conflicts) = merge_documents(
+            &document(&[base]),
+            "",
+            &document(std::slice::from_ref(&theirs)),
+        )
+        .unwrap();
+
+        assert_eq!(vec![theirs], merged_todos(&merged));
+        assert_eq!(1, conflicts.len());
+        assert_eq!("removed", conflicts[0].field);
+        assert_eq!(json!(true), conflicts[0].ours);
+        assert_eq!(MergeSide::Theirs, conflicts[0].kept);
+    }
+
+    #[test]
+    fn should_merge_default_list() {
+        let with_default = |list: Option<&str>| {
+            JsonDocument::new(Vec::new(), list.map(String::from))
+                .to_json()
+                .unwrap()
+        };
+
+        let (merged, _) = merge_documents(
+            &with_default(None),
+            &with_default(None),
+            &with_default(Some("work")),
+        )
+        .unwrap();
+        assert_eq!(
+            Some(String::from("work")),
+            JsonDocument::parse(&merged).unwrap().default_list
+        );
+
+        let (merged, _) = merge_documents(
+            &with_default(None),
+            &with_default(Some("home")),
+            &with_default(Some("work")),
+        )
+        .unwrap();
+        assert_eq!(
+            Some(String::from("home")),
+            JsonDocument::parse(&merged).unwrap().default_list
+        );
+    }
+
+    #[test]
+    fn should_report_parse_error_of_either_side() {
+        let result = merge_documents("", "[]", "{");
+        assert!(matches!(result, Err(TodoErrors::ParseError { .. })));
+    }
+}
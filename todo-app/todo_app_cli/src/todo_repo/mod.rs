@@ -1,119 +1,662 @@
+mod bulk;
 mod data_access;
+mod dependencies;
+mod errors;
+mod hierarchy;
+mod journal;
+mod lists;
+mod merge;
+mod query;
+mod recurrence;
+mod remote;
+mod search;
+mod sqlite;
+mod storage;
 
-use crate::cli::AddCommandArgs;
-pub use data_access::{FileDataAccess, TodoDataAccess};
+use crate::cli::{AddCommandArgs, EditCommandArgs};
+pub use bulk::{BulkReport, TodoSelection};
+use chrono::{DateTime, Duration, Utc};
+use clap::ValueEnum;
+pub use data_access::FileDataAccess;
+pub use dependencies::open_blockers;
+pub use errors::TodoErrors;
+use hierarchy::SubtaskRule;
+pub use hierarchy::{SubtaskProgress, subtask_progress};
+pub use journal::{FileJournal, HistoryEntry, JournalRecord, TodoChange, TodoJournal};
+use journal::{JournalState, RecordingStorage};
+pub use lists::{DEFAULT_LIST, ListSummary};
+pub use merge::{MergeConflict, MergeSide, merge_documents};
 use nanoid::nanoid;
+pub use query::{TodoQuery, TodoSortKey, TodoStatus};
+pub use recurrence::Recurrence;
+pub use remote::{RemoteChanges, RemoteDataAccess};
+pub use search::{SearchField, SearchHit, SearchMatch, SearchQuery};
 use serde::{Deserialize, Serialize};
-use serde_json::{error::Error, from_str, to_string_pretty};
+pub use sqlite::SqliteDataAccess;
+use std::collections::HashMap;
+use storage::JsonDocument;
+pub use storage::{TodoFilter, TodoStorage};
 
 const ID_LENGTH: usize = 7;
+/// Stands for the most recently added todo wherever an id is expected.
+pub const LAST_REFERENCE: &str = "@last";
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Todo {
     pub id: String,
     pub name: String,
     pub description: String,
     pub completed: bool,
+    // Fields below were added after the first release; `serde(default)` lets older files load.
+    #[serde(default)]
+    pub due: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Set while the todo is in the trash.
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Completing a todo with a recurrence adds its next occurrence.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Id of the todo this is a subtask of.
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Ids of the todos that have to be done before this one can be completed.
+    #[serde(default)]
+    pub blocked_by: Vec<String>,
+    /// Name of the list the todo belongs to; todos without one are in [`DEFAULT_LIST`].
+    #[serde(default)]
+    pub list: Option<String>,
 }
 
-#[derive(Debug, PartialEq)]
-pub enum TodoErrors {
-    TodoGetError(String),
-    TodoSaveError(String),
+impl Todo {
+    pub fn list_name(&self) -> &str {
+        self.list.as_deref().unwrap_or(DEFAULT_LIST)
+    }
+}
+
+#[derive(Serialize, Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+    Urgent,
 }
 
-impl TodoErrors {
-    pub fn error_message(&self) -> &str {
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
         match self {
-            TodoErrors::TodoGetError(msg) => msg,
-            TodoErrors::TodoSaveError(msg) => msg,
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        <Priority as ValueEnum>::from_str(value, true).ok()
+    }
+}
+
+/// Trims tags, dropping empty and duplicate entries while keeping their first-seen order.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized = Vec::<String>::new();
+    for tag in tags.iter().map(|tag| tag.trim()) {
+        if !tag.is_empty() && !normalized.iter().any(|existing| existing == tag) {
+            normalized.push(String::from(tag));
         }
     }
+    normalized
 }
 
-pub struct TodoRepository<D: TodoDataAccess> {
-    data_access: D,
+pub struct TodoRepository<S: TodoStorage> {
+    storage: S,
+    journal: Option<Box<dyn TodoJournal>>,
+    list: Option<String>,
+    default_list: Option<String>,
 }
 
-impl<D: TodoDataAccess> TodoRepository<D> {
-    pub fn new(data_access: D) -> Self {
-        Self { data_access }
+impl<S: TodoStorage> TodoRepository<S> {
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            journal: None,
+            list: None,
+            default_list: None,
+        }
     }
 
-    fn load_all(&mut self) -> Result<Vec<Todo>, TodoErrors> {
-        let input_result = self.data_access.read_all();
-        let input = match input_result {
-            Err(e) => return Err(TodoErrors::TodoGetError(String::from(e.error_message()))),
-            Ok(input) => input,
-        };
+    /// Records every mutation in `journal`, which enables `undo`, `redo` and `history`.
+    pub fn with_journal(mut self, journal: Box<dyn TodoJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Works on the list named `list` instead of the default one.
+    pub fn with_list(mut self, list: String) -> Self {
+        self.list = Some(list);
+        self
+    }
+
+    /// Uses `list` as the default list, ahead of the one stored with the todos.
+    pub fn with_default_list(mut self, list: String) -> Self {
+        self.default_list = Some(list);
+        self
+    }
 
-        if input.is_empty() {
-            return Ok(Vec::new());
+    /// The list new todos go into and that listings and filters are limited to: the one
+    /// given to `with_list`, else the default list. Commands that take ids reach todos in
+    /// every list.
+    pub fn current_list(&mut self) -> Result<String, TodoErrors> {
+        match &self.list {
+            Some(list) => Ok(list.clone()),
+            None => self.default_list(),
         }
+    }
 
-        let todos_parse_result: Result<Vec<Todo>, Error> = from_str(&input);
-        match todos_parse_result {
-            Ok(todos) => Ok(todos),
-            Err(parse_error) => Err(TodoErrors::TodoGetError(parse_error.to_string())),
+    /// The list given to `with_default_list`, else the one stored with the todos, else
+    /// [`DEFAULT_LIST`].
+    pub fn default_list(&mut self) -> Result<String, TodoErrors> {
+        match &self.default_list {
+            Some(list) => Ok(list.clone()),
+            None => Ok(self
+                .storage
+                .default_list()?
+                .unwrap_or_else(|| String::from(DEFAULT_LIST))),
         }
     }
 
-    fn save_all(&mut self, all_todos: Vec<Todo>) -> Result<(), TodoErrors> {
-        let output_result = to_string_pretty(&all_todos);
-        let output = match output_result {
-            Ok(output) => output,
-            Err(pretty_print_error) => {
-                return Err(TodoErrors::TodoSaveError(pretty_print_error.to_string()));
+    // `filter` limited to the current list.
+    fn in_current_list(&mut self, filter: TodoFilter) -> Result<TodoFilter, TodoErrors> {
+        Ok(TodoFilter {
+            list: Some(self.current_list()?),
+            ..filter
+        })
+    }
+
+    fn not_found(todo_id: &str) -> TodoErrors {
+        TodoErrors::NotFound(format!("Todo by id:{} not found", todo_id))
+    }
+
+    /// Looks up a todo outside the trash; trashed todos are reported as not found.
+    fn find_active<T: TodoStorage>(storage: &mut T, todo_id: &str) -> Result<Todo, TodoErrors> {
+        storage
+            .get(todo_id)?
+            .filter(|todo| todo.deleted_at.is_none())
+            .ok_or_else(|| Self::not_found(todo_id))
+    }
+
+    /// The id `reference` stands for, `None` when it matches no todo. In order of precedence,
+    /// a reference is:
+    ///
+    /// - the id of a todo, in the trash or not;
+    /// - `@last`, the most recently added todo of `list` outside the trash;
+    /// - a number, the 1-based position of a todo of `list` outside the trash, as `get all`
+    ///   shows it;
+    /// - the start of exactly one id of a todo outside the trash, in any list. Starts shared by
+    ///   several ids are refused with the candidates.
+    fn resolve_reference<T: TodoStorage>(
+        storage: &mut T,
+        reference: &str,
+        list: &str,
+    ) -> Result<Option<String>, TodoErrors> {
+        if reference.is_empty() {
+            return Ok(None);
+        }
+        if storage.get(reference)?.is_some() {
+            return Ok(Some(String::from(reference)));
+        }
+        let list_filter = TodoFilter {
+            list: Some(String::from(list)),
+            ..TodoFilter::active()
+        };
+        if reference == LAST_REFERENCE {
+            // `max_by_key` keeps the last of equals, so stored order breaks ties.
+            let last = storage
+                .scan(&list_filter)?
+                .into_iter()
+                .max_by_key(|todo| todo.created_at);
+            return Ok(last.map(|todo| todo.id));
+        }
+        if let Ok(index) = reference.parse::<usize>() {
+            let todos = storage.scan(&list_filter)?;
+            if let Some(todo) = index
+                .checked_sub(1)
+                .and_then(|position| todos.get(position))
+            {
+                return Ok(Some(todo.id.clone()));
             }
+        }
+
+        let mut candidates = storage.scan(&TodoFilter::active())?;
+        candidates.retain(|todo| todo.id.starts_with(reference));
+        match candidates.as_slice() {
+            [] => Ok(None),
+            [todo] => Ok(Some(todo.id.clone())),
+            _ => Err(TodoErrors::Validation(format!(
+                "Todo id prefix {} is ambiguous, it matches {}",
+                reference,
+                candidates
+                    .iter()
+                    .map(|todo| format!("{} ({})", todo.id, todo.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
+    /// Resolves `selection` to ids, dropping blank and repeated ones. Filters only match
+    /// todos in `list`.
+    fn selected_ids<T: TodoStorage>(
+        storage: &mut T,
+        selection: &TodoSelection,
+        list: &str,
+    ) -> Result<Vec<String>, TodoErrors> {
+        let ids: Vec<String> = match selection {
+            TodoSelection::Ids(ids) => ids.iter().map(|id| String::from(id.trim())).collect(),
+            TodoSelection::Matching(query) => query
+                .apply(storage.scan(&TodoFilter {
+                    list: Some(String::from(list)),
+                    ..query.storage_filter()
+                })?)
+                .into_iter()
+                .map(|todo| todo.id)
+                .collect(),
         };
-        self.data_access.write_all(output)?;
-        Ok(())
+        let mut unique_ids = Vec::<String>::new();
+        for todo_id in ids {
+            if !todo_id.is_empty() && !unique_ids.contains(&todo_id) {
+                unique_ids.push(todo_id);
+            }
+        }
+        Ok(unique_ids)
     }
 
-    fn find_in_memory_todo(
+    /// Applies `change` to every selected todo in one transaction, reporting ids that are
+    /// missing or already in the trash instead of failing on them. A todo returned by
+    /// `change` is added alongside, as the next occurrence of a recurring todo is.
+    ///
+    /// Unfinished subtasks of the selected todos are changed too when `subtasks` cascades;
+    /// otherwise the whole operation fails, before changing anything, if any is left out.
+    /// `check` gets the todos outside the trash and the ids about to change, and can refuse
+    /// the operation the same way.
+    fn bulk_update(
         &mut self,
-        todos: &Vec<Todo>,
-        todo_id: &String,
-    ) -> Result<Todo, TodoErrors> {
-        match todos.iter().find(|todo| &todo.id == todo_id) {
-            Some(todo) => Ok(todo.clone()),
-            None => Err(TodoErrors::TodoGetError(format!(
-                "Todo by id:{} not found",
-                todo_id
-            ))),
+        operation_name: &str,
+        selection: &TodoSelection,
+        subtasks: SubtaskRule,
+        check: impl Fn(&[Todo], &[String]) -> Result<(), TodoErrors>,
+        change: impl Fn(&mut Todo) -> Result<Option<Todo>, TodoErrors>,
+    ) -> Result<BulkReport, TodoErrors> {
+        let list = self.current_list()?;
+        self.write_transaction(operation_name, |storage| {
+            let mut todo_ids = Self::selected_ids(storage, selection, &list)?;
+            let active = storage.scan(&TodoFilter::active())?;
+            Self::add_unfinished_subtasks(&active, &mut todo_ids, subtasks)?;
+            check(&active, &todo_ids)?;
+            let mut report = BulkReport::default();
+            for todo_id in todo_ids {
+                match Self::find_active(storage, &todo_id) {
+                    Ok(mut todo) => {
+                        let added = change(&mut todo)?;
+                        storage.update(&todo)?;
+                        if let Some(added) = added {
+                            storage.insert(&added)?;
+                            report.created.push(added);
+                        }
+                        report.succeeded.push(todo);
+                    }
+                    Err(TodoErrors::NotFound(_)) => report.not_found.push(todo_id),
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(report)
+        })
+    }
+
+    /// Appends the unfinished subtasks of the selected todos to `todo_ids` when `subtasks`
+    /// cascades, and otherwise fails if any of them is not selected as well.
+    fn add_unfinished_subtasks(
+        active: &[Todo],
+        todo_ids: &mut Vec<String>,
+        subtasks: SubtaskRule,
+    ) -> Result<(), TodoErrors> {
+        for todo_id in todo_ids.clone() {
+            let unfinished: Vec<&Todo> = hierarchy::descendants(active, &todo_id)
+                .into_iter()
+                .filter(|subtask| (subtasks.unfinished)(subtask) && !todo_ids.contains(&subtask.id))
+                .collect();
+            if subtasks.cascade {
+                todo_ids.extend(unfinished.iter().map(|subtask| subtask.id.clone()));
+            } else if !unfinished.is_empty() && active.iter().any(|todo| todo.id == todo_id) {
+                return Err(TodoErrors::Validation(format!(
+                    "Todo by id:{} has {} unfinished subtask(s), pass {} to include them",
+                    todo_id,
+                    unfinished.len(),
+                    subtasks.flag
+                )));
+            }
         }
+        Ok(())
     }
 
-    pub fn get_all_todos(&mut self) -> Result<Vec<Todo>, TodoErrors> {
-        self.load_all()
+    /// Fails if a pending todo in `todo_ids` waits on an open todo that is not completed along
+    /// with it.
+    fn refuse_open_blockers(active: &[Todo], todo_ids: &[String]) -> Result<(), TodoErrors> {
+        for todo in active
+            .iter()
+            .filter(|todo| !todo.completed && todo_ids.contains(&todo.id))
+        {
+            let open: Vec<&str> = dependencies::open_blockers(todo, active)
+                .into_iter()
+                .filter(|blocker| !todo_ids.contains(&blocker.id))
+                .map(|blocker| blocker.id.as_str())
+                .collect();
+            if !open.is_empty() {
+                return Err(TodoErrors::Validation(format!(
+                    "Todo by id:{} is blocked by open todo(s) {}, complete them first or pass --force",
+                    todo.id,
+                    open.join(",")
+                )));
+            }
+        }
+        Ok(())
     }
 
-    pub fn get_todo_by_id(&mut self, todo_id: String) -> Result<Todo, TodoErrors> {
-        let todos_result = self.load_all();
-        let todos = match todos_result {
-            Ok(todos) => todos,
-            Err(todo_error) => return Err(todo_error),
+    fn find_blocker<T: TodoStorage>(storage: &mut T, blocker_id: &str) -> Result<Todo, TodoErrors> {
+        Self::find_active(storage, blocker_id).map_err(|e| match e {
+            TodoErrors::NotFound(_) => {
+                TodoErrors::NotFound(format!("Blocking todo by id:{} not found", blocker_id))
+            }
+            e => e,
+        })
+    }
+
+    /// Looks up the todo a new subtask goes under; it must be outside the trash and pending.
+    fn find_parent<T: TodoStorage>(storage: &mut T, parent_id: &str) -> Result<Todo, TodoErrors> {
+        let parent = Self::find_active(storage, parent_id).map_err(|e| match e {
+            TodoErrors::NotFound(_) => {
+                TodoErrors::NotFound(format!("Parent todo by id:{} not found", parent_id))
+            }
+            e => e,
+        })?;
+        if parent.completed {
+            return Err(TodoErrors::Validation(format!(
+                "Parent todo by id:{} is completed, reopen it before adding subtasks",
+                parent_id
+            )));
+        }
+        Ok(parent)
+    }
+
+    /// Completes `todo` and returns its next occurrence when it recurs. Completing a todo
+    /// that is already done adds nothing, so a recurrence is only ever advanced once.
+    fn mark_completed(todo: &mut Todo) -> Result<Option<Todo>, TodoErrors> {
+        let now = Utc::now();
+        let was_pending = !todo.completed;
+        todo.completed = true;
+        todo.completed_at = Some(now);
+        todo.updated_at = Some(now);
+        let Some(recurrence) = todo.recurrence.as_ref().filter(|_| was_pending) else {
+            return Ok(None);
         };
+        let due = recurrence.next_due(todo.due, now).ok_or_else(|| {
+            TodoErrors::Validation(format!(
+                "Todo by id:{} repeats {}, its next occurrence is past the last date that can be stored",
+                todo.id, recurrence
+            ))
+        })?;
+        Ok(Some(Todo {
+            id: nanoid!(ID_LENGTH),
+            completed: false,
+            due: Some(due),
+            created_at: Some(now),
+            updated_at: Some(now),
+            completed_at: None,
+            ..todo.clone()
+        }))
+    }
 
-        self.find_in_memory_todo(&todos, &todo_id)
+    fn mark_trashed(todo: &mut Todo) {
+        let now = Utc::now();
+        todo.deleted_at = Some(now);
+        todo.updated_at = Some(now);
     }
 
-    pub fn get_todo_by_name(&mut self, todo_name: String) -> Result<Vec<Todo>, TodoErrors> {
-        let todos_result = self.load_all();
-        let todos = match todos_result {
-            Ok(todos) => todos,
-            Err(todo_error) => return Err(todo_error),
+    /// Runs `operation` while holding the storage write lock, committing only if it succeeds.
+    fn run_transaction<T>(
+        storage: &mut S,
+        operation: impl FnOnce(&mut S) -> Result<T, TodoErrors>,
+    ) -> Result<T, TodoErrors> {
+        storage.begin_write()?;
+        match operation(storage) {
+            Ok(value) => {
+                storage.commit()?;
+                Ok(value)
+            }
+            Err(e) => {
+                storage.rollback();
+                Err(e)
+            }
+        }
+    }
+
+    /// Like `run_transaction`, and journals the committed changes under `operation_name`.
+    fn write_transaction<T>(
+        &mut self,
+        operation_name: &str,
+        operation: impl FnOnce(&mut RecordingStorage<'_, S>) -> Result<T, TodoErrors>,
+    ) -> Result<T, TodoErrors> {
+        let (value, changes) = Self::run_transaction(&mut self.storage, |storage| {
+            let mut recording = RecordingStorage::new(storage);
+            let value = operation(&mut recording)?;
+            Ok((value, recording.into_changes()))
+        })?;
+        if !changes.is_empty() {
+            let record = JournalRecord::Operation {
+                operation: String::from(operation_name),
+                changes: changes.clone(),
+            };
+            let revert = changes
+                .iter()
+                .rev()
+                .map(|change| (&change.after, &change.before));
+            self.append_or_revert(record, revert)?;
+        }
+        Ok(value)
+    }
+
+    /// Journals `record` for changes that are already committed. The journal only learns of
+    /// changes that landed, so a failed commit leaves nothing for `undo` to trip over. When
+    /// the record cannot be appended, `revert` sets the todos back, so no change is kept out
+    /// of reach of `undo` either.
+    fn append_or_revert<'c>(
+        &mut self,
+        record: JournalRecord,
+        revert: impl Iterator<Item = (&'c Option<Todo>, &'c Option<Todo>)>,
+    ) -> Result<(), TodoErrors> {
+        let Some(journal) = self.journal.as_mut() else {
+            return Ok(());
         };
+        let Err(e) = journal.append(record) else {
+            return Ok(());
+        };
+        Self::run_transaction(&mut self.storage, |storage| {
+            Self::restore_changes(storage, "the journal write failed", revert)
+        })
+        .map_err(|revert_error| {
+            TodoErrors::Io(format!(
+                "{}, and the unjournaled change could not be reverted: {}",
+                e.error_message(),
+                revert_error.error_message()
+            ))
+        })?;
+        Err(e)
+    }
+
+    fn journal_state(&mut self) -> Result<JournalState, TodoErrors> {
+        let journal = self.journal.as_mut().ok_or_else(|| {
+            TodoErrors::Validation(String::from("No operation journal is configured"))
+        })?;
+        Ok(JournalState::replay(journal.read_entries()?))
+    }
+
+    /// Sets every todo in `changes` from its `expected` state to its `target` state, refusing if
+    /// any todo no longer matches `expected`, as it was changed after `since`.
+    fn restore_changes<'c, T: TodoStorage>(
+        storage: &mut T,
+        since: &str,
+        changes: impl Iterator<Item = (&'c Option<Todo>, &'c Option<Todo>)>,
+    ) -> Result<(), TodoErrors> {
+        for (expected, target) in changes {
+            let todo_id = expected
+                .as_ref()
+                .or(target.as_ref())
+                .map(|todo| todo.id.as_str())
+                .unwrap_or_default();
+            let current = storage.get(todo_id)?;
+            if &current != expected {
+                return Err(TodoErrors::Conflict(format!(
+                    "Todo by id:{} was changed after {}",
+                    todo_id, since
+                )));
+            }
+            match (current, target) {
+                (Some(_), Some(todo)) => storage.update(todo)?,
+                (None, Some(todo)) => storage.insert(todo)?,
+                (Some(_), None) => {
+                    storage.remove(todo_id)?;
+                }
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Reverses up to `count` of the most recent operations that are not already undone, newest
+    /// first, and returns them.
+    pub fn undo(&mut self, count: usize) -> Result<Vec<HistoryEntry>, TodoErrors> {
+        let mut undone = Vec::new();
+        for _ in 0..count {
+            let Some(entry) = self.journal_state()?.next_undo().cloned() else {
+                break;
+            };
+            Self::run_transaction(&mut self.storage, |storage| {
+                let changes = entry.changes.iter().rev();
+                Self::restore_changes(
+                    storage,
+                    &format!("operation #{}", entry.seq),
+                    changes.map(|change| (&change.after, &change.before)),
+                )
+            })?;
+            let redo = entry
+                .changes
+                .iter()
+                .map(|change| (&change.before, &change.after));
+            self.append_or_revert(JournalRecord::Undo { target: entry.seq }, redo)?;
+            undone.push(entry);
+        }
+        if undone.is_empty() {
+            return Err(TodoErrors::Validation(String::from("Nothing to undo")));
+        }
+        Ok(undone)
+    }
+
+    /// Reapplies up to `count` of the most recently undone operations and returns them.
+    pub fn redo(&mut self, count: usize) -> Result<Vec<HistoryEntry>, TodoErrors> {
+        let mut redone = Vec::new();
+        for _ in 0..count {
+            let Some(entry) = self.journal_state()?.next_redo().cloned() else {
+                break;
+            };
+            Self::run_transaction(&mut self.storage, |storage| {
+                let changes = entry.changes.iter();
+                Self::restore_changes(
+                    storage,
+                    &format!("operation #{}", entry.seq),
+                    changes.map(|change| (&change.before, &change.after)),
+                )
+            })?;
+            let undo = entry
+                .changes
+                .iter()
+                .rev()
+                .map(|change| (&change.after, &change.before));
+            self.append_or_revert(JournalRecord::Redo { target: entry.seq }, undo)?;
+            redone.push(entry);
+        }
+        if redone.is_empty() {
+            return Err(TodoErrors::Validation(String::from("Nothing to redo")));
+        }
+        Ok(redone)
+    }
+
+    /// Journaled operations, newest first.
+    pub fn history(&mut self, limit: Option<usize>) -> Result<Vec<HistoryEntry>, TodoErrors> {
+        let history = self.journal_state()?.history();
+        Ok(history
+            .into_iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    pub fn get_all_todos(&mut self) -> Result<Vec<Todo>, TodoErrors> {
+        let filter = self.in_current_list(TodoFilter::active())?;
+        self.storage.scan(&filter)
+    }
+
+    /// Subtask progress of every todo outside the trash that has subtasks, by id.
+    pub fn subtask_progress(&mut self) -> Result<HashMap<String, SubtaskProgress>, TodoErrors> {
+        Ok(subtask_progress(&self.get_all_todos()?))
+    }
 
-        let todo_name_lowercase = todo_name.to_lowercase();
-        let found_todos: Vec<Todo> = todos
+    /// The id of the todo `reference` stands for: an id, a unique id prefix, a position in
+    /// the current list or `@last`. Other methods take exact ids only, so callers that accept
+    /// references from a user resolve them here first.
+    pub fn resolve_id(&mut self, reference: &str) -> Result<String, TodoErrors> {
+        let list = self.current_list()?;
+        Self::resolve_reference(&mut self.storage, reference.trim(), &list)?
+            .ok_or_else(|| Self::not_found(reference))
+    }
+
+    /// Resolves each of `references` like [`Self::resolve_id`]. References matching no todo
+    /// are kept as given, so a bulk operation reports them as not found.
+    pub fn resolve_ids(&mut self, references: &[String]) -> Result<Vec<String>, TodoErrors> {
+        let list = self.current_list()?;
+        references
             .iter()
-            .filter(|todo| todo.name.to_lowercase().contains(&todo_name_lowercase))
-            .cloned()
-            .collect();
+            .map(|reference| {
+                let reference = reference.trim();
+                Ok(
+                    Self::resolve_reference(&mut self.storage, reference, &list)?
+                        .unwrap_or_else(|| String::from(reference)),
+                )
+            })
+            .collect()
+    }
+
+    pub fn get_todo_by_id(&mut self, todo_id: String) -> Result<Todo, TodoErrors> {
+        Self::find_active(&mut self.storage, &todo_id)
+    }
+
+    pub fn get_todo_by_name(&mut self, todo_name: String) -> Result<Vec<Todo>, TodoErrors> {
+        let filter = self.in_current_list(TodoFilter {
+            name_contains: Some(String::from(&todo_name)),
+            ..TodoFilter::active()
+        })?;
+        let found_todos = self.storage.scan(&filter)?;
 
         if found_todos.is_empty() {
-            Err(TodoErrors::TodoGetError(format!(
+            Err(TodoErrors::NotFound(format!(
                 "Todo by name: {} not found",
                 todo_name
             )))
@@ -122,55 +665,484 @@ impl<D: TodoDataAccess> TodoRepository<D> {
         }
     }
 
+    /// Todos outside the trash matching `query`, most relevant first.
+    pub fn search_todos(&mut self, query: &SearchQuery) -> Result<Vec<SearchHit>, TodoErrors> {
+        let todos = self.get_all_todos()?;
+        Ok(query.rank(todos))
+    }
+
+    pub fn query_todos(&mut self, query: &TodoQuery) -> Result<Vec<Todo>, TodoErrors> {
+        let filter = self.in_current_list(query.storage_filter())?;
+        Ok(query.apply(self.storage.scan(&filter)?))
+    }
+
+    /// The next `count` occurrences of every pending recurring todo, soonest first. The first
+    /// is the pending todo itself; later ones are projections that share its id.
+    pub fn upcoming_occurrences(&mut self, count: usize) -> Result<Vec<Todo>, TodoErrors> {
+        let now = Utc::now();
+        let filter = self.in_current_list(TodoFilter {
+            completed: Some(false),
+            ..TodoFilter::active()
+        })?;
+        let recurring = self.storage.scan(&filter)?;
+        let mut occurrences: Vec<Todo> = recurring
+            .into_iter()
+            .filter_map(|todo| Some((todo.recurrence.clone()?, todo)))
+            .flat_map(|(recurrence, todo)| {
+                // Occurrences past the last date that can be stored are left out.
+                std::iter::successors(Some(todo), move |previous| {
+                    Some(Todo {
+                        due: Some(recurrence.next_due(previous.due, now)?),
+                        ..previous.clone()
+                    })
+                })
+                .take(count)
+            })
+            .collect();
+        occurrences.sort_by_key(|todo| todo.due);
+        Ok(occurrences)
+    }
+
     pub fn add_todo(&mut self, add_command_args: &AddCommandArgs) -> Result<Todo, TodoErrors> {
-        let todos_result = self.load_all();
-        let mut todos = match todos_result {
-            Ok(todos) => todos,
-            Err(todo_error) => return Err(todo_error),
-        };
+        let mut added = self.add_todos(std::slice::from_ref(add_command_args))?;
+        Ok(added.remove(0))
+    }
 
-        let new_todo = Todo {
-            id: nanoid!(ID_LENGTH),
-            name: String::from(&add_command_args.name),
-            description: String::from(&add_command_args.description),
-            completed: false,
-        };
-        let todo_to_return = new_todo.clone();
-        todos.push(new_todo);
+    /// Adds every todo in one save; nothing is added if any of them is invalid. Todos go
+    /// into the current list, subtasks into the list of their parent.
+    pub fn add_todos(
+        &mut self,
+        add_command_args: &[AddCommandArgs],
+    ) -> Result<Vec<Todo>, TodoErrors> {
+        let now = Utc::now();
+        let list = self.current_list()?;
+        let mut new_todos = add_command_args
+            .iter()
+            .map(|args| {
+                if args.name.trim().is_empty() {
+                    return Err(TodoErrors::Validation(String::from(
+                        "Todo name cannot be empty",
+                    )));
+                }
+                Ok(Todo {
+                    id: nanoid!(ID_LENGTH),
+                    name: String::from(&args.name),
+                    description: String::from(&args.description),
+                    completed: false,
+                    due: args.due,
+                    priority: args.priority,
+                    tags: normalize_tags(&args.tags),
+                    created_at: Some(now),
+                    updated_at: Some(now),
+                    completed_at: None,
+                    deleted_at: None,
+                    recurrence: args.recurrence.clone(),
+                    parent_id: args.parent_id.clone(),
+                    blocked_by: normalize_tags(&args.blocked_by),
+                    list: Some(list.clone()),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.write_transaction("add", |storage| {
+            new_todos.iter_mut().try_for_each(|todo| {
+                if let Some(parent_id) = &todo.parent_id {
+                    let parent = Self::find_parent(storage, parent_id)?;
+                    todo.list = Some(String::from(parent.list_name()));
+                }
+                for blocker_id in &todo.blocked_by {
+                    Self::find_blocker(storage, blocker_id)?;
+                }
+                storage.insert(todo)
+            })
+        })?;
+        Ok(new_todos)
+    }
+
+    /// Adds `todos`, keeping each id unless it is blank or already taken, in which case a new
+    /// one is generated. Parent and blocker ids follow the todos they point to; ones that are
+    /// neither imported nor stored are dropped. Todos are imported into the current list.
+    /// With `dry_run` nothing is written. Returns the todos as they would be stored.
+    pub fn import_todos(
+        &mut self,
+        todos: Vec<Todo>,
+        dry_run: bool,
+    ) -> Result<Vec<Todo>, TodoErrors> {
+        let list = self.current_list()?;
+        if dry_run {
+            return Self::prepare_import(&mut self.storage, todos, &list);
+        }
+        self.write_transaction("import", |storage| {
+            let prepared = Self::prepare_import(storage, todos, &list)?;
+            for todo in &prepared {
+                storage.insert(todo)?;
+            }
+            Ok(prepared)
+        })
+    }
 
-        match self.save_all(todos) {
-            Ok(_) => Ok(todo_to_return),
-            Err(save_error) => Err(save_error),
+    fn prepare_import<T: TodoStorage>(
+        storage: &mut T,
+        todos: Vec<Todo>,
+        list: &str,
+    ) -> Result<Vec<Todo>, TodoErrors> {
+        let mut taken_ids: Vec<String> = storage
+            .scan(&TodoFilter::all())?
+            .into_iter()
+            .map(|todo| todo.id)
+            .collect();
+        let now = Utc::now();
+        let mut prepared = Vec::new();
+        let mut new_ids = HashMap::new();
+        for (index, mut todo) in todos.into_iter().enumerate() {
+            if todo.name.trim().is_empty() {
+                return Err(TodoErrors::Validation(format!(
+                    "Imported todo #{} has an empty name",
+                    index + 1
+                )));
+            }
+            let imported_id = todo.id.clone();
+            while todo.id.trim().is_empty() || taken_ids.contains(&todo.id) {
+                todo.id = nanoid!(ID_LENGTH);
+            }
+            new_ids.insert(imported_id, todo.id.clone());
+            taken_ids.push(todo.id.clone());
+            todo.tags = normalize_tags(&todo.tags);
+            todo.created_at = todo.created_at.or(Some(now));
+            todo.updated_at = todo.updated_at.or(Some(now));
+            todo.deleted_at = None;
+            todo.list = Some(String::from(list));
+            prepared.push(todo);
+        }
+        let imported_or_stored = |todo_id: String| {
+            Some(new_ids.get(&todo_id).cloned().unwrap_or(todo_id))
+                .filter(|todo_id| taken_ids.contains(todo_id))
+        };
+        for todo in &mut prepared {
+            todo.parent_id = todo.parent_id.take().and_then(imported_or_stored);
+            todo.blocked_by = std::mem::take(&mut todo.blocked_by)
+                .into_iter()
+                .filter_map(imported_or_stored)
+                .collect();
         }
+        Ok(prepared)
     }
 
+    /// Moves the todo to the trash; it stays restorable until purged. A todo with subtasks
+    /// is refused, see [`TodoRepository::delete_todos`].
     pub fn delete_todo(&mut self, todo_id: String) -> Result<Todo, TodoErrors> {
-        let mut all_todo = self.get_all_todos()?;
-        let todo_to_delete = self.find_in_memory_todo(&all_todo, &todo_id)?;
-        all_todo.retain(|todo| &todo.id != &todo_id);
-        match self.save_all(all_todo) {
-            Ok(_) => Ok(todo_to_delete),
-            Err(save_error) => Err(save_error),
+        let report = self.delete_todos(&TodoSelection::Ids(vec![todo_id.clone()]), false)?;
+        Self::single_result(report, &todo_id)
+    }
+
+    /// Moves the selected todos to the trash. With `recursive` their subtasks go too;
+    /// without it, selecting a todo that has subtasks outside the trash fails.
+    pub fn delete_todos(
+        &mut self,
+        selection: &TodoSelection,
+        recursive: bool,
+    ) -> Result<BulkReport, TodoErrors> {
+        let subtasks = SubtaskRule {
+            unfinished: |_| true,
+            cascade: recursive,
+            flag: "--recursive",
+        };
+        self.bulk_update(
+            "delete",
+            selection,
+            subtasks,
+            |_, _| Ok(()),
+            |todo| {
+                Self::mark_trashed(todo);
+                Ok(None)
+            },
+        )
+    }
+
+    // The todo of a bulk operation on one id, or why it was not changed.
+    fn single_result(mut report: BulkReport, todo_id: &str) -> Result<Todo, TodoErrors> {
+        if report.succeeded.is_empty() {
+            return Err(Self::not_found(todo_id));
         }
+        Ok(report.succeeded.remove(0))
+    }
+
+    pub fn list_trash(&mut self) -> Result<Vec<Todo>, TodoErrors> {
+        let filter = self.in_current_list(TodoFilter::trashed())?;
+        self.storage.scan(&filter)
+    }
+
+    pub fn restore_todo(&mut self, todo_id: String) -> Result<Todo, TodoErrors> {
+        self.write_transaction("restore", |storage| {
+            let mut todo = storage
+                .get(&todo_id)?
+                .filter(|todo| todo.deleted_at.is_some())
+                .ok_or_else(|| {
+                    TodoErrors::NotFound(format!("Todo by id:{} not found in trash", todo_id))
+                })?;
+            todo.deleted_at = None;
+            todo.updated_at = Some(Utc::now());
+            storage.update(&todo)?;
+            Ok(todo)
+        })
+    }
+
+    /// Permanently removes trashed todos of the current list, or only those trashed at least
+    /// `older_than` ago.
+    pub fn purge_todos(&mut self, older_than: Option<Duration>) -> Result<Vec<Todo>, TodoErrors> {
+        let cutoff = older_than.map(|age| Utc::now() - age);
+        let filter = self.in_current_list(TodoFilter::trashed())?;
+        self.write_transaction("purge", |storage| {
+            let mut purged = Vec::new();
+            for todo in storage.scan(&filter)? {
+                let expired =
+                    cutoff.is_none_or(|cutoff| todo.deleted_at.is_some_and(|at| at <= cutoff));
+                if expired && let Some(removed) = storage.remove(&todo.id)? {
+                    purged.push(removed);
+                }
+            }
+            Ok(purged)
+        })
     }
 
+    /// Completes the todo; one with pending subtasks or open blockers is refused, see
+    /// [`TodoRepository::complete_todos`].
     pub fn mark_todo_complete(&mut self, todo_id: String) -> Result<Todo, TodoErrors> {
-        let mut all_todo = self.get_all_todos()?;
-        let pos = all_todo
+        let selection = TodoSelection::Ids(vec![todo_id.clone()]);
+        let report = self.complete_todos(&selection, false, false)?;
+        Self::single_result(report, &todo_id)
+    }
+
+    /// Completes the selected todos. With `cascade` their pending subtasks are completed too;
+    /// without it, selecting a todo that has pending subtasks fails. Selecting a todo blocked
+    /// by an open todo that is not selected as well fails unless `force` is set.
+    pub fn complete_todos(
+        &mut self,
+        selection: &TodoSelection,
+        cascade: bool,
+        force: bool,
+    ) -> Result<BulkReport, TodoErrors> {
+        let subtasks = SubtaskRule {
+            unfinished: |todo| !todo.completed,
+            cascade,
+            flag: "--cascade",
+        };
+        let check = |active: &[Todo], todo_ids: &[String]| match force {
+            true => Ok(()),
+            false => Self::refuse_open_blockers(active, todo_ids),
+        };
+        self.bulk_update("complete", selection, subtasks, check, Self::mark_completed)
+    }
+
+    /// Pending todos of the current list that wait on no open todo.
+    pub fn ready_todos(&mut self) -> Result<Vec<Todo>, TodoErrors> {
+        let list = self.current_list()?;
+        // Blockers may sit in another list.
+        let active = self.storage.scan(&TodoFilter::active())?;
+        Ok(active
             .iter()
-            .position(|todo| todo.id == todo_id)
-            .ok_or_else(|| TodoErrors::TodoGetError(format!("Todo by id:{} not found", todo_id)))?;
-        all_todo[pos].completed = true;
-        let updated_todo = all_todo[pos].clone();
-        match self.save_all(all_todo) {
-            Ok(_) => Ok(updated_todo),
-            Err(save_error) => Err(save_error),
+            .filter(|todo| {
+                todo.list_name() == list
+                    && !todo.completed
+                    && open_blockers(todo, &active).is_empty()
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Every list holding a todo outside the trash, by name, plus the default list.
+    pub fn list_summaries(&mut self) -> Result<Vec<ListSummary>, TodoErrors> {
+        let default_list = self.default_list()?;
+        let active = self.storage.scan(&TodoFilter::active())?;
+        Ok(lists::summarize(&active, &default_list))
+    }
+
+    /// Stores `list` as the one used when no list is given; one passed to
+    /// `with_default_list` still comes first. Not journaled, as no todo changes.
+    pub fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+        Self::run_transaction(&mut self.storage, |storage| storage.set_default_list(list))
+    }
+
+    /// Every todo, trashed ones included, and the stored default list as a JSON document in
+    /// the data file format. This is what [`RemoteDataAccess`] reads.
+    pub fn storage_document(&mut self) -> Result<String, TodoErrors> {
+        let todos = self.storage.scan(&TodoFilter::all())?;
+        let default_list = self.storage.default_list()?;
+        JsonDocument::new(todos, default_list).to_json()
+    }
+
+    /// Applies the changes of a [`RemoteDataAccess`] write transaction as one journaled
+    /// operation. Nothing is applied if any todo no longer matches its `before` state.
+    pub fn apply_remote_changes(
+        &mut self,
+        remote_changes: &RemoteChanges,
+    ) -> Result<(), TodoErrors> {
+        self.write_transaction("remote", |storage| {
+            let changes = remote_changes.changes.iter();
+            Self::restore_changes(
+                storage,
+                "it was read",
+                changes.map(|change| (&change.before, &change.after)),
+            )?;
+            match &remote_changes.default_list {
+                Some(list) => storage.set_default_list(list),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Moves the selected todos, along with all their subtasks, into the list named `list`.
+    pub fn move_todos(
+        &mut self,
+        selection: &TodoSelection,
+        list: &str,
+    ) -> Result<BulkReport, TodoErrors> {
+        let subtasks = SubtaskRule {
+            unfinished: |_| true,
+            cascade: true,
+            flag: "",
+        };
+        self.bulk_update(
+            "move",
+            selection,
+            subtasks,
+            |_, _| Ok(()),
+            |todo| {
+                todo.list = Some(String::from(list));
+                todo.updated_at = Some(Utc::now());
+                Ok(None)
+            },
+        )
+    }
+
+    /// Makes `todo_id` wait on each of `blocker_ids`. A link that would make a todo wait on
+    /// itself through a chain of blockers is refused and nothing is linked.
+    pub fn link_todos(
+        &mut self,
+        todo_id: &str,
+        blocker_ids: &[String],
+    ) -> Result<Todo, TodoErrors> {
+        let blocker_ids = normalize_tags(blocker_ids);
+        if blocker_ids.is_empty() {
+            return Err(TodoErrors::Validation(String::from(
+                "Nothing to link, pass at least one blocking todo id",
+            )));
         }
+        self.write_transaction("link", |storage| {
+            let mut todo = Self::find_active(storage, todo_id)?;
+            // Trashed todos count too, as restoring one could otherwise close a cycle.
+            let mut todos = storage.scan(&TodoFilter::all())?;
+            for blocker_id in blocker_ids {
+                Self::find_blocker(storage, &blocker_id)?;
+                if todo.blocked_by.contains(&blocker_id) {
+                    continue;
+                }
+                if let Some(cycle) = dependencies::find_cycle(&todos, &todo.id, &blocker_id) {
+                    return Err(TodoErrors::Conflict(format!(
+                        "Todo by id:{} cannot wait on {}, that would create a dependency cycle: {}",
+                        todo.id,
+                        blocker_id,
+                        cycle.join(" -> ")
+                    )));
+                }
+                todo.blocked_by.push(blocker_id);
+                if let Some(stored) = todos.iter_mut().find(|stored| stored.id == todo.id) {
+                    stored.blocked_by = todo.blocked_by.clone();
+                }
+            }
+            todo.updated_at = Some(Utc::now());
+            storage.update(&todo)?;
+            Ok(todo)
+        })
+    }
+
+    /// Removes each of `blocker_ids` from the todos `todo_id` waits on.
+    pub fn unlink_todos(
+        &mut self,
+        todo_id: &str,
+        blocker_ids: &[String],
+    ) -> Result<Todo, TodoErrors> {
+        let blocker_ids = normalize_tags(blocker_ids);
+        if blocker_ids.is_empty() {
+            return Err(TodoErrors::Validation(String::from(
+                "Nothing to unlink, pass at least one blocking todo id",
+            )));
+        }
+        self.write_transaction("unlink", |storage| {
+            let mut todo = Self::find_active(storage, todo_id)?;
+            for blocker_id in &blocker_ids {
+                if !todo.blocked_by.contains(blocker_id) {
+                    return Err(TodoErrors::NotFound(format!(
+                        "Todo by id:{} is not blocked by {}",
+                        todo.id, blocker_id
+                    )));
+                }
+            }
+            todo.blocked_by
+                .retain(|blocker_id| !blocker_ids.contains(blocker_id));
+            todo.updated_at = Some(Utc::now());
+            storage.update(&todo)?;
+            Ok(todo)
+        })
+    }
+
+    /// Applies only the fields set in `edit_command_args`, keeping the todo id.
+    pub fn update_todo(&mut self, edit_command_args: &EditCommandArgs) -> Result<Todo, TodoErrors> {
+        if !edit_command_args.has_changes() {
+            return Err(TodoErrors::Validation(String::from(
+                "Nothing to update, pass at least one field to change",
+            )));
+        }
+        if edit_command_args
+            .name
+            .as_ref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            return Err(TodoErrors::Validation(String::from(
+                "Todo name cannot be empty",
+            )));
+        }
+
+        self.write_transaction("edit", |storage| {
+            let todo_id = &edit_command_args.id;
+            let mut todo = Self::find_active(storage, todo_id)?;
+            if let Some(name) = &edit_command_args.name {
+                todo.name = String::from(name);
+            }
+            if let Some(description) = &edit_command_args.description {
+                todo.description = String::from(description);
+            }
+            if edit_command_args.clear_due {
+                todo.due = None;
+            } else if edit_command_args.due.is_some() {
+                todo.due = edit_command_args.due;
+            }
+            if edit_command_args.clear_priority {
+                todo.priority = None;
+            } else if edit_command_args.priority.is_some() {
+                todo.priority = edit_command_args.priority;
+            }
+            let removed_tags = normalize_tags(&edit_command_args.remove_tag);
+            todo.tags.retain(|tag| !removed_tags.contains(tag));
+            todo.tags.extend(normalize_tags(&edit_command_args.add_tag));
+            todo.tags = normalize_tags(&todo.tags);
+            if edit_command_args.clear_repeat {
+                todo.recurrence = None;
+            } else if edit_command_args.recurrence.is_some() {
+                todo.recurrence = edit_command_args.recurrence.clone();
+            }
+            if edit_command_args.reopen {
+                todo.completed = false;
+                todo.completed_at = None;
+            }
+            todo.updated_at = Some(Utc::now());
+            storage.update(&todo)?;
+            Ok(todo)
+        })
     }
 
     #[cfg(test)]
-    pub fn into_writer(self) -> D {
-        self.data_access
+    pub fn into_writer(self) -> S {
+        self.storage
     }
 }
 
@@ -179,7 +1151,9 @@ mod tests {
 
     use super::*;
     use data_access::{CursorDataAccess, FailingDataAccess};
-    use std::{io::Cursor, str::FromStr};
+    use journal::MemoryJournal;
+    use serde_json::to_string_pretty;
+    use std::{io::Cursor, slice, str::FromStr};
 
     fn get_todo_list() -> Vec<Todo> {
         vec![
@@ -188,31 +1162,40 @@ mod tests {
                 name: String::from("First Todo"),
                 description: String::from("First Todo description"),
                 completed: false,
+                ..Todo::default()
             },
             Todo {
                 id: nanoid!(ID_LENGTH),
                 name: String::from("Second Todo"),
                 description: String::from("Second Todo description"),
                 completed: false,
+                ..Todo::default()
             },
             Todo {
                 id: nanoid!(ID_LENGTH),
                 name: String::from("Third Todo"),
                 description: String::from("Third Todo description"),
                 completed: false,
+                ..Todo::default()
             },
         ]
     }
 
-    fn get_cursor_data_access(todos: &Vec<Todo>) -> CursorDataAccess {
+    fn parse_saved_todos(output_str: &str) -> Vec<Todo> {
+        JsonDocument::parse(output_str)
+            .unwrap()
+            .scan(&TodoFilter::all())
+    }
+
+    fn get_cursor_data_access(todos: &[Todo]) -> CursorDataAccess {
         let input_str = to_string_pretty(todos).unwrap();
         let input_cursor = Cursor::new(input_str);
         let output_cursor = Cursor::new(Vec::<u8>::new());
         CursorDataAccess::new(input_cursor, output_cursor)
     }
 
-    fn get_failing_data_access(todos: &Vec<Todo>) -> FailingDataAccess {
-        let input_str = to_string_pretty(&todos).unwrap();
+    fn get_failing_data_access(todos: &[Todo]) -> FailingDataAccess {
+        let input_str = to_string_pretty(todos).unwrap();
         let input_cursor = Cursor::new(input_str);
         FailingDataAccess::new(input_cursor)
     }
@@ -254,7 +1237,7 @@ mod tests {
         let mut todo_repository = TodoRepository::new(cursor_data_access);
         let get_result = todo_repository.get_todo_by_id(String::from(&not_present_id));
         assert!(
-            matches!(get_result, Err(TodoErrors::TodoGetError(ref msg)) if msg.contains(&format!("Todo by id:{} not found", &not_present_id))
+            matches!(get_result, Err(TodoErrors::NotFound(ref msg)) if msg.contains(&format!("Todo by id:{} not found", &not_present_id))
             )
         );
     }
@@ -286,7 +1269,7 @@ mod tests {
         let mut todo_repo = TodoRepository::new(cursor_data_access);
         let find_result = todo_repo.get_todo_by_name(String::from_str(todo_search_str).unwrap());
         assert!(
-            matches!(find_result, Err(TodoErrors::TodoGetError(ref msg)) if msg.contains(&format!("Todo by name: {} not found", todo_search_str)))
+            matches!(find_result, Err(TodoErrors::NotFound(ref msg)) if msg.contains(&format!("Todo by name: {} not found", todo_search_str)))
         )
     }
 
@@ -298,6 +1281,7 @@ mod tests {
         let add_command_args = AddCommandArgs {
             name: String::from("New Todo"),
             description: String::from("New Todo Description"),
+            ..AddCommandArgs::default()
         };
         let added_todo = todo_repo.add_todo(&add_command_args).unwrap();
 
@@ -310,13 +1294,10 @@ mod tests {
         let output_str = String::from_utf8(output_bytes).unwrap();
 
         // Deserialize for assertion
-        let updated_todos: Vec<Todo> = from_str(&output_str).unwrap();
+        let updated_todos = parse_saved_todos(&output_str);
         let mut expected_todos = saved_todos.clone();
         expected_todos.push(added_todo);
-        let test_pair: Vec<_> = expected_todos
-            .into_iter()
-            .zip(updated_todos.into_iter())
-            .collect();
+        let test_pair: Vec<_> = expected_todos.into_iter().zip(updated_todos).collect();
 
         test_pair.iter().for_each(|pair| {
             assert_eq!(pair.0.name, pair.1.name);
@@ -333,10 +1314,11 @@ mod tests {
         let add_command_args = AddCommandArgs {
             name: String::from("New Todo"),
             description: String::from("New Todo Description"),
+            ..AddCommandArgs::default()
         };
         let added_todo_result = todo_repo.add_todo(&add_command_args);
         assert!(
-            matches!(added_todo_result, Err(TodoErrors::TodoSaveError(ref msg)) if msg.contains("Simulated write error"))
+            matches!(added_todo_result, Err(TodoErrors::Io(ref msg)) if msg.contains("Simulated write error"))
         );
     }
 
@@ -350,25 +1332,22 @@ mod tests {
         let todo_by_id = todo_repository
             .delete_todo(String::from(&second_todo.id))
             .unwrap();
-        assert_eq!(second_todo, &todo_by_id);
+        let trashed_todo = Todo {
+            deleted_at: todo_by_id.deleted_at,
+            updated_at: todo_by_id.updated_at,
+            ..second_todo.clone()
+        };
+        assert_eq!(trashed_todo, todo_by_id);
+        assert!(todo_by_id.deleted_at.is_some());
 
         // Convert written data back to string
         let output_bytes = todo_repository.into_writer().writer.into_inner();
         let output_str = String::from_utf8(output_bytes).unwrap();
 
-        // Deserialize for assertion
-        let updated_todos: Vec<Todo> = from_str(&output_str).unwrap();
-        saved_todos.remove(index_to_remove);
-        let test_pair: Vec<_> = saved_todos
-            .into_iter()
-            .zip(updated_todos.into_iter())
-            .collect();
-
-        test_pair.iter().for_each(|pair| {
-            assert_eq!(pair.0.name, pair.1.name);
-            assert_eq!(pair.0.description, pair.1.description);
-            assert_eq!(pair.0.completed, pair.1.completed);
-        });
+        // Trashed todos stay in the data file until purged
+        let updated_todos = parse_saved_todos(&output_str);
+        saved_todos[index_to_remove] = trashed_todo;
+        assert_eq!(saved_todos, updated_todos);
     }
 
     #[test]
@@ -379,7 +1358,7 @@ mod tests {
         let mut todo_repository = TodoRepository::new(cursor_data_access);
         let get_result = todo_repository.delete_todo(String::from(&not_present_id));
         assert!(
-            matches!(get_result, Err(TodoErrors::TodoGetError(ref msg)) if msg.contains(&format!("Todo by id:{} not found", &not_present_id))
+            matches!(get_result, Err(TodoErrors::NotFound(ref msg)) if msg.contains(&format!("Todo by id:{} not found", &not_present_id))
             )
         );
     }
@@ -393,7 +1372,7 @@ mod tests {
         let second_todo = &saved_todos[index_to_remove];
         let delete_todo_by_id_result = todo_repository.delete_todo(String::from(&second_todo.id));
         assert!(
-            matches!(delete_todo_by_id_result, Err(TodoErrors::TodoSaveError(ref msg)) if msg.contains("Simulated write error"))
+            matches!(delete_todo_by_id_result, Err(TodoErrors::Io(ref msg)) if msg.contains("Simulated write error"))
         );
     }
 
@@ -408,24 +1387,22 @@ mod tests {
             .mark_todo_complete(String::from(&second_todo.id))
             .unwrap();
         let updated_todo = Todo {
-            id: String::from(&second_todo.id),
-            name: String::from(&second_todo.name),
-            description: String::from(&second_todo.description),
             completed: true,
+            completed_at: todo_by_id.completed_at,
+            updated_at: todo_by_id.updated_at,
+            ..second_todo.clone()
         };
         assert_eq!(&updated_todo, &todo_by_id);
+        assert!(todo_by_id.completed_at.is_some());
 
         // Convert written data back to string
         let output_bytes = todo_repository.into_writer().writer.into_inner();
         let output_str = String::from_utf8(output_bytes).unwrap();
 
         // Deserialize for assertion
-        let updated_todos: Vec<Todo> = from_str(&output_str).unwrap();
+        let updated_todos = parse_saved_todos(&output_str);
         saved_todos[index_to_modify] = updated_todo;
-        let test_pair: Vec<_> = saved_todos
-            .into_iter()
-            .zip(updated_todos.into_iter())
-            .collect();
+        let test_pair: Vec<_> = saved_todos.into_iter().zip(updated_todos).collect();
 
         test_pair.iter().for_each(|pair| {
             assert_eq!(pair.0.name, pair.1.name);
@@ -442,7 +1419,7 @@ mod tests {
         let mut todo_repository = TodoRepository::new(cursor_data_access);
         let get_result = todo_repository.mark_todo_complete(String::from(&not_present_id));
         assert!(
-            matches!(get_result, Err(TodoErrors::TodoGetError(ref msg)) if msg.contains(&format!("Todo by id:{} not found", &not_present_id))
+            matches!(get_result, Err(TodoErrors::NotFound(ref msg)) if msg.contains(&format!("Todo by id:{} not found", &not_present_id))
             )
         );
     }
@@ -457,7 +1434,1044 @@ mod tests {
         let modify_todo_by_id_result =
             todo_repository.mark_todo_complete(String::from(&second_todo.id));
         assert!(
-            matches!(modify_todo_by_id_result, Err(TodoErrors::TodoSaveError(ref msg)) if msg.contains("Simulated write error"))
+            matches!(modify_todo_by_id_result, Err(TodoErrors::Io(ref msg)) if msg.contains("Simulated write error"))
+        );
+    }
+
+    #[test]
+    fn should_add_and_complete_todo_with_sqlite_storage() {
+        let sqlite_data_access = SqliteDataAccess::open_in_memory().unwrap();
+        let mut todo_repository = TodoRepository::new(sqlite_data_access);
+        let add_command_args = AddCommandArgs {
+            name: String::from("New Todo"),
+            description: String::from("New Todo Description"),
+            ..AddCommandArgs::default()
+        };
+        let added_todo = todo_repository.add_todo(&add_command_args).unwrap();
+        let completed_todo = todo_repository
+            .mark_todo_complete(String::from(&added_todo.id))
+            .unwrap();
+
+        assert!(completed_todo.completed);
+        assert_eq!(
+            vec![completed_todo],
+            todo_repository.get_all_todos().unwrap()
         );
     }
+
+    #[test]
+    fn should_store_due_priority_tags_and_timestamps_on_add() {
+        let cursor_data_access = get_cursor_data_access(&[]);
+        let mut todo_repo = TodoRepository::new(cursor_data_access);
+        let due = Utc::now();
+        let add_command_args = AddCommandArgs {
+            name: String::from("New Todo"),
+            description: String::from("New Todo Description"),
+            due: Some(due),
+            priority: Some(Priority::Medium),
+            tags: vec![
+                String::from(" work "),
+                String::from(""),
+                String::from("work"),
+                String::from("home"),
+            ],
+            recurrence: None,
+            parent_id: None,
+            blocked_by: Vec::new(),
+        };
+        let added_todo = todo_repo.add_todo(&add_command_args).unwrap();
+
+        assert_eq!(Some(due), added_todo.due);
+        assert_eq!(Some(Priority::Medium), added_todo.priority);
+        assert_eq!(vec!["work", "home"], added_todo.tags);
+        assert!(added_todo.created_at.is_some());
+        assert_eq!(added_todo.created_at, added_todo.updated_at);
+        assert_eq!(None, added_todo.completed_at);
+    }
+
+    #[test]
+    fn should_update_only_supplied_fields_of_todo() {
+        let saved_todos = get_todo_list();
+        let cursor_data_access = get_cursor_data_access(&saved_todos);
+        let mut todo_repo = TodoRepository::new(cursor_data_access);
+        let edit_command_args = EditCommandArgs {
+            id: String::from(&saved_todos[1].id),
+            name: Some(String::from("Renamed Todo")),
+            add_tag: vec![String::from("work")],
+            ..EditCommandArgs::default()
+        };
+        let updated_todo = todo_repo.update_todo(&edit_command_args).unwrap();
+
+        assert_eq!(saved_todos[1].id, updated_todo.id);
+        assert_eq!("Renamed Todo", updated_todo.name);
+        assert_eq!(saved_todos[1].description, updated_todo.description);
+        assert_eq!(vec!["work"], updated_todo.tags);
+        assert!(updated_todo.updated_at.is_some());
+
+        let output_bytes = todo_repo.into_writer().writer.into_inner();
+        let updated_todos = parse_saved_todos(&String::from_utf8(output_bytes).unwrap());
+        assert_eq!(updated_todo, updated_todos[1]);
+        assert_eq!(saved_todos[0], updated_todos[0]);
+    }
+
+    #[test]
+    fn should_reopen_completed_todo_on_update() {
+        let saved_todos = get_todo_list();
+        let cursor_data_access = get_cursor_data_access(&saved_todos);
+        let mut todo_repo = TodoRepository::new(cursor_data_access);
+        let todo_id = String::from(&saved_todos[0].id);
+        todo_repo
+            .mark_todo_complete(String::from(&todo_id))
+            .unwrap();
+        let edit_command_args = EditCommandArgs {
+            id: todo_id,
+            reopen: true,
+            ..EditCommandArgs::default()
+        };
+        let reopened_todo = todo_repo.update_todo(&edit_command_args).unwrap();
+
+        assert!(!reopened_todo.completed);
+        assert_eq!(None, reopened_todo.completed_at);
+    }
+
+    #[test]
+    fn should_clear_due_priority_and_remove_tags_on_update() {
+        let mut saved_todos = get_todo_list();
+        saved_todos[0].due = Some(Utc::now());
+        saved_todos[0].priority = Some(Priority::Low);
+        saved_todos[0].tags = vec![String::from("home"), String::from("work")];
+        let cursor_data_access = get_cursor_data_access(&saved_todos);
+        let mut todo_repo = TodoRepository::new(cursor_data_access);
+        let edit_command_args = EditCommandArgs {
+            id: String::from(&saved_todos[0].id),
+            clear_due: true,
+            clear_priority: true,
+            remove_tag: vec![String::from("home")],
+            ..EditCommandArgs::default()
+        };
+        let updated_todo = todo_repo.update_todo(&edit_command_args).unwrap();
+
+        assert_eq!(None, updated_todo.due);
+        assert_eq!(None, updated_todo.priority);
+        assert_eq!(vec!["work"], updated_todo.tags);
+    }
+
+    #[test]
+    fn should_return_err_when_update_todo_not_present_or_has_no_changes() {
+        let saved_todos = get_todo_list();
+        let cursor_data_access = get_cursor_data_access(&saved_todos);
+        let mut todo_repo = TodoRepository::new(cursor_data_access);
+        let not_present_id = nanoid!();
+        let missing_result = todo_repo.update_todo(&EditCommandArgs {
+            id: String::from(&not_present_id),
+            name: Some(String::from("Renamed")),
+            ..EditCommandArgs::default()
+        });
+        let no_change_result = todo_repo.update_todo(&EditCommandArgs {
+            id: String::from(&saved_todos[0].id),
+            ..EditCommandArgs::default()
+        });
+
+        assert!(
+            matches!(missing_result, Err(TodoErrors::NotFound(ref msg)) if msg.contains(&format!("Todo by id:{} not found", &not_present_id)))
+        );
+        assert!(
+            matches!(no_change_result, Err(TodoErrors::Validation(ref msg)) if msg.contains("Nothing to update"))
+        );
+    }
+
+    fn get_journaled_repository(todos: &[Todo]) -> TodoRepository<CursorDataAccess> {
+        TodoRepository::new(get_cursor_data_access(todos))
+            .with_journal(Box::new(MemoryJournal::default()))
+    }
+
+    /// SQLite storage whose commits fail while `fail_commits` is set, as a busy database would.
+    struct UncommittableStorage {
+        inner: SqliteDataAccess,
+        fail_commits: bool,
+    }
+
+    impl TodoStorage for UncommittableStorage {
+        fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+            self.inner.get(todo_id)
+        }
+
+        fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+            self.inner.insert(todo)
+        }
+
+        fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+            self.inner.update(todo)
+        }
+
+        fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+            self.inner.remove(todo_id)
+        }
+
+        fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors> {
+            self.inner.scan(filter)
+        }
+
+        fn default_list(&mut self) -> Result<Option<String>, TodoErrors> {
+            self.inner.default_list()
+        }
+
+        fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+            self.inner.set_default_list(list)
+        }
+
+        fn begin_write(&mut self) -> Result<(), TodoErrors> {
+            self.inner.begin_write()
+        }
+
+        fn commit(&mut self) -> Result<(), TodoErrors> {
+            if self.fail_commits {
+                self.inner.rollback();
+                return Err(TodoErrors::Io(String::from("database is locked")));
+            }
+            self.inner.commit()
+        }
+
+        fn rollback(&mut self) {
+            self.inner.rollback()
+        }
+    }
+
+    #[test]
+    fn should_not_journal_changes_whose_commit_failed() {
+        let mut inner = SqliteDataAccess::open_in_memory().unwrap();
+        let saved_todos = get_todo_list();
+        for todo in &saved_todos {
+            inner.insert(todo).unwrap();
+        }
+        let storage = UncommittableStorage {
+            inner,
+            fail_commits: true,
+        };
+        let mut todo_repo =
+            TodoRepository::new(storage).with_journal(Box::new(MemoryJournal::default()));
+
+        let result = todo_repo.delete_todo(saved_todos[0].id.clone());
+        assert!(matches!(result, Err(TodoErrors::Io(_))));
+        assert!(todo_repo.history(None).unwrap().is_empty());
+
+        todo_repo.storage.fail_commits = false;
+        todo_repo.delete_todo(saved_todos[0].id.clone()).unwrap();
+        todo_repo.delete_todo(saved_todos[1].id.clone()).unwrap();
+        todo_repo.storage.fail_commits = true;
+        assert!(matches!(todo_repo.undo(1), Err(TodoErrors::Io(_))));
+
+        todo_repo.storage.fail_commits = false;
+        assert!(matches!(
+            todo_repo.redo(1),
+            Err(TodoErrors::Validation(ref msg)) if msg == "Nothing to redo"
+        ));
+        todo_repo.undo(2).unwrap();
+        assert_eq!(saved_todos, todo_repo.get_all_todos().unwrap());
+    }
+
+    #[test]
+    fn should_revert_change_that_cannot_be_journaled() {
+        let mut storage = SqliteDataAccess::open_in_memory().unwrap();
+        let saved_todos = get_todo_list();
+        for todo in &saved_todos {
+            storage.insert(todo).unwrap();
+        }
+        let journal = MemoryJournal {
+            fail_appends: true,
+            ..MemoryJournal::default()
+        };
+        let mut todo_repo = TodoRepository::new(storage).with_journal(Box::new(journal));
+
+        let result = todo_repo.delete_todo(saved_todos[1].id.clone());
+        assert!(matches!(result, Err(TodoErrors::Io(_))));
+        assert_eq!(saved_todos, todo_repo.get_all_todos().unwrap());
+        let add_args = AddCommandArgs {
+            name: String::from("Buy milk"),
+            ..AddCommandArgs::default()
+        };
+        assert!(todo_repo.add_todo(&add_args).is_err());
+        assert_eq!(3, todo_repo.get_all_todos().unwrap().len());
+    }
+
+    #[test]
+    fn should_restore_deleted_todo_on_undo_and_remove_it_again_on_redo() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+        let todo_id = String::from(&saved_todos[1].id);
+        todo_repo.delete_todo(String::from(&todo_id)).unwrap();
+
+        let undone = todo_repo.undo(1).unwrap();
+        assert_eq!(
+            vec!["delete"],
+            undone.iter().map(|e| &e.operation).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            saved_todos[1],
+            todo_repo.get_todo_by_id(String::from(&todo_id)).unwrap()
+        );
+
+        todo_repo.redo(1).unwrap();
+        assert!(todo_repo.get_todo_by_id(todo_id).is_err());
+        assert!(matches!(
+            todo_repo.redo(1),
+            Err(TodoErrors::Validation(ref msg)) if msg == "Nothing to redo"
+        ));
+    }
+
+    #[test]
+    fn should_undo_several_operations_newest_first() {
+        let mut todo_repo = get_journaled_repository(&[]);
+        let added_todo = todo_repo
+            .add_todo(&AddCommandArgs {
+                name: String::from("New Todo"),
+                ..AddCommandArgs::default()
+            })
+            .unwrap();
+        todo_repo
+            .mark_todo_complete(String::from(&added_todo.id))
+            .unwrap();
+
+        let undone = todo_repo.undo(5).unwrap();
+
+        assert_eq!(
+            vec!["complete", "add"],
+            undone.iter().map(|e| &e.operation).collect::<Vec<_>>()
+        );
+        assert_eq!(Vec::<Todo>::new(), todo_repo.get_all_todos().unwrap());
+        assert!(todo_repo.history(None).unwrap().iter().all(|e| e.undone));
+    }
+
+    #[test]
+    fn should_refuse_undo_when_todo_changed_outside_journal() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+        let todo_id = String::from(&saved_todos[0].id);
+        todo_repo
+            .mark_todo_complete(String::from(&todo_id))
+            .unwrap();
+        // Bypass the journal so the stored todo no longer matches the recorded state.
+        let mut changed = todo_repo.get_todo_by_id(String::from(&todo_id)).unwrap();
+        changed.name = String::from("Changed elsewhere");
+        todo_repo.storage.update(&changed).unwrap();
+
+        assert!(matches!(
+            todo_repo.undo(1),
+            Err(TodoErrors::Conflict(ref msg)) if msg.contains("was changed after operation #1")
+        ));
+        assert_eq!(changed, todo_repo.get_todo_by_id(todo_id).unwrap());
+    }
+
+    #[test]
+    fn should_list_history_newest_first_and_skip_failed_operations() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+        todo_repo
+            .mark_todo_complete(String::from(&saved_todos[0].id))
+            .unwrap();
+        assert!(todo_repo.delete_todo(nanoid!()).is_err());
+        todo_repo
+            .delete_todo(String::from(&saved_todos[1].id))
+            .unwrap();
+
+        let history = todo_repo.history(None).unwrap();
+        assert_eq!(
+            vec![(2, "delete"), (1, "complete")],
+            history
+                .iter()
+                .map(|e| (e.seq, e.operation.as_str()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(saved_todos[1].clone()), history[0].changes[0].before);
+        assert_eq!(1, todo_repo.history(Some(1)).unwrap().len());
+    }
+
+    #[test]
+    fn should_return_err_on_undo_without_journal() {
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&[]));
+        assert!(matches!(
+            todo_repo.undo(1),
+            Err(TodoErrors::Validation(ref msg)) if msg.contains("No operation journal")
+        ));
+    }
+
+    #[test]
+    fn should_hide_trashed_todo_until_restored() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+        let todo_id = String::from(&saved_todos[0].id);
+        todo_repo.delete_todo(String::from(&todo_id)).unwrap();
+
+        assert!(todo_repo.get_todo_by_id(String::from(&todo_id)).is_err());
+        assert!(todo_repo.get_todo_by_name(String::from("first")).is_err());
+        assert!(
+            todo_repo
+                .mark_todo_complete(String::from(&todo_id))
+                .is_err()
+        );
+        assert!(todo_repo.delete_todo(String::from(&todo_id)).is_err());
+        assert_eq!(2, todo_repo.get_all_todos().unwrap().len());
+        assert_eq!(
+            vec![todo_id.as_str()],
+            todo_repo
+                .list_trash()
+                .unwrap()
+                .iter()
+                .map(|todo| todo.id.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let restored = todo_repo.restore_todo(String::from(&todo_id)).unwrap();
+        assert_eq!(None, restored.deleted_at);
+        assert_eq!(
+            saved_todos[0].name,
+            todo_repo
+                .get_todo_by_id(String::from(&todo_id))
+                .unwrap()
+                .name
+        );
+        assert!(matches!(
+            todo_repo.restore_todo(todo_id),
+            Err(TodoErrors::NotFound(ref msg)) if msg.contains("not found in trash")
+        ));
+    }
+
+    #[test]
+    fn should_purge_only_todos_trashed_before_cutoff() {
+        let mut saved_todos = get_todo_list();
+        saved_todos[0].deleted_at = Some(Utc::now() - Duration::days(40));
+        saved_todos[1].deleted_at = Some(Utc::now() - Duration::days(2));
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+
+        let purged = todo_repo.purge_todos(Some(Duration::days(30))).unwrap();
+        assert_eq!(vec![saved_todos[0].clone()], purged);
+        assert_eq!(
+            vec![saved_todos[1].clone()],
+            todo_repo.list_trash().unwrap()
+        );
+
+        let purged = todo_repo.purge_todos(None).unwrap();
+        assert_eq!(vec![saved_todos[1].clone()], purged);
+        assert_eq!(
+            vec![saved_todos[2].clone()],
+            parse_saved_todos(
+                &String::from_utf8(todo_repo.into_writer().writer.into_inner()).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn should_complete_selected_ids_in_one_write_and_report_missing() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+        let missing_id = nanoid!();
+        let selection = TodoSelection::Ids(vec![
+            String::from(&saved_todos[0].id),
+            String::from(&missing_id),
+            format!(" {} ", saved_todos[2].id),
+            String::from(&saved_todos[0].id),
+            String::from(""),
+        ]);
+
+        let report = todo_repo.complete_todos(&selection, false, false).unwrap();
+
+        assert_eq!(
+            vec![&saved_todos[0].id, &saved_todos[2].id],
+            report
+                .succeeded
+                .iter()
+                .map(|todo| &todo.id)
+                .collect::<Vec<_>>()
+        );
+        assert!(report.succeeded.iter().all(|todo| todo.completed));
+        assert_eq!(vec![missing_id], report.not_found);
+        let history = todo_repo.history(None).unwrap();
+        assert_eq!(1, history.len());
+        assert_eq!(2, history[0].changes.len());
+    }
+
+    #[test]
+    fn should_trash_todos_matching_filter() {
+        let mut saved_todos = get_todo_list();
+        saved_todos[1].completed = true;
+        saved_todos[2].completed = true;
+        saved_todos[2].deleted_at = Some(Utc::now());
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+        let selection = TodoSelection::Matching(TodoQuery {
+            status: Some(TodoStatus::Done),
+            ..TodoQuery::default()
+        });
+
+        let report = todo_repo.delete_todos(&selection, false).unwrap();
+
+        assert_eq!(
+            vec![&saved_todos[1].id],
+            report
+                .succeeded
+                .iter()
+                .map(|todo| &todo.id)
+                .collect::<Vec<_>>()
+        );
+        assert!(report.not_found.is_empty());
+        assert_eq!(
+            vec![saved_todos[0].clone()],
+            todo_repo.get_all_todos().unwrap()
+        );
+    }
+
+    #[test]
+    fn should_import_todos_generating_ids_for_blank_and_taken_ones() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+        let imported = vec![
+            Todo {
+                id: String::from(&saved_todos[0].id),
+                name: String::from("Clashing id"),
+                ..Todo::default()
+            },
+            Todo {
+                id: String::from("fresh01"),
+                name: String::from("Kept id"),
+                ..Todo::default()
+            },
+            Todo {
+                name: String::from("No id"),
+                ..Todo::default()
+            },
+        ];
+
+        let added = todo_repo.import_todos(imported, false).unwrap();
+
+        assert_ne!(saved_todos[0].id, added[0].id);
+        assert_eq!("fresh01", added[1].id);
+        assert_eq!(ID_LENGTH, added[2].id.len());
+        assert!(added.iter().all(|todo| todo.created_at.is_some()));
+        assert_eq!(6, todo_repo.get_all_todos().unwrap().len());
+    }
+
+    #[test]
+    fn should_not_write_anything_on_import_dry_run() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+        let imported = vec![Todo {
+            name: String::from("Preview"),
+            ..Todo::default()
+        }];
+
+        let added = todo_repo.import_todos(imported, true).unwrap();
+
+        assert_eq!("Preview", added[0].name);
+        assert_eq!(3, todo_repo.get_all_todos().unwrap().len());
+        assert!(todo_repo.into_writer().writer.into_inner().is_empty());
+    }
+
+    #[test]
+    fn should_reject_import_with_empty_name() {
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&[]));
+        let imported = vec![Todo {
+            name: String::from("  "),
+            ..Todo::default()
+        }];
+        assert!(matches!(
+            todo_repo.import_todos(imported, false),
+            Err(TodoErrors::Validation(ref msg)) if msg.contains("#1 has an empty name")
+        ));
+    }
+
+    #[test]
+    fn should_add_all_todos_or_none_when_one_has_empty_name() {
+        let mut todo_repo = get_journaled_repository(&[]);
+        let named = |name: &str| AddCommandArgs {
+            name: String::from(name),
+            ..AddCommandArgs::default()
+        };
+
+        let added = todo_repo.add_todos(&[named("One"), named("Two")]).unwrap();
+        let rejected = todo_repo.add_todos(&[named("Three"), named(" ")]);
+
+        assert_eq!(2, added.len());
+        assert!(matches!(
+            rejected,
+            Err(TodoErrors::Validation(ref msg)) if msg == "Todo name cannot be empty"
+        ));
+        assert_eq!(added, todo_repo.get_all_todos().unwrap());
+        assert_eq!(1, todo_repo.history(None).unwrap().len());
+    }
+
+    #[test]
+    fn should_add_next_occurrence_once_when_recurring_todo_is_completed() {
+        let mut saved_todos = get_todo_list();
+        let due = Utc::now() + chrono::Duration::hours(1);
+        saved_todos[0].due = Some(due);
+        saved_todos[0].recurrence = Some(Recurrence::Daily);
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+        let todo_id = String::from(&saved_todos[0].id);
+
+        todo_repo
+            .mark_todo_complete(String::from(&todo_id))
+            .unwrap();
+        let report = todo_repo
+            .complete_todos(
+                &TodoSelection::Ids(vec![String::from(&todo_id)]),
+                false,
+                false,
+            )
+            .unwrap();
+
+        assert!(report.created.is_empty());
+        let occurrences = todo_repo
+            .get_todo_by_name(String::from(&saved_todos[0].name))
+            .unwrap();
+        assert_eq!(2, occurrences.len());
+        let next = occurrences.iter().find(|todo| todo.id != todo_id).unwrap();
+        assert!(!next.completed);
+        assert_eq!(Recurrence::Daily.next_after(due), next.due);
+        assert_eq!(saved_todos[0].recurrence, next.recurrence);
+
+        todo_repo.undo(2).unwrap();
+        assert_eq!(saved_todos, todo_repo.get_all_todos().unwrap());
+    }
+
+    #[test]
+    fn should_list_upcoming_occurrences_of_pending_recurring_todos_by_due_date() {
+        let mut saved_todos = get_todo_list();
+        let now = Utc::now();
+        saved_todos[0].due = Some(now + chrono::Duration::days(1));
+        saved_todos[0].recurrence = Some(Recurrence::EveryDays(2));
+        saved_todos[1].due = Some(now + chrono::Duration::hours(36));
+        saved_todos[1].recurrence = Some(Recurrence::EveryDays(2));
+        saved_todos[1].completed = true;
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+
+        let upcoming = todo_repo.upcoming_occurrences(3).unwrap();
+
+        assert_eq!(3, upcoming.len());
+        assert!(upcoming.iter().all(|todo| todo.id == saved_todos[0].id));
+        let dues: Vec<_> = upcoming.iter().map(|todo| todo.due.unwrap()).collect();
+        assert_eq!(saved_todos[0].due, Some(dues[0]));
+        assert!(dues.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn should_refuse_to_complete_recurring_todo_without_next_occurrence() {
+        let mut saved_todos = get_todo_list();
+        saved_todos[0].due = Some(Utc::now());
+        saved_todos[0].recurrence = Some(Recurrence::EveryDays(100_000_000));
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+
+        let result = todo_repo.mark_todo_complete(saved_todos[0].id.clone());
+
+        assert!(matches!(result, Err(TodoErrors::Validation(_))));
+        assert_eq!(saved_todos, todo_repo.get_all_todos().unwrap());
+    }
+
+    #[test]
+    fn should_stop_upcoming_occurrences_at_the_last_representable_date() {
+        let mut saved_todos = get_todo_list();
+        saved_todos[0].due = Some(Utc::now());
+        saved_todos[0].recurrence = Some(Recurrence::EveryDays(100_000_000));
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+
+        let upcoming = todo_repo.upcoming_occurrences(3).unwrap();
+
+        assert_eq!(vec![saved_todos[0].clone()], upcoming);
+    }
+
+    fn add_subtask(
+        todo_repo: &mut TodoRepository<CursorDataAccess>,
+        name: &str,
+        parent_id: &str,
+    ) -> Result<Todo, TodoErrors> {
+        todo_repo.add_todo(&AddCommandArgs {
+            name: String::from(name),
+            parent_id: Some(String::from(parent_id)),
+            ..AddCommandArgs::default()
+        })
+    }
+
+    #[test]
+    fn should_add_subtask_only_under_pending_parent_outside_trash() {
+        let mut saved_todos = get_todo_list();
+        saved_todos[1].completed = true;
+        saved_todos[2].deleted_at = Some(Utc::now());
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+
+        let subtask = add_subtask(&mut todo_repo, "Sub", &saved_todos[0].id).unwrap();
+
+        assert_eq!(Some(&saved_todos[0].id), subtask.parent_id.as_ref());
+        assert!(matches!(
+            add_subtask(&mut todo_repo, "Sub", &saved_todos[1].id),
+            Err(TodoErrors::Validation(ref msg)) if msg.contains("is completed")
+        ));
+        assert!(matches!(
+            add_subtask(&mut todo_repo, "Sub", &saved_todos[2].id),
+            Err(TodoErrors::NotFound(ref msg)) if msg.starts_with("Parent todo")
+        ));
+        assert_eq!(
+            SubtaskProgress { done: 0, total: 1 },
+            todo_repo.subtask_progress().unwrap()[&saved_todos[0].id]
+        );
+    }
+
+    #[test]
+    fn should_complete_parent_only_once_subtasks_are_done_or_with_cascade() {
+        let saved_todos = get_todo_list();
+        let parent_id = String::from(&saved_todos[0].id);
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+        let child = add_subtask(&mut todo_repo, "Child", &parent_id).unwrap();
+        let grandchild = add_subtask(&mut todo_repo, "Grandchild", &child.id).unwrap();
+
+        let refused = todo_repo.mark_todo_complete(String::from(&parent_id));
+        assert!(matches!(
+            refused,
+            Err(TodoErrors::Validation(ref msg))
+                if msg.contains("2 unfinished subtask(s)") && msg.contains("--cascade")
+        ));
+        assert!(
+            !todo_repo
+                .get_todo_by_id(String::from(&parent_id))
+                .unwrap()
+                .completed
+        );
+
+        // Selecting the subtasks themselves is as good as cascading.
+        let selection = TodoSelection::Ids(vec![
+            String::from(&parent_id),
+            String::from(&child.id),
+            String::from(&grandchild.id),
+        ]);
+        assert_eq!(
+            3,
+            todo_repo
+                .complete_todos(&selection, false, false)
+                .unwrap()
+                .succeeded
+                .len()
+        );
+        todo_repo.undo(1).unwrap();
+
+        let selection = TodoSelection::Ids(vec![String::from(&parent_id)]);
+        let report = todo_repo.complete_todos(&selection, true, false).unwrap();
+        assert_eq!(
+            vec![&parent_id, &child.id, &grandchild.id],
+            report
+                .succeeded
+                .iter()
+                .map(|todo| &todo.id)
+                .collect::<Vec<_>>()
+        );
+        assert!(report.succeeded.iter().all(|todo| todo.completed));
+    }
+
+    #[test]
+    fn should_delete_parent_with_subtasks_only_when_recursive() {
+        let saved_todos = get_todo_list();
+        let parent_id = String::from(&saved_todos[0].id);
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+        let child = add_subtask(&mut todo_repo, "Child", &parent_id).unwrap();
+        todo_repo
+            .mark_todo_complete(String::from(&child.id))
+            .unwrap();
+
+        assert!(matches!(
+            todo_repo.delete_todo(String::from(&parent_id)),
+            Err(TodoErrors::Validation(ref msg)) if msg.contains("--recursive")
+        ));
+
+        let selection = TodoSelection::Ids(vec![String::from(&parent_id)]);
+        let report = todo_repo.delete_todos(&selection, true).unwrap();
+        assert_eq!(2, report.succeeded.len());
+        assert_eq!(2, todo_repo.list_trash().unwrap().len());
+
+        todo_repo.undo(1).unwrap();
+        assert!(todo_repo.list_trash().unwrap().is_empty());
+        assert_eq!(
+            Some(&parent_id),
+            todo_repo
+                .get_todo_by_id(child.id)
+                .unwrap()
+                .parent_id
+                .as_ref()
+        );
+    }
+
+    #[test]
+    fn should_import_links_following_renamed_todos_and_drop_missing_ones() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+        let imported = |id: &str, parent_id: Option<&str>| Todo {
+            id: String::from(id),
+            name: String::from(id),
+            parent_id: parent_id.map(String::from),
+            ..Todo::default()
+        };
+        let taken_id = saved_todos[0].id.as_str();
+
+        let prepared = todo_repo
+            .import_todos(
+                vec![
+                    imported(taken_id, None),
+                    imported("child", Some(taken_id)),
+                    imported("orphan", Some("missing")),
+                    imported("stored", Some(&saved_todos[1].id)),
+                    Todo {
+                        blocked_by: vec![String::from(taken_id), String::from("missing")],
+                        ..imported("blocked", None)
+                    },
+                ],
+                true,
+            )
+            .unwrap();
+
+        assert_ne!(taken_id, prepared[0].id);
+        assert_eq!(Some(&prepared[0].id), prepared[1].parent_id.as_ref());
+        assert_eq!(None, prepared[2].parent_id);
+        assert_eq!(Some(&saved_todos[1].id), prepared[3].parent_id.as_ref());
+        assert_eq!(vec![prepared[0].id.clone()], prepared[4].blocked_by);
+    }
+
+    #[test]
+    fn should_link_todos_refusing_cycles_and_unknown_blockers() {
+        let saved_todos = get_todo_list();
+        let [a, b, c] = [0, 1, 2].map(|index| saved_todos[index].id.clone());
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+
+        todo_repo.link_todos(&a, slice::from_ref(&b)).unwrap();
+        let linked = todo_repo.link_todos(&b, &[c.clone(), c.clone()]).unwrap();
+        assert_eq!(vec![c.clone()], linked.blocked_by);
+
+        assert!(matches!(
+            todo_repo.link_todos(&c, slice::from_ref(&a)),
+            Err(TodoErrors::Conflict(ref msg))
+                if msg.ends_with(&format!("{} -> {} -> {} -> {}", c, a, b, c))
+        ));
+        assert!(matches!(
+            todo_repo.link_todos(&a, &[String::from("missing")]),
+            Err(TodoErrors::NotFound(ref msg)) if msg.starts_with("Blocking todo")
+        ));
+        assert!(matches!(
+            todo_repo.unlink_todos(&a, slice::from_ref(&c)),
+            Err(TodoErrors::NotFound(ref msg)) if msg.contains("is not blocked by")
+        ));
+        assert!(
+            todo_repo
+                .unlink_todos(&a, &[b])
+                .unwrap()
+                .blocked_by
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn should_refuse_completing_blocked_todo_unless_forced_and_list_ready_ones() {
+        let saved_todos = get_todo_list();
+        let [a, b, c] = [0, 1, 2].map(|index| saved_todos[index].id.clone());
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+        todo_repo.link_todos(&a, slice::from_ref(&b)).unwrap();
+
+        let ready_ids = |todo_repo: &mut TodoRepository<CursorDataAccess>| {
+            let ready = todo_repo.ready_todos().unwrap();
+            ready.into_iter().map(|todo| todo.id).collect::<Vec<_>>()
+        };
+        assert_eq!(vec![b.clone(), c.clone()], ready_ids(&mut todo_repo));
+        assert!(matches!(
+            todo_repo.mark_todo_complete(a.clone()),
+            Err(TodoErrors::Validation(ref msg))
+                if msg.contains(&format!("blocked by open todo(s) {}", b))
+        ));
+
+        // Completing the blocker in the same operation unblocks the todo.
+        let both = TodoSelection::Ids(vec![a.clone(), b.clone()]);
+        assert_eq!(
+            2,
+            todo_repo
+                .complete_todos(&both, false, false)
+                .unwrap()
+                .succeeded
+                .len()
+        );
+        todo_repo.undo(1).unwrap();
+
+        let only_a = TodoSelection::Ids(vec![a.clone()]);
+        assert!(
+            todo_repo
+                .complete_todos(&only_a, false, true)
+                .unwrap()
+                .succeeded[0]
+                .completed
+        );
+        assert_eq!(vec![b, c], ready_ids(&mut todo_repo));
+    }
+
+    #[test]
+    fn should_limit_listings_filters_and_new_todos_to_current_list() {
+        let saved_todos = get_todo_list();
+        let mut todo_repo = get_journaled_repository(&saved_todos).with_list(String::from("work"));
+        assert!(todo_repo.get_all_todos().unwrap().is_empty());
+
+        let report = todo_repo
+            .add_todo(&AddCommandArgs {
+                name: String::from("Report"),
+                ..AddCommandArgs::default()
+            })
+            .unwrap();
+        assert_eq!("work", report.list_name());
+        let subtask = todo_repo
+            .add_todo(&AddCommandArgs {
+                name: String::from("Subtask"),
+                parent_id: Some(saved_todos[0].id.clone()),
+                ..AddCommandArgs::default()
+            })
+            .unwrap();
+        assert_eq!("inbox", subtask.list_name());
+        assert_eq!(vec![report.clone()], todo_repo.get_all_todos().unwrap());
+
+        let pending = TodoSelection::Matching(TodoQuery {
+            status: Some(TodoStatus::Pending),
+            ..TodoQuery::default()
+        });
+        let completed = todo_repo.complete_todos(&pending, false, false).unwrap();
+        assert_eq!(
+            vec![report.id],
+            completed
+                .succeeded
+                .iter()
+                .map(|t| t.id.clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn should_move_todos_with_subtasks_and_use_stored_default_list() {
+        let mut saved_todos = get_todo_list();
+        saved_todos[1].parent_id = Some(saved_todos[0].id.clone());
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+
+        let selection = TodoSelection::Ids(vec![saved_todos[0].id.clone()]);
+        let report = todo_repo.move_todos(&selection, "home").unwrap();
+        assert_eq!(2, report.succeeded.len());
+        assert!(
+            report
+                .succeeded
+                .iter()
+                .all(|todo| todo.list_name() == "home")
+        );
+
+        let summaries = todo_repo.list_summaries().unwrap();
+        let names = summaries
+            .iter()
+            .map(|list| list.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["home", "inbox"], names);
+        assert_eq!((2, true), (summaries[0].pending, summaries[1].default));
+
+        todo_repo.set_default_list("home").unwrap();
+        assert_eq!("home", todo_repo.current_list().unwrap());
+        assert_eq!(2, todo_repo.get_all_todos().unwrap().len());
+
+        todo_repo.undo(1).unwrap();
+        assert!(todo_repo.get_all_todos().unwrap().is_empty());
+    }
+
+    fn get_todos_with_ids(ids: &[&str]) -> Vec<Todo> {
+        ids.iter()
+            .zip(get_todo_list().into_iter().cycle())
+            .enumerate()
+            .map(|(position, (id, todo))| Todo {
+                id: String::from(*id),
+                created_at: Some(Utc::now() + Duration::minutes(position as i64)),
+                ..todo
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_resolve_ids_prefixes_positions_and_last() {
+        let mut saved_todos = get_todos_with_ids(&["abc1234", "abd5678", "x2y3z4a", "1ab2cd3"]);
+        saved_todos[3].list = Some(String::from("home"));
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+
+        assert_eq!("abc1234", todo_repo.resolve_id("abc1234").unwrap());
+        assert_eq!("abc1234", todo_repo.resolve_id(" abc ").unwrap());
+        assert_eq!("x2y3z4a", todo_repo.resolve_id("x").unwrap());
+        assert_eq!("abd5678", todo_repo.resolve_id("2").unwrap());
+        assert_eq!("x2y3z4a", todo_repo.resolve_id("@last").unwrap());
+        // Positions count the current list only; past its end a number is read as a prefix.
+        assert_eq!("1ab2cd3", todo_repo.resolve_id("1a").unwrap());
+        assert!(matches!(
+            todo_repo.resolve_id("4"),
+            Err(TodoErrors::NotFound(ref msg)) if msg == "Todo by id:4 not found"
+        ));
+        assert!(matches!(
+            todo_repo.resolve_id(""),
+            Err(TodoErrors::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn should_refuse_ambiguous_prefix_listing_candidates() {
+        let saved_todos = get_todos_with_ids(&["abc1234", "abd5678", "x2y3z4a"]);
+        let mut todo_repo = TodoRepository::new(get_cursor_data_access(&saved_todos));
+
+        let result = todo_repo.resolve_id("ab");
+        assert!(matches!(
+            result,
+            Err(TodoErrors::Validation(ref msg))
+                if msg == "Todo id prefix ab is ambiguous, it matches abc1234 (First Todo), abd5678 (Second Todo)"
+        ));
+        let references = vec![String::from("x"), String::from("ab")];
+        assert!(matches!(
+            todo_repo.resolve_ids(&references),
+            Err(TodoErrors::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn should_resolve_references_keeping_unknown_ones_for_not_found() {
+        let saved_todos = get_todos_with_ids(&["abc1234", "abd5678", "x2y3z4a"]);
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+
+        let references = vec![
+            String::from("abc"),
+            String::from(" 1 "),
+            String::from("@last"),
+            String::from("nope"),
+        ];
+        let todo_ids = todo_repo.resolve_ids(&references).unwrap();
+        assert_eq!(vec!["abc1234", "abc1234", "x2y3z4a", "nope"], todo_ids);
+
+        todo_repo.delete_todo(String::from("x2y3z4a")).unwrap();
+        assert_eq!("abd5678", todo_repo.resolve_id("@last").unwrap());
+        // A trashed todo keeps its full id but no longer answers to a prefix.
+        assert_eq!("x2y3z4a", todo_repo.resolve_id("x2y3z4a").unwrap());
+        assert!(matches!(
+            todo_repo.resolve_id("x2y"),
+            Err(TodoErrors::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn should_only_accept_exact_ids_outside_resolve() {
+        let saved_todos = get_todos_with_ids(&["abc1234", "abd5678", "x2y3z4a"]);
+        let mut todo_repo = get_journaled_repository(&saved_todos);
+
+        assert!(matches!(
+            todo_repo.get_todo_by_id(String::from("1")),
+            Err(TodoErrors::NotFound(_))
+        ));
+        assert!(matches!(
+            todo_repo.delete_todo(String::from("@last")),
+            Err(TodoErrors::NotFound(_))
+        ));
+        let edit_args = EditCommandArgs {
+            id: String::from("abd"),
+            name: Some(String::from("Renamed")),
+            ..EditCommandArgs::default()
+        };
+        assert!(matches!(
+            todo_repo.update_todo(&edit_args),
+            Err(TodoErrors::NotFound(_))
+        ));
+        assert_eq!(saved_todos, todo_repo.get_all_todos().unwrap());
+    }
 }
@@ -0,0 +1,256 @@
+use crate::todo_repo::storage::TodoFilter;
+use crate::todo_repo::{Priority, Todo};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use std::cmp::{Ordering, Reverse};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum TodoStatus {
+    Pending,
+    Done,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum TodoSortKey {
+    /// Oldest first
+    Created,
+    /// Soonest first
+    Due,
+    /// Most urgent first
+    Priority,
+    /// Alphabetical, ignoring case
+    Name,
+}
+
+/// Composable criteria for listing todos. Unset fields match every todo; todos without a
+/// due date, priority or creation time sort after those that have one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TodoQuery {
+    pub status: Option<TodoStatus>,
+    /// Every listed tag must be present on the todo.
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    pub due_before: Option<DateTime<Utc>>,
+    pub due_after: Option<DateTime<Utc>>,
+    /// Case-insensitive substring of the name or description.
+    pub search: Option<String>,
+    pub sort: Option<TodoSortKey>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+impl TodoQuery {
+    /// The part of the query a storage backend can apply while scanning.
+    pub fn storage_filter(&self) -> TodoFilter {
+        TodoFilter {
+            completed: self.status.map(|status| status == TodoStatus::Done),
+            ..TodoFilter::active()
+        }
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        let status_matches = self
+            .status
+            .is_none_or(|status| todo.completed == (status == TodoStatus::Done));
+        let tags_match = self.tags.iter().all(|tag| todo.tags.contains(tag));
+        let priority_matches = self
+            .priority
+            .is_none_or(|priority| todo.priority == Some(priority));
+        let due_before_matches = self
+            .due_before
+            .is_none_or(|before| todo.due.is_some_and(|due| due < before));
+        let due_after_matches = self
+            .due_after
+            .is_none_or(|after| todo.due.is_some_and(|due| due > after));
+        let search_matches = self.search.as_ref().is_none_or(|search| {
+            let search = search.to_lowercase();
+            todo.name.to_lowercase().contains(&search)
+                || todo.description.to_lowercase().contains(&search)
+        });
+        status_matches
+            && tags_match
+            && priority_matches
+            && due_before_matches
+            && due_after_matches
+            && search_matches
+    }
+
+    /// Filters, sorts and paginates `todos`.
+    pub fn apply(&self, todos: Vec<Todo>) -> Vec<Todo> {
+        let mut found: Vec<Todo> = todos
+            .into_iter()
+            .filter(|todo| self.matches(todo))
+            .collect();
+        if let Some(sort) = self.sort {
+            found.sort_by(|a, b| Self::compare(sort, a, b));
+        }
+        found
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    fn compare(sort: TodoSortKey, a: &Todo, b: &Todo) -> Ordering {
+        match sort {
+            TodoSortKey::Created => Self::compare_present_first(a.created_at, b.created_at),
+            TodoSortKey::Due => Self::compare_present_first(a.due, b.due),
+            TodoSortKey::Priority => {
+                Self::compare_present_first(a.priority.map(Reverse), b.priority.map(Reverse))
+            }
+            TodoSortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        }
+    }
+
+    fn compare_present_first<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_repo::TodoRepository;
+    use crate::todo_repo::data_access::CursorDataAccess;
+    use chrono::{Duration, TimeZone};
+    use serde_json::to_string_pretty;
+    use std::io::Cursor;
+
+    fn get_todo(id: &str, name: &str) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(name),
+            description: format!("{} description", name),
+            ..Todo::default()
+        }
+    }
+
+    fn get_todo_list() -> Vec<Todo> {
+        let base = Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap();
+        vec![
+            Todo {
+                priority: Some(Priority::Low),
+                tags: vec![String::from("home")],
+                due: Some(base + Duration::days(3)),
+                created_at: Some(base),
+                ..get_todo("a", "Water plants")
+            },
+            Todo {
+                completed: true,
+                priority: Some(Priority::Urgent),
+                tags: vec![String::from("work"), String::from("finance")],
+                due: Some(base + Duration::days(1)),
+                created_at: Some(base + Duration::hours(1)),
+                ..get_todo("b", "File taxes")
+            },
+            Todo {
+                priority: Some(Priority::High),
+                tags: vec![String::from("work")],
+                created_at: Some(base + Duration::hours(2)),
+                description: String::from("Quarterly numbers for finance"),
+                ..get_todo("c", "budget review")
+            },
+            get_todo("d", "Call mom"),
+        ]
+    }
+
+    fn query_ids(query: &TodoQuery) -> Vec<String> {
+        let input_str = to_string_pretty(&get_todo_list()).unwrap();
+        let cursor_data_access =
+            CursorDataAccess::new(Cursor::new(input_str), Cursor::new(Vec::<u8>::new()));
+        let mut todo_repo = TodoRepository::new(cursor_data_access);
+        todo_repo
+            .query_todos(query)
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.id)
+            .collect()
+    }
+
+    #[test]
+    fn should_return_all_todos_in_stored_order_for_empty_query() {
+        assert_eq!(vec!["a", "b", "c", "d"], query_ids(&TodoQuery::default()));
+    }
+
+    #[test]
+    fn should_filter_by_status() {
+        let pending = TodoQuery {
+            status: Some(TodoStatus::Pending),
+            ..TodoQuery::default()
+        };
+        let done = TodoQuery {
+            status: Some(TodoStatus::Done),
+            ..TodoQuery::default()
+        };
+        assert_eq!(vec!["a", "c", "d"], query_ids(&pending));
+        assert_eq!(vec!["b"], query_ids(&done));
+    }
+
+    #[test]
+    fn should_require_every_tag_and_match_priority() {
+        let work_finance = TodoQuery {
+            tags: vec![String::from("work"), String::from("finance")],
+            ..TodoQuery::default()
+        };
+        let high = TodoQuery {
+            priority: Some(Priority::High),
+            ..TodoQuery::default()
+        };
+        assert_eq!(vec!["b"], query_ids(&work_finance));
+        assert_eq!(vec!["c"], query_ids(&high));
+    }
+
+    #[test]
+    fn should_filter_by_due_window_excluding_todos_without_due() {
+        let base = Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap();
+        let query = TodoQuery {
+            due_after: Some(base),
+            due_before: Some(base + Duration::days(2)),
+            ..TodoQuery::default()
+        };
+        assert_eq!(vec!["b"], query_ids(&query));
+    }
+
+    #[test]
+    fn should_search_name_and_description_case_insensitively() {
+        let query = TodoQuery {
+            search: Some(String::from("FINANCE")),
+            ..TodoQuery::default()
+        };
+        assert_eq!(vec!["c"], query_ids(&query));
+    }
+
+    #[test]
+    fn should_sort_with_missing_values_last() {
+        let by = |sort| TodoQuery {
+            sort: Some(sort),
+            ..TodoQuery::default()
+        };
+        assert_eq!(vec!["b", "a", "c", "d"], query_ids(&by(TodoSortKey::Due)));
+        assert_eq!(
+            vec!["b", "c", "a", "d"],
+            query_ids(&by(TodoSortKey::Priority))
+        );
+        assert_eq!(
+            vec!["a", "b", "c", "d"],
+            query_ids(&by(TodoSortKey::Created))
+        );
+        assert_eq!(vec!["c", "d", "b", "a"], query_ids(&by(TodoSortKey::Name)));
+    }
+
+    #[test]
+    fn should_apply_offset_and_limit_after_sorting() {
+        let query = TodoQuery {
+            sort: Some(TodoSortKey::Name),
+            offset: 1,
+            limit: Some(2),
+            ..TodoQuery::default()
+        };
+        assert_eq!(vec!["d", "b"], query_ids(&query));
+    }
+}
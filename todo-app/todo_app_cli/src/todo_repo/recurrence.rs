@@ -0,0 +1,331 @@
+use chrono::{
+    DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Timelike, Utc,
+    Weekday,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Longest stretch of wall-clock time a DST or time zone change skips, a whole day.
+const MAX_GAP_MINUTES: i64 = 24 * 60;
+
+/// How a todo repeats. Written as `daily`, `weekly`, `weekly:mon,thu`, `monthly:15` or
+/// `every:3` (days), which is also how it is stored.
+///
+/// Dates are worked out in local time and keep the time of day of the due date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    /// On each listed weekday; on the weekday of the due date when the list is empty.
+    Weekly(Vec<Weekday>),
+    /// On this day of the month, or on the last day of shorter months.
+    Monthly(u32),
+    EveryDays(u32),
+}
+
+impl Recurrence {
+    /// The first occurrence strictly after `after`, or none past the last date that can be
+    /// represented.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.next_after_in(&Local, after)
+    }
+
+    fn next_after_in<Tz: TimeZone>(&self, tz: &Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = after.with_timezone(tz);
+        let date = local.date_naive();
+        let next_date = match self {
+            Recurrence::Daily => date.checked_add_days(Days::new(1))?,
+            Recurrence::EveryDays(days) => date.checked_add_days(Days::new(u64::from(*days)))?,
+            // Every weekday occurs within a week, so none is found only at the end of time.
+            Recurrence::Weekly(weekdays) => (1..=7)
+                .map_while(|offset| date.checked_add_days(Days::new(offset)))
+                .find(|candidate| {
+                    if weekdays.is_empty() {
+                        candidate.weekday() == date.weekday()
+                    } else {
+                        weekdays.contains(&candidate.weekday())
+                    }
+                })?,
+            Recurrence::Monthly(day) => {
+                let this_month = Self::day_in_month(date.year(), date.month(), *day)?;
+                if this_month > date {
+                    this_month
+                } else if date.month() == 12 {
+                    Self::day_in_month(date.year() + 1, 1, *day)?
+                } else {
+                    Self::day_in_month(date.year(), date.month() + 1, *day)?
+                }
+            }
+        };
+        Self::first_valid_time(tz, next_date.and_time(local.time()))
+    }
+
+    /// `naive` in `tz`, or the first wall-clock minute after it when a DST change skips it.
+    fn first_valid_time<Tz: TimeZone>(tz: &Tz, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let minute = naive.with_second(0)?.with_nanosecond(0)?;
+        std::iter::once(naive)
+            .chain(
+                (1..=MAX_GAP_MINUTES)
+                    .map_while(|minutes| minute.checked_add_signed(TimeDelta::minutes(minutes))),
+            )
+            .find_map(|candidate| tz.from_local_datetime(&candidate).earliest())
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// Due date for the occurrence after one due at `due`, skipping any that are already past
+    /// `now`. Without a due date the schedule starts from `now`.
+    pub fn next_due(
+        &self,
+        due: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let mut next = self.next_after(due.unwrap_or(now))?;
+        while next <= now {
+            next = self.next_after(next)?;
+        }
+        Some(next)
+    }
+
+    /// None only when the year is past the last one that can be represented.
+    fn day_in_month(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        (1..=day)
+            .rev()
+            .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(weekdays) if weekdays.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly(weekdays) => {
+                let names: Vec<String> = weekdays
+                    .iter()
+                    .map(|weekday| weekday.to_string().to_lowercase())
+                    .collect();
+                write!(f, "weekly:{}", names.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly:{}", day),
+            Recurrence::EveryDays(days) => write!(f, "every:{}", days),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        let invalid = || {
+            format!(
+                "invalid repeat rule '{}', expected daily, weekly[:mon,...], monthly:<day> or every:<days>",
+                value
+            )
+        };
+        let (kind, argument) = match value.split_once(':') {
+            Some((kind, argument)) => (kind, Some(argument.trim())),
+            None => (value.as_str(), None),
+        };
+        match (kind, argument) {
+            ("daily", None) => Ok(Recurrence::Daily),
+            ("weekly", None) => Ok(Recurrence::Weekly(Vec::new())),
+            ("weekly", Some(days)) => {
+                let mut weekdays = days
+                    .split(',')
+                    .map(|day| day.trim().parse::<Weekday>().map_err(|_| invalid()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                weekdays.sort_by_key(Weekday::num_days_from_monday);
+                weekdays.dedup();
+                Ok(Recurrence::Weekly(weekdays))
+            }
+            ("monthly", Some(day)) => match day.parse() {
+                Ok(day @ 1..=31) => Ok(Recurrence::Monthly(day)),
+                _ => Err(invalid()),
+            },
+            ("every", Some(days)) => match days.parse() {
+                Ok(days @ 1..) => Ok(Recurrence::EveryDays(days)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Serialize for Recurrence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult};
+
+    /// UTC+1, moving to UTC+2 when it is 02:00 on 2026-03-29, so 02:00 to 03:00 never happens.
+    #[derive(Debug, Clone)]
+    struct GapZone;
+
+    impl GapZone {
+        fn change() -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2026, 3, 29)
+                .unwrap()
+                .and_hms_opt(2, 0, 0)
+                .unwrap()
+        }
+
+        fn offset(summer: bool) -> FixedOffset {
+            FixedOffset::east_opt(if summer { 7200 } else { 3600 }).unwrap()
+        }
+    }
+
+    impl TimeZone for GapZone {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            GapZone
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            if *local < Self::change() {
+                LocalResult::Single(Self::offset(false))
+            } else if *local < Self::change() + TimeDelta::hours(1) {
+                LocalResult::None
+            } else {
+                LocalResult::Single(Self::offset(true))
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            Self::offset(*utc >= Self::change() - TimeDelta::hours(1))
+        }
+    }
+
+    fn local(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Local
+            .with_ymd_and_hms(year, month, day, 18, 30, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn should_parse_and_display_rules() {
+        for rule in ["daily", "weekly", "weekly:mon,thu", "monthly:15", "every:3"] {
+            assert_eq!(rule, rule.parse::<Recurrence>().unwrap().to_string());
+        }
+        assert_eq!(
+            Ok(Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri])),
+            "Weekly: friday,mon,fri".parse()
+        );
+        for rule in [
+            "hourly",
+            "daily:2",
+            "weekly:funday",
+            "monthly:0",
+            "monthly:32",
+            "every:0",
+            "every",
+        ] {
+            assert!(
+                rule.parse::<Recurrence>().is_err(),
+                "{} should be rejected",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn should_find_next_occurrence_keeping_time_of_day() {
+        // 2026-10-15 is a Thursday.
+        let due = local(2026, 10, 15);
+        assert_eq!(Some(local(2026, 10, 16)), Recurrence::Daily.next_after(due));
+        assert_eq!(
+            Some(local(2026, 10, 25)),
+            Recurrence::EveryDays(10).next_after(due)
+        );
+        assert_eq!(
+            Some(local(2026, 10, 22)),
+            Recurrence::Weekly(Vec::new()).next_after(due)
+        );
+        assert_eq!(
+            Some(local(2026, 10, 19)),
+            Recurrence::Weekly(vec![Weekday::Mon, Weekday::Thu]).next_after(due)
+        );
+        assert_eq!(
+            Some(local(2026, 11, 15)),
+            Recurrence::Monthly(15).next_after(due)
+        );
+        assert_eq!(
+            Some(local(2026, 10, 20)),
+            Recurrence::Monthly(20).next_after(due)
+        );
+    }
+
+    #[test]
+    fn should_clamp_monthly_day_to_shorter_months_and_roll_over_year() {
+        assert_eq!(
+            Some(local(2027, 2, 28)),
+            Recurrence::Monthly(31).next_after(local(2027, 1, 31))
+        );
+        assert_eq!(
+            Some(local(2027, 1, 31)),
+            Recurrence::Monthly(31).next_after(local(2026, 12, 31))
+        );
+    }
+
+    #[test]
+    fn should_skip_occurrences_already_past() {
+        let now = local(2026, 10, 15);
+        assert_eq!(
+            Some(local(2026, 10, 16)),
+            Recurrence::Daily.next_due(Some(local(2026, 10, 1)), now)
+        );
+        assert_eq!(
+            Some(local(2026, 10, 22)),
+            Recurrence::Weekly(Vec::new()).next_due(None, now)
+        );
+    }
+
+    #[test]
+    fn should_move_time_skipped_by_dst_change_to_first_valid_minute() {
+        let after = GapZone
+            .with_ymd_and_hms(2026, 3, 28, 2, 30, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let next = Recurrence::Daily.next_after_in(&GapZone, after).unwrap();
+        assert_eq!(
+            GapZone.with_ymd_and_hms(2026, 3, 29, 3, 0, 0).unwrap(),
+            next.with_timezone(&GapZone)
+        );
+    }
+
+    #[test]
+    fn should_find_no_occurrence_past_the_last_representable_date() {
+        let due = local(2026, 10, 20);
+        assert_eq!(None, Recurrence::EveryDays(100_000_000).next_after(due));
+        assert_eq!(
+            None,
+            Recurrence::EveryDays(100_000_000).next_due(Some(due), due)
+        );
+        let last_day = NaiveDate::MAX.and_hms_opt(12, 0, 0).unwrap().and_utc();
+        assert_eq!(None, Recurrence::Daily.next_after(last_day));
+        assert_eq!(None, Recurrence::Monthly(1).next_after(last_day));
+    }
+}
@@ -0,0 +1,480 @@
+use crate::todo_repo::storage::{JsonDocument, TodoFilter, TodoStorage};
+use crate::todo_repo::{Todo, TodoChange, TodoErrors};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use ureq::{Agent, AgentBuilder};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const RETRIES: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// What a [`RemoteDataAccess`] write transaction changed, sent to `POST /storage` in one
+/// request when it commits.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RemoteChanges {
+    pub changes: Vec<TodoChange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_list: Option<String>,
+}
+
+/// Backend that keeps the todos on a `todo serve` instance instead of a local file.
+///
+/// Reads fetch the whole document from `GET /storage`. A write transaction works on a copy
+/// of the document and commits its changes in one `POST /storage`, where the server applies
+/// them only if no todo changed since the copy was read. That check also makes retrying a
+/// commit safe: a commit applied before its response was lost is never applied twice.
+pub struct RemoteDataAccess {
+    base_url: String,
+    agent: Agent,
+    retries: u32,
+    retry_delay: Duration,
+    transaction: Option<RemoteTransaction>,
+}
+
+struct RemoteTransaction {
+    read: JsonDocument,
+    document: JsonDocument,
+}
+
+impl RemoteDataAccess {
+    /// `base_url` is the address the server prints on startup, such as `http://host:8080`.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: String::from(base_url.trim_end_matches('/')),
+            agent: Self::agent(REQUEST_TIMEOUT),
+            retries: RETRIES,
+            retry_delay: RETRY_DELAY,
+            transaction: None,
+        }
+    }
+
+    /// Gives up on a request after `timeout`, which also bounds connecting.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.agent = Self::agent(timeout);
+        self
+    }
+
+    /// Retries a request that failed to connect, timed out or got a 502, 503 or 504 up to
+    /// `retries` times, waiting `delay` before the first retry and twice as long before each
+    /// next one.
+    pub fn with_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    fn agent(timeout: Duration) -> Agent {
+        AgentBuilder::new()
+            .timeout_connect(CONNECT_TIMEOUT.min(timeout))
+            .timeout(timeout)
+            .build()
+    }
+
+    fn storage_url(&self) -> String {
+        format!("{}/storage", self.base_url)
+    }
+
+    fn send(&self, method: &str, body: Option<&str>) -> Result<String, TodoErrors> {
+        let url = self.storage_url();
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let request = self
+                .agent
+                .request(method, &url)
+                .set("Accept", "application/json");
+            let result = match body {
+                Some(body) => request
+                    .set("Content-Type", "application/json")
+                    .send_string(body),
+                None => request.call(),
+            };
+            let transient = match &result {
+                Err(ureq::Error::Status(status, _)) => matches!(status, 502..=504),
+                Err(ureq::Error::Transport(_)) => true,
+                Ok(_) => false,
+            };
+            if transient && attempt < self.retries {
+                attempt += 1;
+                std::thread::sleep(delay);
+                delay *= 2;
+                continue;
+            }
+            return match result {
+                Ok(response) => response.into_string().map_err(|e| {
+                    TodoErrors::Io(format!("failed to read response from {}: {}", url, e))
+                }),
+                Err(ureq::Error::Status(status, response)) => {
+                    Err(Self::server_error(status, response.into_string().ok()))
+                }
+                Err(ureq::Error::Transport(transport)) => Err(TodoErrors::Io(format!(
+                    "request to {} failed: {}",
+                    url, transport
+                ))),
+            };
+        }
+    }
+
+    // Rebuilds the error from the server's JSON envelope, keeping its category.
+    fn server_error(status: u16, body: Option<String>) -> TodoErrors {
+        let error = body
+            .and_then(|body| serde_json::from_str::<Value>(&body).ok())
+            .map(|mut body| body["error"].take());
+        let kind = error.as_ref().and_then(|error| error["kind"].as_str());
+        let message = error
+            .as_ref()
+            .and_then(|error| error["message"].as_str())
+            .map_or_else(|| format!("server answered {}", status), String::from);
+        match kind {
+            Some("not_found") => TodoErrors::NotFound(message),
+            Some("validation") => TodoErrors::Validation(message),
+            Some("conflict") => TodoErrors::Conflict(message),
+            Some("parse_error") => TodoErrors::parse_error(message),
+            _ => TodoErrors::Io(message),
+        }
+    }
+
+    fn fetch(&self) -> Result<JsonDocument, TodoErrors> {
+        let body = self.send("GET", None)?;
+        JsonDocument::parse(&body)
+    }
+
+    fn read<T>(&mut self, reader: impl FnOnce(&JsonDocument) -> T) -> Result<T, TodoErrors> {
+        match &self.transaction {
+            Some(transaction) => Ok(reader(&transaction.document)),
+            None => Ok(reader(&self.fetch()?)),
+        }
+    }
+
+    fn modify<T>(
+        &mut self,
+        modifier: impl FnOnce(&mut JsonDocument) -> Result<T, TodoErrors>,
+    ) -> Result<T, TodoErrors> {
+        let owns_transaction = self.transaction.is_none();
+        if owns_transaction {
+            self.begin_write()?;
+        }
+        let transaction = self
+            .transaction
+            .as_mut()
+            .expect("write transaction started");
+        let result = modifier(&mut transaction.document);
+        if owns_transaction {
+            match &result {
+                Ok(_) => self.commit()?,
+                Err(_) => self.rollback(),
+            }
+        }
+        result
+    }
+
+    // Every todo that differs between the document as read and as committed, new todos in
+    // the order they were added.
+    fn changes(transaction: &RemoteTransaction) -> RemoteChanges {
+        let read = transaction.read.scan(&TodoFilter::all());
+        let written = transaction.document.scan(&TodoFilter::all());
+        let find =
+            |todos: &[Todo], todo_id: &str| todos.iter().find(|todo| todo.id == todo_id).cloned();
+        let mut changes: Vec<TodoChange> = written
+            .iter()
+            .map(|todo| TodoChange {
+                before: find(&read, &todo.id),
+                after: Some(todo.clone()),
+            })
+            .chain(
+                read.iter()
+                    .filter(|todo| find(&written, &todo.id).is_none())
+                    .map(|todo| TodoChange {
+                        before: Some(todo.clone()),
+                        after: None,
+                    }),
+            )
+            .collect();
+        changes.retain(|change| change.before != change.after);
+        let written_list = &transaction.document.default_list;
+        let default_list = (*written_list != transaction.read.default_list)
+            .then(|| written_list.clone())
+            .flatten();
+        RemoteChanges {
+            changes,
+            default_list,
+        }
+    }
+
+    // Whether the server already holds every `after` state, as after a commit whose
+    // response was lost and that was sent again.
+    fn already_applied(&self, remote_changes: &RemoteChanges) -> Result<bool, TodoErrors> {
+        let current = self.fetch()?;
+        let todos_match = remote_changes
+            .changes
+            .iter()
+            .all(|change| current.get(change.todo_id()) == change.after);
+        let list_matches = remote_changes
+            .default_list
+            .as_ref()
+            .is_none_or(|list| current.default_list.as_ref() == Some(list));
+        Ok(todos_match && list_matches)
+    }
+}
+
+impl TodoStorage for RemoteDataAccess {
+    fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        self.read(|document| document.get(todo_id))
+    }
+
+    fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        self.modify(|document| document.insert(todo))
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        self.modify(|document| document.update(todo))
+    }
+
+    fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        self.modify(|document| Ok(document.remove(todo_id)))
+    }
+
+    fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors> {
+        self.read(|document| document.scan(filter))
+    }
+
+    fn default_list(&mut self) -> Result<Option<String>, TodoErrors> {
+        self.read(|document| document.default_list.clone())
+    }
+
+    fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+        self.modify(|document| {
+            document.default_list = Some(String::from(list));
+            Ok(())
+        })
+    }
+
+    fn begin_write(&mut self) -> Result<(), TodoErrors> {
+        if self.transaction.is_some() {
+            return Err(TodoErrors::Conflict(String::from(
+                "A write transaction is already in progress",
+            )));
+        }
+        let read = self.fetch()?;
+        self.transaction = Some(RemoteTransaction {
+            document: read.clone(),
+            read,
+        });
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), TodoErrors> {
+        let Some(transaction) = self.transaction.take() else {
+            return Ok(());
+        };
+        let remote_changes = Self::changes(&transaction);
+        if remote_changes.changes.is_empty() && remote_changes.default_list.is_none() {
+            return Ok(());
+        }
+        let body = serde_json::to_string(&remote_changes)?;
+        match self.send("POST", Some(&body)) {
+            Err(TodoErrors::Conflict(message)) => match self.already_applied(&remote_changes)? {
+                true => Ok(()),
+                false => Err(TodoErrors::Conflict(message)),
+            },
+            result => result.map(|_| ()),
+        }
+    }
+
+    fn rollback(&mut self) {
+        self.transaction = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::AddCommandArgs;
+    use crate::todo_repo::{SqliteDataAccess, TodoRepository};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+    use tiny_http::{Response, Server};
+
+    /// A server on a free localhost port, stopped when dropped.
+    struct MockServer {
+        url: String,
+        server: Arc<Server>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl MockServer {
+        /// Answers each request with the status and body `handler` returns.
+        fn start(handler: impl Fn(&tiny_http::Request) -> (u16, String) + Send + 'static) -> Self {
+            Self::spawn(move |server| {
+                for request in server.incoming_requests() {
+                    let (status, body) = handler(&request);
+                    let _ = request.respond(Response::from_string(body).with_status_code(status));
+                }
+            })
+        }
+
+        /// Runs `todo serve` over an in-memory SQLite database.
+        fn todo_server() -> Self {
+            Self::spawn(|server| {
+                let storage = SqliteDataAccess::open_in_memory().unwrap();
+                crate::server::serve(&mut TodoRepository::new(storage), server);
+            })
+        }
+
+        fn spawn(run: impl FnOnce(&Server) + Send + 'static) -> Self {
+            let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+            let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+            let running = Arc::clone(&server);
+            let thread = thread::spawn(move || run(&running));
+            Self {
+                url,
+                server,
+                thread: Some(thread),
+            }
+        }
+    }
+
+    impl Drop for MockServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    fn add_args(name: &str) -> AddCommandArgs {
+        AddCommandArgs {
+            name: String::from(name),
+            ..AddCommandArgs::default()
+        }
+    }
+
+    #[test]
+    fn should_run_repository_operations_against_server() {
+        let server = MockServer::todo_server();
+        let mut repo = TodoRepository::new(RemoteDataAccess::new(&format!("{}/", server.url)));
+
+        let milk = repo.add_todo(&add_args("Milk")).unwrap();
+        repo.add_todo(&add_args("Bread")).unwrap();
+        repo.mark_todo_complete(milk.id.clone()).unwrap();
+        let bread = repo.get_todo_by_name(String::from("Bread")).unwrap();
+        repo.delete_todo(bread[0].id.clone()).unwrap();
+        repo.set_default_list("work").unwrap();
+
+        // A second client sees everything the first one committed.
+        let mut other = TodoRepository::new(RemoteDataAccess::new(&server.url));
+        let todos = other.get_all_todos().unwrap();
+        assert!(todos.is_empty(), "inbox is no longer the default list");
+        assert_eq!("work", other.default_list().unwrap());
+        assert!(other.get_todo_by_id(milk.id).unwrap().completed);
+        assert_eq!(
+            "Bread",
+            other.with_list(String::from("inbox")).list_trash().unwrap()[0].name
+        );
+    }
+
+    #[test]
+    fn should_refuse_commit_when_todo_changed_since_read() {
+        let server = MockServer::todo_server();
+        let mut repo = TodoRepository::new(RemoteDataAccess::new(&server.url));
+        let milk = repo.add_todo(&add_args("Milk")).unwrap();
+
+        let mut first = RemoteDataAccess::new(&server.url);
+        first.begin_write().unwrap();
+        let mut renamed = milk.clone();
+        renamed.name = String::from("Oat milk");
+        first.update(&renamed).unwrap();
+
+        repo.mark_todo_complete(milk.id.clone()).unwrap();
+        assert!(matches!(first.commit(), Err(TodoErrors::Conflict(_))));
+        assert_eq!("Milk", repo.get_todo_by_id(milk.id).unwrap().name);
+    }
+
+    #[test]
+    fn should_retry_transient_failures() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&requests);
+        let server = MockServer::start(move |_| match counted.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => (503, String::new()),
+            _ => (
+                200,
+                String::from(
+                    r#"{"version": 7, "todos": [{"id": "a", "name": "Milk", "description": "", "completed": false}]}"#,
+                ),
+            ),
+        });
+        let mut storage =
+            RemoteDataAccess::new(&server.url).with_retries(2, Duration::from_millis(1));
+        assert_eq!(1, storage.scan(&TodoFilter::all()).unwrap().len());
+        assert_eq!(3, requests.load(Ordering::SeqCst));
+
+        let mut impatient =
+            RemoteDataAccess::new(&server.url).with_retries(0, Duration::from_millis(1));
+        requests.store(0, Ordering::SeqCst);
+        assert!(matches!(
+            impatient.scan(&TodoFilter::all()),
+            Err(TodoErrors::Io(ref message)) if message.contains("server answered 503")
+        ));
+    }
+
+    #[test]
+    fn should_time_out_slow_server_and_keep_error_categories() {
+        let server = MockServer::start(|request| {
+            if request.url().contains("slow") {
+                thread::sleep(Duration::from_millis(500));
+            }
+            (
+                409,
+                String::from(r#"{"error": {"kind": "conflict", "message": "taken"}}"#),
+            )
+        });
+        let mut slow = RemoteDataAccess::new(&format!("{}/slow", server.url))
+            .with_timeout(Duration::from_millis(100))
+            .with_retries(0, Duration::ZERO);
+        assert!(matches!(slow.get("a"), Err(TodoErrors::Io(_))));
+
+        let mut storage = RemoteDataAccess::new(&server.url);
+        assert_eq!(
+            Err(TodoErrors::Conflict(String::from("taken"))),
+            storage.get("a")
+        );
+    }
+
+    #[test]
+    fn should_treat_conflicting_commit_already_applied_as_success() {
+        let applied = Arc::new(Mutex::new(false));
+        let server_applied = Arc::clone(&applied);
+        // Answers the first commit with a conflict although it holds the committed state.
+        let server = MockServer::start(move |request| {
+            let mut applied = server_applied.lock().unwrap();
+            match (request.method(), *applied) {
+                (tiny_http::Method::Post, _) => {
+                    *applied = true;
+                    (
+                        409,
+                        String::from(r#"{"error": {"kind": "conflict", "message": "changed"}}"#),
+                    )
+                }
+                (_, false) => (200, String::from(r#"{"version": 7, "todos": []}"#)),
+                (_, true) => (
+                    200,
+                    String::from(
+                        r#"{"version": 7, "todos": [{"id": "a", "name": "Milk", "description": "", "completed": false}]}"#,
+                    ),
+                ),
+            }
+        });
+        let mut storage = RemoteDataAccess::new(&server.url);
+        let todo = Todo {
+            id: String::from("a"),
+            name: String::from("Milk"),
+            ..Todo::default()
+        };
+        assert_eq!(Ok(()), storage.insert(&todo));
+        assert!(*applied.lock().unwrap());
+    }
+}
@@ -0,0 +1,366 @@
+use crate::todo_repo::{Todo, TodoErrors};
+use serde::Serialize;
+use strsim::damerau_levenshtein;
+
+/// How much a match counts towards the score, by the field it is found in.
+const NAME_WEIGHT: f64 = 3.0;
+const TAG_WEIGHT: f64 = 2.0;
+const DESCRIPTION_WEIGHT: f64 = 1.0;
+/// Share of a match's value lost per typo, and for a prefix that is not the whole word.
+const TYPO_PENALTY: f64 = 0.3;
+const PREFIX_PENALTY: f64 = 0.2;
+
+#[derive(Debug, Clone, PartialEq)]
+enum SearchTerm {
+    /// Matches a word with up to [`allowed_typos`] letters wrong, missing, extra or swapped.
+    Word(String),
+    /// Matches every word starting with the text, written as `text*`.
+    Prefix(String),
+    /// Matches the exact words one after another, written in double quotes.
+    Phrase(Vec<String>),
+}
+
+/// Words to look for in the name, description and tags of a todo, all of which have to match.
+///
+/// Words are compared ignoring case and anything but letters and digits, so `e-mail` is the
+/// phrase `"e mail"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    terms: Vec<SearchTerm>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchField {
+    Name,
+    Description,
+    Tags,
+}
+
+/// Byte range of a matched word in a field; `tag` is the position of the tag it is in.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchMatch {
+    pub field: SearchField,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<usize>,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub score: f64,
+    pub todo: Todo,
+    pub matches: Vec<SearchMatch>,
+}
+
+struct Token {
+    start: usize,
+    end: usize,
+    word: String,
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(token_start)) => {
+                tokens.push(Token {
+                    start: token_start,
+                    end: index,
+                    word: text[token_start..index].to_lowercase(),
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// Short words have to match exactly, or almost anything would.
+fn allowed_typos(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+fn words(text: &str) -> Vec<String> {
+    tokenize(text).into_iter().map(|token| token.word).collect()
+}
+
+impl SearchQuery {
+    /// Parses words, `prefix*` and `"quoted phrases"` separated by whitespace.
+    pub fn parse(query: &str) -> Result<Self, TodoErrors> {
+        let mut terms = Vec::new();
+        let mut rest = query.trim_start();
+        while !rest.is_empty() {
+            let (chunk, quoted) = match rest.strip_prefix('"') {
+                Some(phrase) => {
+                    let end = phrase.find('"').ok_or_else(|| {
+                        TodoErrors::Validation(String::from("Search query has an unclosed quote"))
+                    })?;
+                    rest = &phrase[end + 1..];
+                    (&phrase[..end], true)
+                }
+                None => {
+                    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                    let chunk = &rest[..end];
+                    rest = &rest[end..];
+                    (chunk, false)
+                }
+            };
+            rest = rest.trim_start();
+
+            let mut chunk_words = words(chunk);
+            let term = match chunk_words.len() {
+                0 => continue,
+                1 if !quoted && chunk.ends_with('*') => SearchTerm::Prefix(chunk_words.remove(0)),
+                1 if !quoted => SearchTerm::Word(chunk_words.remove(0)),
+                _ => SearchTerm::Phrase(chunk_words),
+            };
+            terms.push(term);
+        }
+        if terms.is_empty() {
+            return Err(TodoErrors::Validation(String::from(
+                "Search query has no words to look for",
+            )));
+        }
+        Ok(Self { terms })
+    }
+
+    /// The todos matching every term, most relevant first. Todos scoring the same keep their
+    /// order.
+    pub fn rank(&self, todos: Vec<Todo>) -> Vec<SearchHit> {
+        let mut hits = todos
+            .into_iter()
+            .filter_map(|todo| self.hit(todo))
+            .collect::<Vec<_>>();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits
+    }
+
+    fn hit(&self, todo: Todo) -> Option<SearchHit> {
+        let mut fields = vec![
+            (SearchField::Name, None, tokenize(&todo.name)),
+            (SearchField::Description, None, tokenize(&todo.description)),
+        ];
+        for (index, tag) in todo.tags.iter().enumerate() {
+            fields.push((SearchField::Tags, Some(index), tokenize(tag)));
+        }
+
+        let mut score = 0.0;
+        let mut matches = Vec::new();
+        for term in &self.terms {
+            let mut best: f64 = 0.0;
+            for (field, tag, tokens) in &fields {
+                let weight = match field {
+                    SearchField::Name => NAME_WEIGHT,
+                    SearchField::Description => DESCRIPTION_WEIGHT,
+                    SearchField::Tags => TAG_WEIGHT,
+                };
+                for (quality, start, end) in term.find(tokens) {
+                    best = best.max(weight * quality);
+                    matches.push(SearchMatch {
+                        field: *field,
+                        tag: *tag,
+                        start,
+                        end,
+                    });
+                }
+            }
+            if best == 0.0 {
+                return None;
+            }
+            score += best;
+        }
+        matches.sort_by_key(|m| (m.field as u8, m.tag, m.start));
+        matches.dedup();
+        Some(SearchHit {
+            score,
+            todo,
+            matches,
+        })
+    }
+}
+
+impl SearchTerm {
+    /// Every match in `tokens` with its byte range and its quality: 1 per exactly matched word,
+    /// less for a typo or a prefix.
+    fn find(&self, tokens: &[Token]) -> Vec<(f64, usize, usize)> {
+        match self {
+            SearchTerm::Word(word) => tokens
+                .iter()
+                .filter_map(|token| {
+                    let typos = damerau_levenshtein(word, &token.word);
+                    (typos <= allowed_typos(word)).then(|| {
+                        let quality = 1.0 - TYPO_PENALTY * typos as f64;
+                        (quality, token.start, token.end)
+                    })
+                })
+                .collect(),
+            SearchTerm::Prefix(prefix) => tokens
+                .iter()
+                .filter(|token| token.word.starts_with(prefix.as_str()))
+                .map(|token| {
+                    let quality = match token.word == *prefix {
+                        true => 1.0,
+                        false => 1.0 - PREFIX_PENALTY,
+                    };
+                    (quality, token.start, token.end)
+                })
+                .collect(),
+            // A phrase is worth one word per word in it.
+            SearchTerm::Phrase(phrase) => tokens
+                .windows(phrase.len())
+                .filter(|window| {
+                    window
+                        .iter()
+                        .zip(phrase)
+                        .all(|(token, word)| token.word == *word)
+                })
+                .map(|window| {
+                    let quality = phrase.len() as f64;
+                    (quality, window[0].start, window[phrase.len() - 1].end)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(name: &str, description: &str, tags: &[&str]) -> Todo {
+        Todo {
+            id: String::from(name),
+            name: String::from(name),
+            description: String::from(description),
+            tags: tags.iter().map(|tag| String::from(*tag)).collect(),
+            ..Todo::default()
+        }
+    }
+
+    fn ranked_names(query: &str, todos: Vec<Todo>) -> Vec<String> {
+        SearchQuery::parse(query)
+            .unwrap()
+            .rank(todos)
+            .into_iter()
+            .map(|hit| hit.todo.name)
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_words_prefixes_and_phrases() {
+        let query = SearchQuery::parse(r#"Milk  rep* "Oat drink" e-mail"#).unwrap();
+        assert_eq!(
+            vec![
+                SearchTerm::Word(String::from("milk")),
+                SearchTerm::Prefix(String::from("rep")),
+                SearchTerm::Phrase(vec![String::from("oat"), String::from("drink")]),
+                SearchTerm::Phrase(vec![String::from("e"), String::from("mail")]),
+            ],
+            query.terms
+        );
+    }
+
+    #[test]
+    fn should_reject_query_without_words_or_with_open_quote() {
+        assert!(matches!(
+            SearchQuery::parse("  - * "),
+            Err(TodoErrors::Validation(_))
+        ));
+        assert!(matches!(
+            SearchQuery::parse(r#"milk "oat"#),
+            Err(TodoErrors::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn should_tolerate_typos_in_longer_words_only() {
+        let todos = vec![todo("Buy groceries", "", &[]), todo("Call mom", "", &[])];
+        assert_eq!(
+            vec!["Buy groceries"],
+            ranked_names("grocereis", todos.clone())
+        );
+        assert_eq!(
+            vec!["Buy groceries"],
+            ranked_names("grocries", todos.clone())
+        );
+        assert!(ranked_names("cal", todos.clone()).is_empty());
+        assert!(ranked_names("mop", todos).is_empty());
+    }
+
+    #[test]
+    fn should_match_prefix_and_exact_phrase() {
+        let todos = vec![
+            todo("Write report", "quarterly numbers", &[]),
+            todo("Read reports", "", &[]),
+            todo("Numbers quarterly", "", &[]),
+        ];
+        assert_eq!(
+            vec!["Write report", "Read reports"],
+            ranked_names("rep*", todos.clone())
+        );
+        assert_eq!(
+            vec!["Write report"],
+            ranked_names(r#""quarterly numbers""#, todos)
+        );
+    }
+
+    #[test]
+    fn should_require_every_term_and_rank_name_over_tags_over_description() {
+        let todos = vec![
+            todo("Call Bob", "about the milk", &[]),
+            todo("Groceries", "", &["milk"]),
+            todo("Milk", "", &[]),
+            todo("Milk and bread", "", &["shop"]),
+        ];
+        assert_eq!(
+            vec!["Milk", "Milk and bread", "Groceries", "Call Bob"],
+            ranked_names("milk", todos.clone())
+        );
+        assert_eq!(vec!["Milk and bread"], ranked_names("milk shop", todos));
+    }
+
+    #[test]
+    fn should_rank_exact_match_over_typo() {
+        let todos = vec![
+            todo("Check the mail", "", &[]),
+            todo("Check the mall", "", &[]),
+        ];
+        assert_eq!(
+            vec!["Check the mall", "Check the mail"],
+            ranked_names("mall", todos)
+        );
+    }
+
+    #[test]
+    fn should_report_byte_ranges_of_matches_in_each_field() {
+        let hits = SearchQuery::parse("milk").unwrap().rank(vec![todo(
+            "Café milk",
+            "Milk, milk",
+            &["oat-milk"],
+        )]);
+        let ranges = hits[0]
+            .matches
+            .iter()
+            .map(|m| (m.field, m.tag, m.start, m.end))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (SearchField::Name, None, 6, 10),
+                (SearchField::Description, None, 0, 4),
+                (SearchField::Description, None, 6, 10),
+                (SearchField::Tags, Some(0), 4, 8),
+            ],
+            ranges
+        );
+        assert_eq!(NAME_WEIGHT, hits[0].score);
+    }
+}
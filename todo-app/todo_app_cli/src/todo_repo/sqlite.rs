@@ -0,0 +1,538 @@
+use crate::todo_repo::storage::{TodoFilter, TodoStorage};
+use crate::todo_repo::{DEFAULT_LIST, Priority, Recurrence, Todo, TodoErrors};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Type, Value, ValueRef};
+use rusqlite::{
+    Connection, OptionalExtension, Row, ToSql, TransactionBehavior, params, params_from_iter,
+};
+use std::time::Duration;
+
+// Each entry upgrades the schema by one version, tracked through `PRAGMA user_version`.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE todos (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        description TEXT NOT NULL,
+        completed INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX idx_todos_name ON todos (name COLLATE NOCASE);",
+    "ALTER TABLE todos ADD COLUMN due TEXT;
+    ALTER TABLE todos ADD COLUMN priority TEXT;
+    ALTER TABLE todos ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE todos ADD COLUMN created_at TEXT;
+    ALTER TABLE todos ADD COLUMN updated_at TEXT;
+    ALTER TABLE todos ADD COLUMN completed_at TEXT;
+    CREATE INDEX idx_todos_due ON todos (due);",
+    "ALTER TABLE todos ADD COLUMN deleted_at TEXT;",
+    "ALTER TABLE todos ADD COLUMN recurrence TEXT;",
+    "ALTER TABLE todos ADD COLUMN parent_id TEXT;
+    CREATE INDEX idx_todos_parent_id ON todos (parent_id);",
+    "ALTER TABLE todos ADD COLUMN blocked_by TEXT NOT NULL DEFAULT '[]';",
+    "ALTER TABLE todos ADD COLUMN list TEXT;
+    CREATE INDEX idx_todos_list ON todos (list);
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY NOT NULL,
+        value TEXT NOT NULL
+    );",
+];
+
+// Column order matches `SqliteDataAccess::todo_values`; `id` must stay first.
+const TODO_COLUMNS: [&str; 15] = [
+    "id",
+    "name",
+    "description",
+    "completed",
+    "due",
+    "priority",
+    "tags",
+    "created_at",
+    "updated_at",
+    "completed_at",
+    "deleted_at",
+    "recurrence",
+    "parent_id",
+    "blocked_by",
+    "list",
+];
+
+// Key of the `settings` row holding the default list.
+const DEFAULT_LIST_SETTING: &str = "default_list";
+
+// How long a writer waits for another process holding the database lock.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct SqliteDataAccess {
+    connection: Connection,
+}
+
+impl SqliteDataAccess {
+    pub fn open(file_path: &str) -> Result<Self, TodoErrors> {
+        let connection = Connection::open(file_path)?;
+        Self::with_connection(connection)
+    }
+
+    pub fn open_in_memory() -> Result<Self, TodoErrors> {
+        let connection = Connection::open_in_memory()?;
+        Self::with_connection(connection)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, TodoErrors> {
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        let mut data_access = Self { connection };
+        data_access.migrate()?;
+        Ok(data_access)
+    }
+
+    fn migrate(&mut self) -> Result<(), TodoErrors> {
+        // An immediate transaction keeps concurrent processes from migrating the same file twice.
+        let tx = self
+            .connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let version: usize = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            tx.execute_batch(migration)
+                .and_then(|_| tx.pragma_update(None, "user_version", index + 1))?;
+        }
+        tx.commit().map_err(TodoErrors::from)
+    }
+
+    fn select_columns() -> String {
+        TODO_COLUMNS.join(", ")
+    }
+
+    fn todo_values(todo: &Todo) -> Result<Vec<Box<dyn ToSql>>, TodoErrors> {
+        let tags = serde_json::to_string(&todo.tags)?;
+        let blocked_by = serde_json::to_string(&todo.blocked_by)?;
+        Ok(vec![
+            Box::new(todo.id.clone()),
+            Box::new(todo.name.clone()),
+            Box::new(todo.description.clone()),
+            Box::new(todo.completed),
+            Box::new(todo.due),
+            Box::new(todo.priority),
+            Box::new(tags),
+            Box::new(todo.created_at),
+            Box::new(todo.updated_at),
+            Box::new(todo.completed_at),
+            Box::new(todo.deleted_at),
+            Box::new(todo.recurrence.clone()),
+            Box::new(todo.parent_id.clone()),
+            Box::new(blocked_by),
+            Box::new(todo.list.clone()),
+        ])
+    }
+
+    // List columns hold a JSON array of strings.
+    fn string_list(row: &Row, column: &str) -> rusqlite::Result<Vec<String>> {
+        let value: String = row.get(column)?;
+        serde_json::from_str(&value).map_err(|e| {
+            let index = TODO_COLUMNS.iter().position(|name| *name == column);
+            rusqlite::Error::FromSqlConversionFailure(
+                index.unwrap_or_default(),
+                Type::Text,
+                Box::new(e),
+            )
+        })
+    }
+
+    fn row_to_todo(row: &Row) -> rusqlite::Result<Todo> {
+        Ok(Todo {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            completed: row.get("completed")?,
+            due: row.get("due")?,
+            priority: row.get("priority")?,
+            tags: Self::string_list(row, "tags")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+            completed_at: row.get("completed_at")?,
+            deleted_at: row.get("deleted_at")?,
+            recurrence: row.get("recurrence")?,
+            parent_id: row.get("parent_id")?,
+            blocked_by: Self::string_list(row, "blocked_by")?,
+            list: row.get("list")?,
+        })
+    }
+}
+
+impl ToSql for Priority {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for Priority {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let text = value.as_str()?;
+        Priority::parse(text)
+            .ok_or_else(|| FromSqlError::Other(format!("unknown priority '{}'", text).into()))
+    }
+}
+
+impl ToSql for Recurrence {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for Recurrence {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+impl TodoStorage for SqliteDataAccess {
+    fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        self.connection
+            .query_row(
+                &format!("SELECT {} FROM todos WHERE id = ?1", Self::select_columns()),
+                params![todo_id],
+                Self::row_to_todo,
+            )
+            .optional()
+            .map_err(TodoErrors::from)
+    }
+
+    fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        let placeholders: Vec<String> = (1..=TODO_COLUMNS.len())
+            .map(|index| format!("?{}", index))
+            .collect();
+        self.connection
+            .execute(
+                &format!(
+                    "INSERT INTO todos ({}) VALUES ({})",
+                    Self::select_columns(),
+                    placeholders.join(", ")
+                ),
+                params_from_iter(Self::todo_values(todo)?),
+            )
+            .map(|_| ())
+            .map_err(TodoErrors::from)
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        let assignments: Vec<String> = TODO_COLUMNS
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(index, column)| format!("{} = ?{}", column, index + 1))
+            .collect();
+        let updated = self.connection.execute(
+            &format!("UPDATE todos SET {} WHERE id = ?1", assignments.join(", ")),
+            params_from_iter(Self::todo_values(todo)?),
+        )?;
+        if updated == 0 {
+            return Err(TodoErrors::NotFound(format!(
+                "Todo by id:{} not found",
+                todo.id
+            )));
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        let removed = self.get(todo_id)?;
+        if removed.is_some() {
+            self.connection
+                .execute("DELETE FROM todos WHERE id = ?1", params![todo_id])?;
+        }
+        Ok(removed)
+    }
+
+    fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors> {
+        let mut conditions = Vec::<&str>::new();
+        let mut values = Vec::<Value>::new();
+        if let Some(completed) = filter.completed {
+            conditions.push("completed = ?");
+            values.push(Value::Integer(completed as i64));
+        }
+        match filter.deleted {
+            Some(true) => conditions.push("deleted_at IS NOT NULL"),
+            Some(false) => conditions.push("deleted_at IS NULL"),
+            None => {}
+        }
+        if let Some(list) = &filter.list {
+            conditions.push("IFNULL(list, ?) = ?");
+            values.push(Value::Text(String::from(DEFAULT_LIST)));
+            values.push(Value::Text(list.clone()));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {} FROM todos {} ORDER BY rowid",
+            Self::select_columns(),
+            where_clause
+        ))?;
+        let todos: Vec<Todo> = statement
+            .query_map(params_from_iter(values), Self::row_to_todo)
+            .and_then(|rows| rows.collect())?;
+        // The name is matched here rather than with LIKE, which folds the case of ASCII only.
+        Ok(todos
+            .into_iter()
+            .filter(|todo| filter.matches(todo))
+            .collect())
+    }
+
+    fn default_list(&mut self) -> Result<Option<String>, TodoErrors> {
+        self.connection
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                params![DEFAULT_LIST_SETTING],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(TodoErrors::from)
+    }
+
+    fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+        self.connection
+            .execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2)
+                ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![DEFAULT_LIST_SETTING, list],
+            )
+            .map(|_| ())
+            .map_err(TodoErrors::from)
+    }
+
+    fn begin_write(&mut self) -> Result<(), TodoErrors> {
+        self.connection
+            .execute_batch("BEGIN IMMEDIATE")
+            .map_err(TodoErrors::from)
+    }
+
+    fn commit(&mut self) -> Result<(), TodoErrors> {
+        self.connection
+            .execute_batch("COMMIT")
+            .map_err(TodoErrors::from)
+    }
+
+    fn rollback(&mut self) {
+        let _ = self.connection.execute_batch("ROLLBACK");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_todo(id: &str, name: &str) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(name),
+            description: format!("{} description", name),
+            completed: false,
+            ..Todo::default()
+        }
+    }
+
+    fn get_data_access_with(todos: &[Todo]) -> SqliteDataAccess {
+        let mut data_access = SqliteDataAccess::open_in_memory().unwrap();
+        todos
+            .iter()
+            .for_each(|todo| data_access.insert(todo).unwrap());
+        data_access
+    }
+
+    #[test]
+    fn should_create_schema_at_latest_version() {
+        let data_access = SqliteDataAccess::open_in_memory().unwrap();
+        let version: usize = data_access
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(MIGRATIONS.len(), version);
+    }
+
+    #[test]
+    fn should_return_all_todos_in_insertion_order() {
+        let todos = vec![get_todo("b", "Second"), get_todo("a", "First")];
+        let mut data_access = get_data_access_with(&todos);
+        assert_eq!(todos, data_access.scan(&TodoFilter::all()).unwrap());
+    }
+
+    #[test]
+    fn should_get_todo_by_id_or_none_when_absent() {
+        let todos = vec![get_todo("a", "First"), get_todo("b", "Second")];
+        let mut data_access = get_data_access_with(&todos);
+        assert_eq!(Some(todos[1].clone()), data_access.get("b").unwrap());
+        assert_eq!(None, data_access.get("z").unwrap());
+    }
+
+    #[test]
+    fn should_reject_duplicate_id_on_insert() {
+        let todos = vec![get_todo("a", "First")];
+        let mut data_access = get_data_access_with(&todos);
+        let insert_result = data_access.insert(&todos[0]);
+        assert!(matches!(insert_result, Err(TodoErrors::Conflict(_))));
+    }
+
+    #[test]
+    fn should_update_single_row() {
+        let todos = vec![get_todo("a", "First"), get_todo("b", "Second")];
+        let mut data_access = get_data_access_with(&todos);
+        let mut updated = todos[0].clone();
+        updated.completed = true;
+        data_access.update(&updated).unwrap();
+        assert_eq!(
+            vec![updated, todos[1].clone()],
+            data_access.scan(&TodoFilter::all()).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_return_err_when_updating_absent_todo() {
+        let mut data_access = get_data_access_with(&[]);
+        let update_result = data_access.update(&get_todo("z", "Missing"));
+        assert!(
+            matches!(update_result, Err(TodoErrors::NotFound(ref msg)) if msg.contains("Todo by id:z not found"))
+        );
+    }
+
+    #[test]
+    fn should_remove_todo_and_return_it() {
+        let todos = vec![get_todo("a", "First"), get_todo("b", "Second")];
+        let mut data_access = get_data_access_with(&todos);
+        assert_eq!(Some(todos[0].clone()), data_access.remove("a").unwrap());
+        assert_eq!(None, data_access.remove("a").unwrap());
+        assert_eq!(
+            vec![todos[1].clone()],
+            data_access.scan(&TodoFilter::all()).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_scan_with_name_and_completed_filter() {
+        let mut done = get_todo("c", "Buy bread");
+        done.completed = true;
+        let todos = vec![
+            get_todo("a", "Buy milk"),
+            get_todo("b", "100%_done"),
+            done,
+            get_todo("d", "ÉTÉ CAMP"),
+        ];
+        let mut data_access = get_data_access_with(&todos);
+        let buy_pending = TodoFilter {
+            name_contains: Some(String::from("BUY")),
+            completed: Some(false),
+            ..TodoFilter::default()
+        };
+        let literal_wildcards = TodoFilter {
+            name_contains: Some(String::from("%_")),
+            ..TodoFilter::default()
+        };
+        assert_eq!(
+            vec![todos[0].clone()],
+            data_access.scan(&buy_pending).unwrap()
+        );
+        assert_eq!(
+            vec![todos[1].clone()],
+            data_access.scan(&literal_wildcards).unwrap()
+        );
+        let accented = TodoFilter {
+            name_contains: Some(String::from("été")),
+            ..TodoFilter::default()
+        };
+        assert_eq!(vec![todos[3].clone()], data_access.scan(&accented).unwrap());
+    }
+
+    #[test]
+    fn should_scan_active_or_trashed_todos() {
+        let trashed = Todo {
+            deleted_at: Some(chrono::Utc::now()),
+            ..get_todo("b", "Second")
+        };
+        let todos = vec![get_todo("a", "First"), trashed];
+        let mut data_access = get_data_access_with(&todos);
+        assert_eq!(
+            vec![todos[0].clone()],
+            data_access.scan(&TodoFilter::active()).unwrap()
+        );
+        assert_eq!(
+            vec![todos[1].clone()],
+            data_access.scan(&TodoFilter::trashed()).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_scan_by_list_counting_unset_list_as_inbox_and_store_default() {
+        let work = Todo {
+            list: Some(String::from("work")),
+            ..get_todo("a", "Report")
+        };
+        let todos = vec![work, get_todo("b", "Groceries")];
+        let mut data_access = get_data_access_with(&todos);
+        let in_list = |list: &str| TodoFilter {
+            list: Some(String::from(list)),
+            ..TodoFilter::default()
+        };
+        assert_eq!(
+            vec![todos[0].clone()],
+            data_access.scan(&in_list("work")).unwrap()
+        );
+        assert_eq!(
+            vec![todos[1].clone()],
+            data_access.scan(&in_list("inbox")).unwrap()
+        );
+
+        assert_eq!(None, data_access.default_list().unwrap());
+        data_access.set_default_list("home").unwrap();
+        data_access.set_default_list("work").unwrap();
+        assert_eq!(
+            Some(String::from("work")),
+            data_access.default_list().unwrap()
+        );
+    }
+
+    #[test]
+    fn should_discard_writes_on_rollback() {
+        let todos = vec![get_todo("a", "First")];
+        let mut data_access = get_data_access_with(&todos);
+        data_access.begin_write().unwrap();
+        data_access.insert(&get_todo("b", "Second")).unwrap();
+        data_access.remove("a").unwrap();
+        data_access.rollback();
+        assert_eq!(todos, data_access.scan(&TodoFilter::all()).unwrap());
+    }
+
+    #[test]
+    fn should_round_trip_due_priority_tags_and_timestamps() {
+        let now = chrono::Utc::now();
+        let todo = Todo {
+            due: Some(now),
+            priority: Some(Priority::High),
+            tags: vec![String::from("work"), String::from("urgent")],
+            created_at: Some(now),
+            updated_at: Some(now),
+            ..get_todo("a", "First")
+        };
+        let mut data_access = get_data_access_with(std::slice::from_ref(&todo));
+        assert_eq!(Some(todo), data_access.get("a").unwrap());
+    }
+
+    #[test]
+    fn should_migrate_version_one_database_keeping_rows() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(MIGRATIONS[0]).unwrap();
+        connection.pragma_update(None, "user_version", 1).unwrap();
+        connection
+            .execute(
+                "INSERT INTO todos (id, name, description, completed) VALUES ('a', 'Old', 'Legacy', 1)",
+                [],
+            )
+            .unwrap();
+
+        let mut data_access = SqliteDataAccess::with_connection(connection).unwrap();
+        let todo = data_access.get("a").unwrap().unwrap();
+        assert!(todo.completed);
+        assert_eq!(None, todo.priority);
+        assert!(todo.tags.is_empty());
+    }
+}
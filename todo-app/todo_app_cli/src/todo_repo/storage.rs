@@ -0,0 +1,364 @@
+use crate::todo_repo::{Todo, TodoErrors};
+use serde::Serialize;
+use serde_json::{Value, from_str, from_value, to_string_pretty};
+
+/// Version written into every JSON data file; bump it alongside a step in `JsonDocument::migrate`.
+pub const SCHEMA_VERSION: u64 = 7;
+
+/// Record-level access to persisted todos, implemented by every storage backend.
+pub trait TodoStorage {
+    fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors>;
+    fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors>;
+    fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors>;
+    fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors>;
+    /// Returns the todos matching `filter`, in insertion order.
+    fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors>;
+    /// The list chosen with `set_default_list`, stored alongside the todos.
+    fn default_list(&mut self) -> Result<Option<String>, TodoErrors>;
+    fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors>;
+
+    /// Starts a read-modify-write cycle; other writers are held off until `commit` or `rollback`.
+    fn begin_write(&mut self) -> Result<(), TodoErrors> {
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), TodoErrors> {
+        Ok(())
+    }
+
+    fn rollback(&mut self) {}
+}
+
+impl<S: TodoStorage + ?Sized> TodoStorage for Box<S> {
+    fn get(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        (**self).get(todo_id)
+    }
+
+    fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        (**self).insert(todo)
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        (**self).update(todo)
+    }
+
+    fn remove(&mut self, todo_id: &str) -> Result<Option<Todo>, TodoErrors> {
+        (**self).remove(todo_id)
+    }
+
+    fn scan(&mut self, filter: &TodoFilter) -> Result<Vec<Todo>, TodoErrors> {
+        (**self).scan(filter)
+    }
+
+    fn default_list(&mut self) -> Result<Option<String>, TodoErrors> {
+        (**self).default_list()
+    }
+
+    fn set_default_list(&mut self, list: &str) -> Result<(), TodoErrors> {
+        (**self).set_default_list(list)
+    }
+
+    fn begin_write(&mut self) -> Result<(), TodoErrors> {
+        (**self).begin_write()
+    }
+
+    fn commit(&mut self) -> Result<(), TodoErrors> {
+        (**self).commit()
+    }
+
+    fn rollback(&mut self) {
+        (**self).rollback()
+    }
+}
+
+/// Criteria a backend applies while scanning; unset fields match every todo.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TodoFilter {
+    /// Case-insensitive substring of the todo name.
+    pub name_contains: Option<String>,
+    pub completed: Option<bool>,
+    /// `Some(false)` skips todos in the trash, `Some(true)` returns only those.
+    pub deleted: Option<bool>,
+    /// Name of the list, see [`Todo::list_name`].
+    pub list: Option<String>,
+}
+
+impl TodoFilter {
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Every todo that is not in the trash.
+    pub fn active() -> Self {
+        Self {
+            deleted: Some(false),
+            ..Self::default()
+        }
+    }
+
+    pub fn trashed() -> Self {
+        Self {
+            deleted: Some(true),
+            ..Self::default()
+        }
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        let name_matches = self
+            .name_contains
+            .as_ref()
+            .is_none_or(|name| todo.name.to_lowercase().contains(&name.to_lowercase()));
+        let completed_matches = self
+            .completed
+            .is_none_or(|completed| todo.completed == completed);
+        let deleted_matches = self
+            .deleted
+            .is_none_or(|deleted| todo.deleted_at.is_some() == deleted);
+        let list_matches = self
+            .list
+            .as_ref()
+            .is_none_or(|list| todo.list_name() == list);
+        name_matches && completed_matches && deleted_matches && list_matches
+    }
+}
+
+/// In-memory todo list backing the JSON document backends, which load and save the whole array.
+///
+/// Files are written as `{"version": N, "default_list": "...", "todos": [...]}`, where
+/// `default_list` is left out until one is set. A bare array is the unversioned layout from
+/// before schema versioning and is read as version 1.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct JsonDocument {
+    todos: Vec<Todo>,
+    pub default_list: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonFile<'a> {
+    version: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_list: Option<&'a str>,
+    todos: &'a [Todo],
+}
+
+impl JsonDocument {
+    pub fn new(todos: Vec<Todo>, default_list: Option<String>) -> Self {
+        Self {
+            todos,
+            default_list,
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Self, TodoErrors> {
+        if input.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let (version, todos, default_list) = match from_str::<Value>(input)? {
+            todos @ Value::Array(_) => (1, todos, None),
+            Value::Object(mut file) => {
+                let version = file.get("version").and_then(Value::as_u64).ok_or_else(|| {
+                    TodoErrors::parse_error("Data file is missing a schema version")
+                })?;
+                let default_list = file
+                    .get("default_list")
+                    .and_then(Value::as_str)
+                    .map(String::from);
+                (
+                    version,
+                    file.remove("todos").unwrap_or(Value::Array(Vec::new())),
+                    default_list,
+                )
+            }
+            _ => {
+                return Err(TodoErrors::parse_error(
+                    "Data file is neither a todo list nor a versioned document",
+                ));
+            }
+        };
+        if version > SCHEMA_VERSION {
+            return Err(TodoErrors::parse_error(format!(
+                "Data file schema version {} is newer than supported version {}",
+                version, SCHEMA_VERSION
+            )));
+        }
+        let todos = from_value(Self::migrate(version, todos))?;
+        Ok(Self {
+            todos,
+            default_list,
+        })
+    }
+
+    /// Upgrades the raw `todos` array one schema version at a time.
+    fn migrate(from_version: u64, todos: Value) -> Value {
+        (from_version..SCHEMA_VERSION).fold(todos, |todos, version| match version {
+            // 1 -> 2 adds due, priority, tags and timestamps, all of which default when missing.
+            1 => todos,
+            // 2 -> 3 adds deleted_at for the trash, absent on every existing todo.
+            2 => todos,
+            // 3 -> 4 adds recurrence, which no existing todo has.
+            3 => todos,
+            // 4 -> 5 adds parent_id for subtasks; every existing todo is top level.
+            4 => todos,
+            // 5 -> 6 adds blocked_by, empty on every existing todo.
+            5 => todos,
+            // 6 -> 7 adds list and the document's default_list; existing todos are in the inbox.
+            6 => todos,
+            _ => unreachable!("no migration from schema version {}", version),
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, TodoErrors> {
+        to_string_pretty(&JsonFile {
+            version: SCHEMA_VERSION,
+            default_list: self.default_list.as_deref(),
+            todos: &self.todos,
+        })
+        .map_err(TodoErrors::from)
+    }
+
+    pub fn get(&self, todo_id: &str) -> Option<Todo> {
+        self.todos.iter().find(|todo| todo.id == todo_id).cloned()
+    }
+
+    pub fn insert(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        if self.get(&todo.id).is_some() {
+            return Err(TodoErrors::Conflict(format!(
+                "Todo by id:{} already exists",
+                todo.id
+            )));
+        }
+        self.todos.push(todo.clone());
+        Ok(())
+    }
+
+    pub fn update(&mut self, todo: &Todo) -> Result<(), TodoErrors> {
+        let existing = self
+            .todos
+            .iter_mut()
+            .find(|existing| existing.id == todo.id)
+            .ok_or_else(|| TodoErrors::NotFound(format!("Todo by id:{} not found", todo.id)))?;
+        *existing = todo.clone();
+        Ok(())
+    }
+
+    pub fn remove(&mut self, todo_id: &str) -> Option<Todo> {
+        let pos = self.todos.iter().position(|todo| todo.id == todo_id)?;
+        Some(self.todos.remove(pos))
+    }
+
+    pub fn scan(&self, filter: &TodoFilter) -> Vec<Todo> {
+        self.todos
+            .iter()
+            .filter(|todo| filter.matches(todo))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_todo(id: &str, name: &str, completed: bool) -> Todo {
+        Todo {
+            id: String::from(id),
+            name: String::from(name),
+            description: format!("{} description", name),
+            completed,
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn should_match_every_todo_with_empty_filter() {
+        let todo = get_todo("a", "Buy milk", true);
+        assert!(TodoFilter::all().matches(&todo));
+    }
+
+    #[test]
+    fn should_match_name_case_insensitively_and_completed_state() {
+        let todo = get_todo("a", "Buy Milk", false);
+        let name_filter = TodoFilter {
+            name_contains: Some(String::from("milk")),
+            ..TodoFilter::default()
+        };
+        let done_filter = TodoFilter {
+            completed: Some(true),
+            ..name_filter.clone()
+        };
+        assert!(name_filter.matches(&todo));
+        assert!(!done_filter.matches(&todo));
+    }
+
+    #[test]
+    fn should_parse_empty_input_as_empty_document() {
+        let document = JsonDocument::parse("").unwrap();
+        assert_eq!(Vec::<Todo>::new(), document.scan(&TodoFilter::all()));
+    }
+
+    #[test]
+    fn should_reject_duplicate_id_on_insert() {
+        let mut document = JsonDocument::default();
+        let todo = get_todo("a", "First", false);
+        document.insert(&todo).unwrap();
+        assert!(matches!(
+            document.insert(&todo),
+            Err(TodoErrors::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn should_load_unversioned_array_with_defaults_for_new_fields() {
+        let legacy = r#"[{"id": "a", "name": "Old", "description": "Legacy", "completed": true}]"#;
+        let document = JsonDocument::parse(legacy).unwrap();
+        let todos = document.scan(&TodoFilter::all());
+        assert_eq!(1, todos.len());
+        assert_eq!("a", todos[0].id);
+        assert!(todos[0].completed);
+        assert_eq!(None, todos[0].due);
+        assert!(todos[0].tags.is_empty());
+    }
+
+    #[test]
+    fn should_write_schema_version_and_read_it_back() {
+        let mut document = JsonDocument::default();
+        document.insert(&get_todo("a", "First", false)).unwrap();
+        let output = document.to_json().unwrap();
+        let raw: Value = from_str(&output).unwrap();
+        assert_eq!(Some(SCHEMA_VERSION), raw["version"].as_u64());
+        assert_eq!(
+            document.scan(&TodoFilter::all()),
+            JsonDocument::parse(&output)
+                .unwrap()
+                .scan(&TodoFilter::all())
+        );
+    }
+
+    #[test]
+    fn should_keep_default_list_and_scan_todos_by_list() {
+        let mut document = JsonDocument::default();
+        let mut work = get_todo("a", "Report", false);
+        work.list = Some(String::from("work"));
+        document.insert(&work).unwrap();
+        document.insert(&get_todo("b", "Groceries", false)).unwrap();
+        document.default_list = Some(String::from("work"));
+
+        let reloaded = JsonDocument::parse(&document.to_json().unwrap()).unwrap();
+        assert_eq!(Some(String::from("work")), reloaded.default_list);
+        let in_list = |list: &str| TodoFilter {
+            list: Some(String::from(list)),
+            ..TodoFilter::default()
+        };
+        assert_eq!(vec![work], reloaded.scan(&in_list("work")));
+        assert_eq!("b", reloaded.scan(&in_list("inbox"))[0].id);
+    }
+
+    #[test]
+    fn should_reject_data_file_from_newer_schema() {
+        let newer = format!(r#"{{"version": {}, "todos": []}}"#, SCHEMA_VERSION + 1);
+        assert!(matches!(
+            JsonDocument::parse(&newer),
+            Err(TodoErrors::ParseError { ref message, .. }) if message.contains("newer than supported")
+        ));
+    }
+}
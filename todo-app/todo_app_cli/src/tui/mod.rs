@@ -0,0 +1,478 @@
+mod view;
+
+use crate::cli::EditCommandArgs;
+use crate::todo_repo::{Todo, TodoErrors, TodoRepository, TodoStorage};
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+pub use view::draw;
+
+/// Which todo field an edit replaces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditField {
+    Name,
+    Description,
+}
+
+impl EditField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditField::Name => "Name",
+            EditField::Description => "Description",
+        }
+    }
+}
+
+/// What keys currently do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    /// Moving through the list and acting on the selected todo.
+    Browse,
+    /// Typed characters narrow the list.
+    Filter,
+    /// Typed characters go into the new value of a field of the selected todo.
+    Edit { field: EditField, input: String },
+    /// Waiting for `y` before the selected todo goes to the trash.
+    ConfirmDelete,
+}
+
+/// Everything the terminal interface shows, changed only through [`TuiState::handle_key`] so
+/// it can be driven without a terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuiState {
+    pub list: String,
+    /// Todos of the list outside the trash, in storage order.
+    pub todos: Vec<Todo>,
+    pub filter: String,
+    /// Index into [`TuiState::visible`].
+    pub selected: usize,
+    pub mode: Mode,
+    /// Outcome of the last action, shown in place of the key help.
+    pub status: Option<String>,
+    pub quit: bool,
+}
+
+impl TuiState {
+    pub fn load<S: TodoStorage>(repo: &mut TodoRepository<S>) -> Result<Self, TodoErrors> {
+        Ok(Self {
+            list: repo.current_list()?,
+            todos: repo.get_all_todos()?,
+            filter: String::new(),
+            selected: 0,
+            mode: Mode::Browse,
+            status: None,
+            quit: false,
+        })
+    }
+
+    /// Todos whose name or description contains the filter, ignoring case.
+    pub fn visible(&self) -> Vec<&Todo> {
+        let filter = self.filter.to_lowercase();
+        self.todos
+            .iter()
+            .filter(|todo| {
+                todo.name.to_lowercase().contains(&filter)
+                    || todo.description.to_lowercase().contains(&filter)
+            })
+            .collect()
+    }
+
+    pub fn selected_todo(&self) -> Option<&Todo> {
+        self.visible().get(self.selected).copied()
+    }
+
+    /// Applies one key press. Failed repository calls are shown in the status line rather
+    /// than ending the session.
+    pub fn handle_key<S: TodoStorage>(&mut self, repo: &mut TodoRepository<S>, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match self.mode.clone() {
+            Mode::Browse => self.browse_key(repo, key.code),
+            Mode::Filter => self.filter_key(key.code),
+            Mode::Edit { field, input } => self.edit_key(repo, key.code, field, input),
+            Mode::ConfirmDelete => self.confirm_delete_key(repo, key.code),
+        }
+    }
+
+    fn browse_key<S: TodoStorage>(&mut self, repo: &mut TodoRepository<S>, code: KeyCode) {
+        self.status = None;
+        let last = self.visible().len().saturating_sub(1);
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => self.selected = last,
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char(' ') | KeyCode::Char('x') => self.toggle_selected(repo),
+            KeyCode::Char('e') => self.start_edit(EditField::Name),
+            KeyCode::Char('E') => self.start_edit(EditField::Description),
+            KeyCode::Char('d') | KeyCode::Delete if self.selected_todo().is_some() => {
+                self.mode = Mode::ConfirmDelete
+            }
+            KeyCode::Char('r') => self.reload(repo),
+            _ => {}
+        }
+    }
+
+    fn filter_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) => self.filter.push(c),
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            KeyCode::Enter => self.mode = Mode::Browse,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.mode = Mode::Browse;
+            }
+            _ => return,
+        }
+        self.selected = 0;
+    }
+
+    fn edit_key<S: TodoStorage>(
+        &mut self,
+        repo: &mut TodoRepository<S>,
+        code: KeyCode,
+        field: EditField,
+        mut input: String,
+    ) {
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => {
+                self.mode = Mode::Browse;
+                return;
+            }
+            KeyCode::Enter => {
+                self.save_edit(repo, field, input);
+                return;
+            }
+            _ => {}
+        }
+        self.mode = Mode::Edit { field, input };
+    }
+
+    fn confirm_delete_key<S: TodoStorage>(&mut self, repo: &mut TodoRepository<S>, code: KeyCode) {
+        self.mode = Mode::Browse;
+        let Some(todo) = self.selected_todo().cloned() else {
+            return;
+        };
+        if code != KeyCode::Char('y') {
+            self.status = Some(String::from("Delete cancelled"));
+            return;
+        }
+        match repo.delete_todo(todo.id) {
+            Ok(deleted) => {
+                self.status = Some(format!("Moved '{}' to the trash", deleted.name));
+                self.reload(repo);
+            }
+            Err(e) => self.status = Some(format!("Unable to delete todo: {}", e)),
+        }
+    }
+
+    fn toggle_selected<S: TodoStorage>(&mut self, repo: &mut TodoRepository<S>) {
+        let Some(todo) = self.selected_todo().cloned() else {
+            return;
+        };
+        let result = match todo.completed {
+            true => repo
+                .update_todo(&EditCommandArgs {
+                    id: todo.id,
+                    reopen: true,
+                    ..EditCommandArgs::default()
+                })
+                .map(|todo| format!("Reopened '{}'", todo.name))
+                .map_err(|e| format!("Unable to reopen todo: {}", e)),
+            false => repo
+                .mark_todo_complete(todo.id)
+                .map(|todo| format!("Completed '{}'", todo.name))
+                .map_err(|e| format!("Unable to complete todo: {}", e)),
+        };
+        let changed = result.is_ok();
+        self.status = Some(result.unwrap_or_else(|e| e));
+        if changed {
+            self.reload(repo);
+        }
+    }
+
+    fn start_edit(&mut self, field: EditField) {
+        if let Some(todo) = self.selected_todo() {
+            let input = match field {
+                EditField::Name => todo.name.clone(),
+                EditField::Description => todo.description.clone(),
+            };
+            self.mode = Mode::Edit { field, input };
+        }
+    }
+
+    // A refused edit keeps the input open so it can be corrected.
+    fn save_edit<S: TodoStorage>(
+        &mut self,
+        repo: &mut TodoRepository<S>,
+        field: EditField,
+        input: String,
+    ) {
+        let Some(todo) = self.selected_todo().cloned() else {
+            self.mode = Mode::Browse;
+            return;
+        };
+        let current = match field {
+            EditField::Name => &todo.name,
+            EditField::Description => &todo.description,
+        };
+        if *current == input {
+            self.mode = Mode::Browse;
+            return;
+        }
+        let mut edit_args = EditCommandArgs {
+            id: todo.id,
+            ..EditCommandArgs::default()
+        };
+        match field {
+            EditField::Name => edit_args.name = Some(input.clone()),
+            EditField::Description => edit_args.description = Some(input.clone()),
+        }
+        match repo.update_todo(&edit_args) {
+            Ok(todo) => {
+                self.mode = Mode::Browse;
+                self.status = Some(format!("Updated '{}'", todo.name));
+                self.reload(repo);
+            }
+            Err(e) => {
+                self.mode = Mode::Edit { field, input };
+                self.status = Some(format!("Unable to update todo: {}", e));
+            }
+        }
+    }
+
+    // Reads the todos again, keeping the selected todo selected while it is still visible.
+    fn reload<S: TodoStorage>(&mut self, repo: &mut TodoRepository<S>) {
+        let selected_id = self.selected_todo().map(|todo| todo.id.clone());
+        match repo.get_all_todos() {
+            Ok(todos) => self.todos = todos,
+            Err(e) => {
+                self.status = Some(format!("Error retrieving todos: {}", e));
+                return;
+            }
+        }
+        let visible = self.visible();
+        self.selected = selected_id
+            .and_then(|id| visible.iter().position(|todo| todo.id == id))
+            .unwrap_or(self.selected)
+            .min(visible.len().saturating_sub(1));
+    }
+}
+
+/// Runs the interface on the terminal until the user quits.
+pub fn run<S: TodoStorage>(repo: &mut TodoRepository<S>) -> Result<(), TodoErrors> {
+    let mut state = TuiState::load(repo)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut state, repo);
+    ratatui::restore();
+    result
+}
+
+fn event_loop<S: TodoStorage>(
+    terminal: &mut DefaultTerminal,
+    state: &mut TuiState,
+    repo: &mut TodoRepository<S>,
+) -> Result<(), TodoErrors> {
+    while !state.quit {
+        terminal.draw(|frame| draw(frame, state))?;
+        if let Event::Key(key) = event::read()? {
+            state.handle_key(repo, key);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::AddCommandArgs;
+    use crate::todo_repo::SqliteDataAccess;
+    use std::slice;
+
+    fn get_repository(names: &[&str]) -> TodoRepository<SqliteDataAccess> {
+        let mut repo = TodoRepository::new(SqliteDataAccess::open_in_memory().unwrap());
+        for name in names {
+            repo.add_todo(&AddCommandArgs {
+                name: String::from(*name),
+                description: format!("{} description", name),
+                ..AddCommandArgs::default()
+            })
+            .unwrap();
+        }
+        repo
+    }
+
+    fn press<S: TodoStorage>(state: &mut TuiState, repo: &mut TodoRepository<S>, keys: &str) {
+        for c in keys.chars() {
+            state.handle_key(repo, KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    fn press_code<S: TodoStorage>(
+        state: &mut TuiState,
+        repo: &mut TodoRepository<S>,
+        code: KeyCode,
+    ) {
+        state.handle_key(repo, KeyEvent::from(code));
+    }
+
+    fn selected_name(state: &TuiState) -> &str {
+        &state.selected_todo().unwrap().name
+    }
+
+    #[test]
+    fn should_move_selection_within_bounds() {
+        let mut repo = get_repository(&["Milk", "Bread", "Eggs"]);
+        let mut state = TuiState::load(&mut repo).unwrap();
+        assert_eq!("Milk", selected_name(&state));
+        press(&mut state, &mut repo, "k");
+        assert_eq!("Milk", selected_name(&state));
+        press(&mut state, &mut repo, "jjj");
+        assert_eq!("Eggs", selected_name(&state));
+        press_code(&mut state, &mut repo, KeyCode::Up);
+        assert_eq!("Bread", selected_name(&state));
+        press(&mut state, &mut repo, "g");
+        assert_eq!("Milk", selected_name(&state));
+        press(&mut state, &mut repo, "G");
+        assert_eq!("Eggs", selected_name(&state));
+        press(&mut state, &mut repo, "q");
+        assert!(state.quit);
+    }
+
+    #[test]
+    fn should_toggle_completion_through_repository() {
+        let mut repo = get_repository(&["Milk", "Bread"]);
+        let mut state = TuiState::load(&mut repo).unwrap();
+        press(&mut state, &mut repo, "j ");
+        assert_eq!(Some(String::from("Completed 'Bread'")), state.status);
+        assert!(state.selected_todo().unwrap().completed);
+        assert!(repo.get_todo_by_name(String::from("Bread")).unwrap()[0].completed);
+
+        press(&mut state, &mut repo, "x");
+        assert_eq!(Some(String::from("Reopened 'Bread'")), state.status);
+        assert!(!repo.get_todo_by_name(String::from("Bread")).unwrap()[0].completed);
+    }
+
+    #[test]
+    fn should_narrow_list_while_typing_filter() {
+        let mut repo = get_repository(&["Milk", "Bread", "Oat milk"]);
+        let mut state = TuiState::load(&mut repo).unwrap();
+        press(&mut state, &mut repo, "j/MILK");
+        assert_eq!(Mode::Filter, state.mode);
+        let names = |state: &TuiState| {
+            state
+                .visible()
+                .iter()
+                .map(|todo| todo.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["Milk", "Oat milk"], names(&state));
+        assert_eq!("Milk", selected_name(&state));
+
+        press_code(&mut state, &mut repo, KeyCode::Enter);
+        press(&mut state, &mut repo, "j");
+        assert_eq!("Oat milk", selected_name(&state));
+
+        press(&mut state, &mut repo, "/");
+        press_code(&mut state, &mut repo, KeyCode::Esc);
+        assert_eq!(Mode::Browse, state.mode);
+        assert_eq!(3, state.visible().len());
+    }
+
+    #[test]
+    fn should_edit_name_and_description_inline() {
+        let mut repo = get_repository(&["Milk"]);
+        let mut state = TuiState::load(&mut repo).unwrap();
+        press(&mut state, &mut repo, "e");
+        for _ in 0.."Milk".len() {
+            press_code(&mut state, &mut repo, KeyCode::Backspace);
+        }
+        press(&mut state, &mut repo, "Oat milk");
+        press_code(&mut state, &mut repo, KeyCode::Enter);
+        assert_eq!(Mode::Browse, state.mode);
+        assert_eq!("Oat milk", selected_name(&state));
+
+        press(&mut state, &mut repo, "E");
+        press(&mut state, &mut repo, ", 2l");
+        press_code(&mut state, &mut repo, KeyCode::Enter);
+        let saved = repo.get_todo_by_name(String::from("Oat milk")).unwrap();
+        assert_eq!("Milk description, 2l", saved[0].description);
+
+        press(&mut state, &mut repo, "E!");
+        press_code(&mut state, &mut repo, KeyCode::Esc);
+        assert_eq!("Milk description, 2l", state.todos[0].description);
+    }
+
+    #[test]
+    fn should_keep_editing_when_update_is_refused() {
+        let mut repo = get_repository(&["Milk"]);
+        let mut state = TuiState::load(&mut repo).unwrap();
+        press(&mut state, &mut repo, "e");
+        for _ in 0.."Milk".len() {
+            press_code(&mut state, &mut repo, KeyCode::Backspace);
+        }
+        press_code(&mut state, &mut repo, KeyCode::Enter);
+        assert_eq!(
+            Mode::Edit {
+                field: EditField::Name,
+                input: String::new()
+            },
+            state.mode
+        );
+        assert_eq!(
+            Some(String::from(
+                "Unable to update todo: Todo name cannot be empty"
+            )),
+            state.status
+        );
+        assert_eq!("Milk", repo.get_all_todos().unwrap()[0].name);
+    }
+
+    #[test]
+    fn should_delete_only_after_confirmation() {
+        let mut repo = get_repository(&["Milk", "Bread"]);
+        let mut state = TuiState::load(&mut repo).unwrap();
+        press(&mut state, &mut repo, "dn");
+        assert_eq!(Some(String::from("Delete cancelled")), state.status);
+        assert_eq!(2, repo.get_all_todos().unwrap().len());
+
+        press(&mut state, &mut repo, "jdy");
+        assert_eq!(
+            Some(String::from("Moved 'Bread' to the trash")),
+            state.status
+        );
+        assert_eq!(1, state.todos.len());
+        assert_eq!("Milk", selected_name(&state));
+        assert_eq!("Bread", repo.list_trash().unwrap()[0].name);
+    }
+
+    #[test]
+    fn should_show_refused_complete_in_status() {
+        let mut repo = get_repository(&["Milk", "Bread"]);
+        let todos = repo.get_all_todos().unwrap();
+        repo.link_todos(&todos[0].id, slice::from_ref(&todos[1].id))
+            .unwrap();
+        let mut state = TuiState::load(&mut repo).unwrap();
+        press(&mut state, &mut repo, " ");
+        assert!(
+            state
+                .status
+                .as_ref()
+                .is_some_and(|status| status.starts_with("Unable to complete todo: "))
+        );
+        assert!(!repo.get_all_todos().unwrap()[0].completed);
+        assert!(!state.quit);
+    }
+}
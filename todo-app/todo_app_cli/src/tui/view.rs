@@ -0,0 +1,155 @@
+use crate::tui::{Mode, TuiState};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use unicode_width::UnicodeWidthStr;
+
+const BROWSE_HELP: &str =
+    "j/k move  space done  e name  E description  / filter  d delete  r reload  q quit";
+
+/// Draws the list with the selected todo highlighted, and a bottom line that shows the
+/// filter, the edit input, a delete prompt or the key help.
+pub fn draw(frame: &mut Frame, state: &TuiState) {
+    let [list_area, footer_area] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+    let visible = state.visible();
+    let done = visible.iter().filter(|todo| todo.completed).count();
+    let mut title = format!(" {} ({}/{} done) ", state.list, done, visible.len());
+    if !state.filter.is_empty() {
+        title.push_str(&format!("filter: {} ", state.filter));
+    }
+    let items = visible.iter().map(|todo| {
+        let (mark, color) = match todo.completed {
+            true => ("✓", Color::Green),
+            false => ("✗", Color::Red),
+        };
+        ListItem::new(Line::from(vec![
+            Span::styled(format!("{} ", mark), Style::default().fg(color)),
+            Span::raw(todo.name.clone()),
+            Span::styled(
+                format!("  {}", todo.description),
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+        ]))
+    });
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    let mut list_state =
+        ListState::default().with_selected(state.selected_todo().map(|_| state.selected));
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+
+    // Filter and edit input are typed into the bottom line, which shows the cursor.
+    let input = match &state.mode {
+        Mode::Filter => Some(format!("/{}", state.filter)),
+        Mode::Edit { field, input } => Some(format!("{}: {}", field.label(), input)),
+        _ => None,
+    };
+    let footer = match (&state.mode, &input) {
+        (Mode::ConfirmDelete, _) => state
+            .selected_todo()
+            .map(|todo| format!("Delete '{}'? (y/n)", todo.name))
+            .unwrap_or_default(),
+        (Mode::Browse, _) => state
+            .status
+            .clone()
+            .unwrap_or_else(|| String::from(BROWSE_HELP)),
+        // A refused edit keeps its input open and says why next to it.
+        (_, Some(input)) => match &state.status {
+            Some(status) => format!("{}  [{}]", input, status),
+            None => input.clone(),
+        },
+        (_, None) => String::new(),
+    };
+    frame.render_widget(Paragraph::new(footer), footer_area);
+    if let Some(input) = input {
+        let cursor_x = footer_area.x.saturating_add(input.width() as u16);
+        frame.set_cursor_position((
+            cursor_x.min(footer_area.right().saturating_sub(1)),
+            footer_area.y,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_repo::Todo;
+    use crate::tui::EditField;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    fn render(state: &TuiState) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(60, 6)).unwrap();
+        terminal.draw(|frame| draw(frame, state)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(usize::from(buffer.area.width))
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .map(|row| String::from(row.trim_end()))
+            .collect()
+    }
+
+    fn get_state() -> TuiState {
+        let todo = |name: &str, completed| Todo {
+            id: String::from(name),
+            name: String::from(name),
+            description: format!("{} description", name),
+            completed,
+            ..Todo::default()
+        };
+        TuiState {
+            list: String::from("inbox"),
+            todos: vec![todo("Milk", true), todo("Bread", false)],
+            filter: String::new(),
+            selected: 1,
+            mode: Mode::Browse,
+            status: None,
+            quit: false,
+        }
+    }
+
+    #[test]
+    fn should_render_todos_with_selection_and_help() {
+        let lines = render(&get_state());
+        assert_eq!(
+            "┌ inbox (1/2 done) ────────────────────────────────────────┐",
+            lines[0]
+        );
+        assert_eq!(
+            "│  ✓ Milk  Milk description                                │",
+            lines[1]
+        );
+        assert_eq!(
+            "│> ✗ Bread  Bread description                              │",
+            lines[2]
+        );
+        assert!(lines[5].starts_with("j/k move  space done"));
+    }
+
+    #[test]
+    fn should_render_prompt_of_current_mode() {
+        let mut state = get_state();
+        state.filter = String::from("br");
+        state.mode = Mode::Filter;
+        let lines = render(&state);
+        assert!(lines[0].contains("filter: br"));
+        assert_eq!("/br", lines[5]);
+
+        state.mode = Mode::Edit {
+            field: EditField::Name,
+            input: String::from("Rye"),
+        };
+        assert_eq!("Name: Rye", render(&state)[5]);
+
+        state.filter.clear();
+        state.selected = 0;
+        state.mode = Mode::ConfirmDelete;
+        assert_eq!("Delete 'Milk'? (y/n)", render(&state)[5]);
+    }
+}
@@ -1,10 +1,12 @@
 use assert_cmd::Command;
 use nanoid::nanoid;
+use predicates::prelude::*;
 use predicates::str::contains;
+use serde::Deserialize;
 use serde_json::{from_str, to_string_pretty};
 use std::fs::{read, write};
 use tempfile::NamedTempFile;
-use todo::todo_repo::Todo;
+use todo::todo_repo::{Priority, Todo};
 
 const ID_LENGTH: usize = 7;
 
@@ -15,22 +17,39 @@ fn get_todo_list() -> Vec<Todo> {
             name: String::from("First Todo"),
             description: String::from("First Todo description"),
             completed: false,
+            ..Todo::default()
         },
         Todo {
             id: nanoid!(ID_LENGTH),
             name: String::from("Second Todo"),
             description: String::from("Second Todo description"),
             completed: false,
+            ..Todo::default()
         },
         Todo {
             id: nanoid!(ID_LENGTH),
             name: String::from("Third Todo"),
             description: String::from("Third Todo description"),
             completed: false,
+            ..Todo::default()
         },
     ]
 }
 
+#[derive(Deserialize)]
+struct TodoFile {
+    version: u64,
+    todos: Vec<Todo>,
+}
+
+fn read_todo_file(path: &str) -> Vec<Todo> {
+    let file_contents: Vec<u8> = read(path).unwrap();
+    let text_content = String::from_utf8_lossy(&file_contents);
+    let todo_file: TodoFile = from_str(&text_content).unwrap();
+    assert!(todo_file.version >= 2);
+    todo_file.todos
+}
+
 fn setup(todo_list: &Vec<Todo>) -> NamedTempFile {
     let tempfile = NamedTempFile::new().expect("failed to create temp file");
     let data = to_string_pretty(todo_list).unwrap();
@@ -100,7 +119,7 @@ fn get_id_should_exit_with_non_zero_exit_code_when_todo_not_present() {
 
     cmd.assert()
         .failure()
-        .code(1)
+        .code(3)
         .stderr(contains("Error retrieving todo by id"));
 }
 
@@ -140,7 +159,7 @@ fn get_name_should_exit_with_non_zero_exit_code_when_todo_not_present() {
 
     cmd.assert()
         .failure()
-        .code(1)
+        .code(3)
         .stderr(contains("Error retrieving todo by name"));
 }
 
@@ -166,9 +185,7 @@ fn add_todo_should_return_new_todo_with_zero_exit_code() {
         .code(0)
         .stdout(contains(new_todo_name));
 
-    let file_contents: Vec<u8> = read(path).unwrap();
-    let text_content = String::from_utf8_lossy(&file_contents);
-    let updated_todo_list: Vec<Todo> = from_str(&text_content).unwrap();
+    let updated_todo_list = read_todo_file(path);
     let result = updated_todo_list
         .iter()
         .any(|todo| todo.name == new_todo_name);
@@ -194,9 +211,7 @@ fn complete_todo_should_mark_todo_complete_with_zero_exit_code() {
         .code(0)
         .stdout(contains(todos[0].name.as_str()));
 
-    let file_contents: Vec<u8> = read(path).unwrap();
-    let text_content = String::from_utf8_lossy(&file_contents);
-    let updated_todo_list: Vec<Todo> = from_str(&text_content).unwrap();
+    let updated_todo_list = read_todo_file(path);
     let result = updated_todo_list
         .iter()
         .any(|todo| todo.id == todos[0].id && todo.completed);
@@ -219,7 +234,7 @@ fn complete_todo_should_exit_with_non_zero_exit_code_when_todo_not_present() {
 
     cmd.assert()
         .failure()
-        .code(1)
+        .code(3)
         .stderr(contains("Unable to mark todo completed"));
 }
 
@@ -241,12 +256,10 @@ fn delete_todo_should_delete_todo_with_zero_exit_code() {
         .code(0)
         .stdout(contains(todos[0].name.as_str()));
 
-    let file_contents: Vec<u8> = read(path).unwrap();
-    let text_content = String::from_utf8_lossy(&file_contents);
-    let updated_todo_list: Vec<Todo> = from_str(&text_content).unwrap();
-    let result = updated_todo_list.iter().any(|todo| todo.id == todos[0].id);
+    let updated_todo_list = read_todo_file(path);
+    let trashed = updated_todo_list.iter().find(|todo| todo.id == todos[0].id);
 
-    assert!(!result);
+    assert!(trashed.is_some_and(|todo| todo.deleted_at.is_some()));
 }
 
 #[test]
@@ -264,6 +277,1625 @@ fn delete_todo_should_exit_with_non_zero_exit_code_when_todo_not_present() {
 
     cmd.assert()
         .failure()
-        .code(1)
+        .code(3)
         .stderr(contains("Unable to delete todo"));
 }
+
+#[test]
+fn sqlite_backend_should_persist_todos_between_invocations() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let db_path = tempdir.path().join("todo.db");
+    let path = db_path.to_str().unwrap();
+    let new_todo_name = "Sqlite";
+
+    let mut add_cmd = Command::cargo_bin("todo").unwrap();
+    add_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("add")
+        .arg("--name")
+        .arg(new_todo_name)
+        .arg("--description")
+        .arg("Stored in sqlite");
+    add_cmd.assert().success().code(0);
+
+    let mut get_cmd = Command::cargo_bin("todo").unwrap();
+    get_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--backend")
+        .arg("sqlite")
+        .arg("get")
+        .arg("all");
+    get_cmd
+        .assert()
+        .success()
+        .code(0)
+        .stdout(contains(new_todo_name));
+
+    let file_header = read(path).unwrap();
+    assert!(file_header.starts_with(b"SQLite format 3"));
+}
+
+fn spawn_concurrent_adds(path: &str, count: usize) {
+    let children: Vec<_> = (0..count)
+        .map(|index| {
+            std::process::Command::new(assert_cmd::cargo::cargo_bin("todo"))
+                .arg("--file")
+                .arg(path)
+                .arg("add")
+                .arg("--name")
+                .arg(format!("Concurrent {}", index))
+                .arg("--description")
+                .arg("Added in parallel")
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn concurrent_adds_should_not_lose_todos() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let concurrent_adds = 16;
+
+    spawn_concurrent_adds(path, concurrent_adds);
+
+    let updated_todo_list = read_todo_file(path);
+    assert_eq!(todos.len() + concurrent_adds, updated_todo_list.len());
+    for index in 0..concurrent_adds {
+        let name = format!("Concurrent {}", index);
+        assert!(updated_todo_list.iter().any(|todo| todo.name == name));
+    }
+}
+
+#[test]
+fn concurrent_adds_should_not_lose_todos_with_sqlite_backend() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let db_path = tempdir.path().join("todo.db");
+    let path = db_path.to_str().unwrap();
+    let concurrent_adds = 16;
+
+    spawn_concurrent_adds(path, concurrent_adds);
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("json")
+        .arg("get")
+        .arg("all");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let all_todos: Vec<Todo> = from_str(&String::from_utf8(output).unwrap()).unwrap();
+    assert_eq!(concurrent_adds, all_todos.len());
+}
+
+#[test]
+fn add_todo_should_store_due_priority_and_tags() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("add")
+        .arg("--name")
+        .arg("Taxes")
+        .arg("--description")
+        .arg("File return")
+        .arg("--due")
+        .arg("2026-10-31")
+        .arg("--priority")
+        .arg("urgent")
+        .arg("--tag")
+        .arg("finance,home");
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout(contains("due=2026-10-31 00:00"))
+        .stdout(contains("priority=urgent"))
+        .stdout(contains("tags=finance,home"));
+
+    let updated_todo_list = read_todo_file(path);
+    let added = updated_todo_list
+        .iter()
+        .find(|todo| todo.name == "Taxes")
+        .unwrap();
+    assert_eq!(Some(Priority::Urgent), added.priority);
+    assert_eq!(vec!["finance", "home"], added.tags);
+    assert!(added.due.is_some());
+    assert!(added.created_at.is_some());
+    // The legacy todos survive the upgrade to the versioned file layout.
+    assert_eq!(todos.len() + 1, updated_todo_list.len());
+}
+
+#[test]
+fn add_todo_should_reject_invalid_due_date() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("add")
+        .arg("--name")
+        .arg("Taxes")
+        .arg("--description")
+        .arg("File return")
+        .arg("--due")
+        .arg("next week");
+
+    cmd.assert().failure().stderr(contains("invalid date"));
+}
+
+#[test]
+fn edit_todo_should_patch_supplied_fields_with_zero_exit_code() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("json")
+        .arg("edit")
+        .arg("--id")
+        .arg(todos[1].id.as_str())
+        .arg("--description")
+        .arg("Edited description");
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout(contains("\"description\": \"Edited description\""))
+        .stdout(contains(todos[1].name.as_str()));
+
+    let updated_todo_list = read_todo_file(path);
+    let edited = updated_todo_list
+        .iter()
+        .find(|todo| todo.id == todos[1].id)
+        .unwrap();
+    assert_eq!("Edited description", edited.description);
+    assert_eq!(todos[1].name, edited.name);
+}
+
+#[test]
+fn edit_todo_should_exit_with_non_zero_exit_code_when_todo_not_present() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("edit")
+        .arg("--id")
+        .arg("Some-Random-Id")
+        .arg("--name")
+        .arg("Renamed");
+
+    cmd.assert()
+        .failure()
+        .code(3)
+        .stderr(contains("Unable to edit todo"));
+}
+
+#[test]
+fn get_list_should_filter_sort_and_limit_todos() {
+    let mut todos = get_todo_list();
+    todos[0].tags = vec![String::from("work")];
+    todos[0].priority = Some(Priority::Low);
+    todos[1].tags = vec![String::from("work")];
+    todos[1].priority = Some(Priority::Urgent);
+    todos[2].completed = true;
+    todos[2].tags = vec![String::from("work")];
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("json")
+        .arg("get")
+        .arg("list")
+        .arg("--status")
+        .arg("pending")
+        .arg("--tag")
+        .arg("work")
+        .arg("--sort")
+        .arg("priority")
+        .arg("--limit")
+        .arg("1");
+
+    let output = cmd.assert().success().code(0).get_output().stdout.clone();
+    let found_todos: Vec<Todo> = from_str(&String::from_utf8(output).unwrap()).unwrap();
+    assert_eq!(1, found_todos.len());
+    assert_eq!(todos[1].id, found_todos[0].id);
+}
+
+#[test]
+fn commands_should_accept_ids_starting_with_hyphen() {
+    let mut todos = get_todo_list();
+    todos[0].id = String::from("-xY1_ab");
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut get_cmd = Command::cargo_bin("todo").unwrap();
+    get_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("get")
+        .arg("id")
+        .arg("-xY1_ab");
+    get_cmd
+        .assert()
+        .success()
+        .stdout(contains(todos[0].name.as_str()));
+
+    let mut complete_cmd = Command::cargo_bin("todo").unwrap();
+    complete_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("complete")
+        .arg("--id")
+        .arg("-xY1_ab");
+    complete_cmd.assert().success();
+}
+
+#[test]
+fn undo_should_restore_deleted_todo_and_redo_should_trash_it_again() {
+    let todos = get_todo_list();
+    let tempdir = tempfile::tempdir().unwrap();
+    let data_path = tempdir.path().join("todo.json");
+    let path = data_path.to_str().unwrap();
+    write(path, to_string_pretty(&todos).unwrap()).unwrap();
+
+    let mut delete_cmd = Command::cargo_bin("todo").unwrap();
+    delete_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("delete")
+        .arg("--id")
+        .arg(todos[0].id.as_str());
+    delete_cmd.assert().success();
+
+    let mut undo_cmd = Command::cargo_bin("todo").unwrap();
+    undo_cmd.arg("--file").arg(path).arg("undo");
+    undo_cmd
+        .assert()
+        .success()
+        .code(0)
+        .stdout(contains("delete"))
+        .stdout(contains(todos[0].id.as_str()));
+    assert_eq!(None, read_todo_file(path)[0].deleted_at);
+
+    let mut redo_cmd = Command::cargo_bin("todo").unwrap();
+    redo_cmd.arg("--file").arg(path).arg("redo");
+    redo_cmd.assert().success().code(0);
+    assert!(read_todo_file(path)[0].deleted_at.is_some());
+
+    let mut history_cmd = Command::cargo_bin("todo").unwrap();
+    history_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("json")
+        .arg("history");
+    let output = history_cmd.assert().success().get_output().stdout.clone();
+    let history: serde_json::Value = from_str(&String::from_utf8(output).unwrap()).unwrap();
+    assert_eq!(1, history.as_array().unwrap().len());
+    assert_eq!("delete", history[0]["operation"]);
+    assert_eq!(false, history[0]["undone"]);
+    assert!(history[0]["timestamp"].is_string());
+}
+
+#[test]
+fn undo_should_exit_with_non_zero_exit_code_when_nothing_to_undo() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let data_path = tempdir.path().join("todo.db");
+    let path = data_path.to_str().unwrap();
+
+    let mut undo_cmd = Command::cargo_bin("todo").unwrap();
+    undo_cmd.arg("--file").arg(path).arg("undo");
+    undo_cmd
+        .assert()
+        .failure()
+        .code(4)
+        .stderr(contains("Nothing to undo"));
+}
+
+#[test]
+fn commands_without_todos_should_refuse_csv_and_template_output() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut history_cmd = Command::cargo_bin("todo").unwrap();
+    history_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("csv")
+        .arg("history");
+    history_cmd
+        .assert()
+        .failure()
+        .code(4)
+        .stderr(contains("history does not support --output csv"));
+
+    let mut lists_cmd = Command::cargo_bin("todo").unwrap();
+    lists_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--template")
+        .arg("{name}")
+        .arg("lists");
+    lists_cmd
+        .assert()
+        .failure()
+        .code(4)
+        .stderr(contains("lists does not support --template"));
+}
+
+#[test]
+fn trash_restore_and_purge_should_manage_deleted_todos() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    for todo in &todos[..2] {
+        let mut delete_cmd = Command::cargo_bin("todo").unwrap();
+        delete_cmd
+            .arg("--file")
+            .arg(path)
+            .arg("delete")
+            .arg("--id")
+            .arg(todo.id.as_str());
+        delete_cmd.assert().success();
+    }
+
+    let mut get_cmd = Command::cargo_bin("todo").unwrap();
+    get_cmd.arg("--file").arg(path).arg("get").arg("all");
+    get_cmd
+        .assert()
+        .success()
+        .stdout(contains(todos[0].id.as_str()).not())
+        .stdout(contains(todos[2].id.as_str()));
+
+    let mut trash_cmd = Command::cargo_bin("todo").unwrap();
+    trash_cmd.arg("--file").arg(path).arg("trash").arg("list");
+    trash_cmd
+        .assert()
+        .success()
+        .stdout(contains(todos[0].id.as_str()))
+        .stdout(contains(todos[1].id.as_str()))
+        .stdout(contains("deleted="));
+
+    let mut restore_cmd = Command::cargo_bin("todo").unwrap();
+    restore_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("restore")
+        .arg("--id")
+        .arg(todos[0].id.as_str());
+    restore_cmd.assert().success();
+
+    let mut recent_purge_cmd = Command::cargo_bin("todo").unwrap();
+    recent_purge_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("purge")
+        .arg("--older-than")
+        .arg("30d");
+    recent_purge_cmd
+        .assert()
+        .success()
+        .stdout(contains("No Todos found"));
+
+    let mut purge_cmd = Command::cargo_bin("todo").unwrap();
+    purge_cmd.arg("--file").arg(path).arg("purge");
+    purge_cmd
+        .assert()
+        .success()
+        .stdout(contains(todos[1].id.as_str()));
+
+    let remaining_ids: Vec<String> = read_todo_file(path)
+        .into_iter()
+        .map(|todo| todo.id)
+        .collect();
+    assert_eq!(
+        vec![todos[0].id.clone(), todos[2].id.clone()],
+        remaining_ids
+    );
+}
+
+#[test]
+fn complete_should_accept_multiple_ids_and_report_missing_ones() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("complete")
+        .arg("--id")
+        .arg(todos[0].id.as_str())
+        .arg("--id")
+        .arg("Some-Random-Id")
+        .arg("--id")
+        .arg(todos[2].id.as_str());
+
+    cmd.assert()
+        .failure()
+        .code(3)
+        .stdout(contains("2 succeeded, 1 not found: Some-Random-Id"))
+        .stderr(contains("Todo by id:Some-Random-Id not found"));
+
+    let completed: Vec<bool> = read_todo_file(path)
+        .iter()
+        .map(|todo| todo.completed)
+        .collect();
+    assert_eq!(vec![true, false, true], completed);
+}
+
+#[test]
+fn delete_should_read_ids_from_stdin() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("json")
+        .arg("delete")
+        .write_stdin(format!("{}\n\n{}\n", todos[1].id, todos[2].id));
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = from_str(&String::from_utf8(output).unwrap()).unwrap();
+    assert_eq!(2, report["succeeded"].as_array().unwrap().len());
+    assert!(report["not_found"].as_array().unwrap().is_empty());
+
+    let trashed: Vec<bool> = read_todo_file(path)
+        .iter()
+        .map(|todo| todo.deleted_at.is_some())
+        .collect();
+    assert_eq!(vec![false, true, true], trashed);
+}
+
+#[test]
+fn delete_should_trash_every_todo_matching_filter() {
+    let mut todos = get_todo_list();
+    todos[0].completed = true;
+    todos[2].completed = true;
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("delete")
+        .arg("--status")
+        .arg("done");
+
+    cmd.assert()
+        .success()
+        .stdout(contains("2 succeeded, 0 not found"));
+
+    let mut get_cmd = Command::cargo_bin("todo").unwrap();
+    get_cmd.arg("--file").arg(path).arg("get").arg("all");
+    get_cmd
+        .assert()
+        .success()
+        .stdout(contains(todos[1].id.as_str()))
+        .stdout(contains(todos[0].id.as_str()).not())
+        .stdout(contains(todos[2].id.as_str()).not());
+}
+
+#[test]
+fn export_and_import_should_round_trip_markdown_checklist() {
+    let mut todos = get_todo_list();
+    todos[1].completed = true;
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut export_cmd = Command::cargo_bin("todo").unwrap();
+    export_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("export")
+        .arg("--format")
+        .arg("markdown");
+    let output = export_cmd.assert().success().get_output().stdout.clone();
+    let markdown = String::from_utf8(output).unwrap();
+    assert!(markdown.starts_with("- [ ] First Todo — First Todo description <!-- todo "));
+    assert!(markdown.contains("- [x] Second Todo"));
+
+    let tempdir = tempfile::tempdir().unwrap();
+    let target_path = tempdir.path().join("todo.json");
+    let target = target_path.to_str().unwrap();
+    let mut dry_run_cmd = Command::cargo_bin("todo").unwrap();
+    dry_run_cmd
+        .arg("--file")
+        .arg(target)
+        .arg("import")
+        .arg("--format")
+        .arg("markdown")
+        .arg("--dry-run")
+        .write_stdin(markdown.clone());
+    dry_run_cmd
+        .assert()
+        .success()
+        .stdout(contains(todos[0].id.as_str()));
+    assert!(!target_path.exists());
+
+    let mut import_cmd = Command::cargo_bin("todo").unwrap();
+    import_cmd
+        .arg("--file")
+        .arg(target)
+        .arg("import")
+        .arg("--format")
+        .arg("markdown")
+        .write_stdin(markdown);
+    import_cmd.assert().success();
+
+    let imported = read_todo_file(target);
+    assert_eq!(
+        todos.iter().map(|todo| &todo.id).collect::<Vec<_>>(),
+        imported.iter().map(|todo| &todo.id).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![false, true, false],
+        imported
+            .iter()
+            .map(|todo| todo.completed)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn import_should_assign_new_ids_on_conflict_and_reject_bad_input() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let csv = format!(
+        "id,name,description,completed,due,priority,tags,created_at,updated_at,completed_at\n{},Clash,From csv,false,,high,\"a,b\",,,\n",
+        todos[0].id
+    );
+
+    let mut import_cmd = Command::cargo_bin("todo").unwrap();
+    import_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("import")
+        .arg("--format")
+        .arg("csv")
+        .write_stdin(csv);
+    import_cmd.assert().success().stdout(contains("Clash"));
+
+    let stored = read_todo_file(path);
+    assert_eq!(4, stored.len());
+    assert_ne!(todos[0].id, stored[3].id);
+    assert_eq!(Some(Priority::High), stored[3].priority);
+    assert_eq!(vec!["a", "b"], stored[3].tags);
+
+    let mut bad_cmd = Command::cargo_bin("todo").unwrap();
+    bad_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("import")
+        .arg("--format")
+        .arg("todotxt")
+        .write_stdin("Fine\nBroken due:never\n");
+    bad_cmd
+        .assert()
+        .failure()
+        .code(6)
+        .stderr(contains("Unable to import todos: line 2:"));
+    assert_eq!(4, read_todo_file(path).len());
+}
+
+#[test]
+fn add_should_read_ndjson_from_stdin_when_no_flags_given() {
+    let tempfile = setup(&Vec::new());
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file").arg(path).arg("add").write_stdin(
+        "{\"name\": \"Milk\", \"description\": \"2 litres\", \"tags\": [\"shop\"]}\n{\"name\": \"Bread\", \"due\": \"2026-10-20\"}\n",
+    );
+    cmd.assert()
+        .success()
+        .stdout(contains("Milk"))
+        .stdout(contains("Bread"));
+
+    let stored = read_todo_file(path);
+    assert_eq!(
+        vec!["Milk", "Bread"],
+        stored.iter().map(|t| &t.name).collect::<Vec<_>>()
+    );
+    assert_eq!(vec!["shop"], stored[0].tags);
+    assert!(stored[1].due.is_some());
+}
+
+#[test]
+fn add_should_reject_missing_name_and_invalid_json() {
+    let tempfile = setup(&Vec::new());
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut flags_cmd = Command::cargo_bin("todo").unwrap();
+    flags_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("add")
+        .arg("--description")
+        .arg("No name");
+    flags_cmd
+        .assert()
+        .failure()
+        .stderr(contains("Todo name cannot be empty"));
+
+    let mut json_cmd = Command::cargo_bin("todo").unwrap();
+    json_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("add")
+        .write_stdin("{\"name\": ");
+    json_cmd
+        .assert()
+        .failure()
+        .stderr(contains("invalid todo JSON"));
+
+    let mut get_cmd = Command::cargo_bin("todo").unwrap();
+    get_cmd.arg("--file").arg(path).arg("get").arg("all");
+    get_cmd
+        .assert()
+        .success()
+        .stdout(contains("No Todos found"));
+}
+
+#[test]
+fn get_id_and_name_should_read_from_stdin() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut id_cmd = Command::cargo_bin("todo").unwrap();
+    id_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("get")
+        .arg("id")
+        .write_stdin(format!("{}\n{}\n", todos[0].id, todos[2].id));
+    id_cmd
+        .assert()
+        .success()
+        .stdout(contains(todos[0].name.as_str()))
+        .stdout(contains(todos[1].name.as_str()).not())
+        .stdout(contains(todos[2].name.as_str()));
+
+    let mut name_cmd = Command::cargo_bin("todo").unwrap();
+    name_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("get")
+        .arg("name")
+        .write_stdin("second\n");
+    name_cmd
+        .assert()
+        .success()
+        .stdout(contains(todos[1].id.as_str()));
+}
+
+#[test]
+fn get_all_as_table_should_print_header_without_colors_when_piped() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("table")
+        .arg("get")
+        .arg("all");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("#  ID "))
+        .stdout(contains("STATUS"))
+        .stdout(contains("DESCRIPTION"))
+        .stdout(contains(format!("1  {}  ✗", todos[0].id)))
+        .stdout(contains("\u{1b}[").not());
+}
+
+#[test]
+fn get_all_should_print_template_and_ndjson_lines() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut template_cmd = Command::cargo_bin("todo").unwrap();
+    template_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--template")
+        .arg(r"{id}\t{name}")
+        .arg("get")
+        .arg("all");
+    let expected_output = todos
+        .iter()
+        .map(|todo| format!("{}\t{}\n", todo.id, todo.name))
+        .collect::<String>();
+    template_cmd.assert().success().stdout(expected_output);
+
+    let mut ndjson_cmd = Command::cargo_bin("todo").unwrap();
+    ndjson_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("ndjson")
+        .arg("get")
+        .arg("all");
+    let output = ndjson_cmd.assert().success().get_output().stdout.clone();
+    let parsed: Vec<Todo> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| from_str(line).unwrap())
+        .collect();
+    assert_eq!(
+        todos.iter().map(|todo| &todo.id).collect::<Vec<_>>(),
+        parsed.iter().map(|todo| &todo.id).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn errors_should_use_category_exit_codes_and_json_envelope() {
+    let tempfile = setup(&get_todo_list());
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut missing_cmd = Command::cargo_bin("todo").unwrap();
+    missing_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("json")
+        .arg("get")
+        .arg("id")
+        .arg("missing");
+    let output = missing_cmd.assert().failure().code(3).get_output().clone();
+    let envelope: serde_json::Value = from_str(&String::from_utf8_lossy(&output.stderr)).unwrap();
+    assert_eq!("not_found", envelope["error"]["kind"]);
+    assert_eq!(3, envelope["error"]["exit_code"]);
+    assert!(
+        envelope["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Todo by id:missing not found")
+    );
+
+    write(
+        tempfile.path(),
+        "{\n  \"version\": 3,\n  \"todos\": [oops]\n}",
+    )
+    .unwrap();
+    let mut corrupt_cmd = Command::cargo_bin("todo").unwrap();
+    corrupt_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--output")
+        .arg("json")
+        .arg("get")
+        .arg("all");
+    let output = corrupt_cmd.assert().failure().code(6).get_output().clone();
+    let envelope: serde_json::Value = from_str(&String::from_utf8_lossy(&output.stderr)).unwrap();
+    assert_eq!("parse_error", envelope["error"]["kind"]);
+    assert_eq!(3, envelope["error"]["line"]);
+
+    let mut text_cmd = Command::cargo_bin("todo").unwrap();
+    text_cmd.arg("--file").arg(path).arg("get").arg("all");
+    text_cmd
+        .assert()
+        .failure()
+        .code(6)
+        .stderr(contains("Error retrieving todos: line 3, column"));
+}
+
+#[test]
+fn complete_should_add_next_occurrence_of_recurring_todo() {
+    let tempfile = setup(&Vec::new());
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut add_cmd = Command::cargo_bin("todo").unwrap();
+    add_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("add")
+        .arg("--name")
+        .arg("Water plants")
+        .arg("--due")
+        .arg("2026-10-01")
+        .arg("--repeat")
+        .arg("every:3");
+    add_cmd
+        .assert()
+        .success()
+        .stdout(contains("repeat=every:3"));
+    let todo_id = read_todo_file(path)[0].id.clone();
+
+    let mut complete_cmd = Command::cargo_bin("todo").unwrap();
+    complete_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("complete")
+        .arg("--id")
+        .arg(&todo_id);
+    complete_cmd
+        .assert()
+        .success()
+        .stdout(contains("1 next occurrence(s) created"));
+
+    let saved_todos = read_todo_file(path);
+    assert_eq!(2, saved_todos.len());
+    let next = saved_todos.iter().find(|todo| todo.id != todo_id).unwrap();
+    assert!(!next.completed);
+    assert!(next.due.unwrap() > chrono::Utc::now());
+
+    let mut upcoming_cmd = Command::cargo_bin("todo").unwrap();
+    upcoming_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--template")
+        .arg("{id}")
+        .arg("get")
+        .arg("upcoming")
+        .arg("-n")
+        .arg("4");
+    upcoming_cmd
+        .assert()
+        .success()
+        .stdout(format!("{}\n", next.id).repeat(4));
+
+    let mut invalid_cmd = Command::cargo_bin("todo").unwrap();
+    invalid_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("add")
+        .arg("--name")
+        .arg("Bad")
+        .arg("--repeat")
+        .arg("hourly");
+    invalid_cmd
+        .assert()
+        .code(2)
+        .stderr(contains("invalid repeat rule"));
+}
+
+#[test]
+fn subtasks_should_render_as_tree_and_cascade_only_when_asked() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let parent_id = todos[0].id.as_str();
+
+    let mut add_cmd = Command::cargo_bin("todo").unwrap();
+    add_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("add")
+        .arg("--name")
+        .arg("Subtask")
+        .arg("--parent")
+        .arg(parent_id);
+    add_cmd
+        .assert()
+        .success()
+        .stdout(contains(format!("parent={}", parent_id)));
+
+    let mut get_cmd = Command::cargo_bin("todo").unwrap();
+    get_cmd.arg("--file").arg(path).arg("get").arg("all");
+    get_cmd
+        .assert()
+        .success()
+        .stdout(contains("subtasks=0/1\n└─ "));
+
+    let mut complete_cmd = Command::cargo_bin("todo").unwrap();
+    complete_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("complete")
+        .arg("--id")
+        .arg(parent_id);
+    complete_cmd
+        .assert()
+        .code(4)
+        .stderr(contains("pass --cascade to include them"));
+
+    let mut cascade_cmd = Command::cargo_bin("todo").unwrap();
+    cascade_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("complete")
+        .arg("--cascade")
+        .arg("--id")
+        .arg(parent_id);
+    cascade_cmd
+        .assert()
+        .success()
+        .stdout(contains("2 succeeded, 0 not found"));
+
+    let mut delete_cmd = Command::cargo_bin("todo").unwrap();
+    delete_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("delete")
+        .arg("--id")
+        .arg(parent_id);
+    delete_cmd.assert().code(4).stderr(contains("--recursive"));
+
+    let mut recursive_cmd = Command::cargo_bin("todo").unwrap();
+    recursive_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("delete")
+        .arg("--recursive")
+        .arg("--id")
+        .arg(parent_id);
+    recursive_cmd.assert().success();
+    let saved_todos = read_todo_file(path);
+    assert_eq!(
+        2,
+        saved_todos
+            .iter()
+            .filter(|todo| todo.deleted_at.is_some())
+            .count()
+    );
+
+    let mut missing_parent_cmd = Command::cargo_bin("todo").unwrap();
+    missing_parent_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("add")
+        .arg("--name")
+        .arg("Orphan")
+        .arg("--parent")
+        .arg(parent_id);
+    missing_parent_cmd
+        .assert()
+        .code(3)
+        .stderr(contains("Parent todo"));
+}
+
+#[test]
+fn blocked_todos_should_wait_on_blockers_unless_forced() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let (blocker_id, blocked_id) = (todos[0].id.as_str(), todos[1].id.as_str());
+
+    let mut link_cmd = Command::cargo_bin("todo").unwrap();
+    link_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("link")
+        .arg("--id")
+        .arg(blocked_id)
+        .arg("--blocked-by")
+        .arg(blocker_id);
+    link_cmd
+        .assert()
+        .success()
+        .stdout(contains(format!("blocked_by={}", blocker_id)));
+
+    let mut cycle_cmd = Command::cargo_bin("todo").unwrap();
+    cycle_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("link")
+        .arg("--id")
+        .arg(blocker_id)
+        .arg("--blocked-by")
+        .arg(blocked_id);
+    cycle_cmd
+        .assert()
+        .code(5)
+        .stderr(contains("dependency cycle"));
+
+    let mut ready_cmd = Command::cargo_bin("todo").unwrap();
+    ready_cmd.arg("--file").arg(path).arg("get").arg("ready");
+    ready_cmd
+        .assert()
+        .success()
+        .stdout(contains(blocker_id).and(contains(blocked_id).not()));
+
+    let mut graph_cmd = Command::cargo_bin("todo").unwrap();
+    graph_cmd.arg("--file").arg(path).arg("graph");
+    graph_cmd.assert().success().stdout(contains(format!(
+        "\"{}\" -> \"{}\";",
+        blocker_id, blocked_id
+    )));
+
+    let mut complete_cmd = Command::cargo_bin("todo").unwrap();
+    complete_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("complete")
+        .arg("--id")
+        .arg(blocked_id);
+    complete_cmd
+        .assert()
+        .code(4)
+        .stderr(contains("pass --force"));
+
+    let mut force_cmd = Command::cargo_bin("todo").unwrap();
+    force_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("complete")
+        .arg("--force")
+        .arg("--id")
+        .arg(blocked_id);
+    force_cmd.assert().success();
+    assert!(read_todo_file(path)[1].completed);
+}
+
+#[test]
+fn lists_should_separate_todos_and_follow_moves_and_default() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let mut add_cmd = Command::cargo_bin("todo").unwrap();
+    add_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--list")
+        .arg("work")
+        .arg("add")
+        .arg("--name")
+        .arg("Quarterly report");
+    add_cmd.assert().success();
+
+    let mut inbox_cmd = Command::cargo_bin("todo").unwrap();
+    inbox_cmd.arg("--file").arg(path).arg("get").arg("all");
+    inbox_cmd
+        .assert()
+        .success()
+        .stdout(contains("First Todo").and(contains("Quarterly").not()));
+
+    let mut move_cmd = Command::cargo_bin("todo").unwrap();
+    move_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("move")
+        .arg("--id")
+        .arg(&todos[0].id)
+        .arg("--to")
+        .arg("work");
+    move_cmd
+        .assert()
+        .success()
+        .stdout(contains("1 succeeded, 0 not found"));
+
+    let mut lists_cmd = Command::cargo_bin("todo").unwrap();
+    lists_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("lists")
+        .arg("--set-default")
+        .arg("work");
+    lists_cmd
+        .assert()
+        .success()
+        .stdout("inbox      pending=2 done=0\nwork       pending=2 done=0 default\n");
+
+    let mut work_cmd = Command::cargo_bin("todo").unwrap();
+    work_cmd.arg("--file").arg(path).arg("get").arg("all");
+    work_cmd.assert().success().stdout(
+        contains("Quarterly")
+            .and(contains("First Todo"))
+            .and(contains("Second Todo").not()),
+    );
+
+    let mut empty_name_cmd = Command::cargo_bin("todo").unwrap();
+    empty_name_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("--list")
+        .arg(" ")
+        .arg("get")
+        .arg("all");
+    empty_name_cmd.assert().code(2);
+}
+
+#[test]
+fn settings_should_come_from_flag_then_env_then_config_file() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let config_home = tempfile::tempdir().unwrap();
+    let todo_cmd = || {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.env("XDG_CONFIG_HOME", config_home.path())
+            .env_remove("TODO_FILE")
+            .env_remove("TODO_OUTPUT");
+        cmd
+    };
+
+    todo_cmd()
+        .args(["config", "set", "output", "JSON"])
+        .assert()
+        .success()
+        .stdout("json\n");
+    todo_cmd()
+        .args(["config", "set", "file", path])
+        .assert()
+        .success();
+    let config_file = config_home.path().join("todo").join("config.toml");
+    assert!(
+        std::fs::read_to_string(&config_file)
+            .unwrap()
+            .contains("output = \"json\"")
+    );
+
+    todo_cmd()
+        .args(["get", "all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("[").and(contains(&todos[0].id)));
+    todo_cmd()
+        .env("TODO_OUTPUT", "ndjson")
+        .args(["get", "all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("{\"id\""));
+    todo_cmd()
+        .env("TODO_OUTPUT", "ndjson")
+        .args(["-o", "text", "get", "all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(todos[0].id.as_str()));
+
+    todo_cmd()
+        .env("TODO_OUTPUT", "text")
+        .args(["config", "show"])
+        .assert()
+        .success()
+        .stdout(
+            contains(format!("file         {} (config)", path))
+                .and(contains("output       text (env)"))
+                .and(contains("date_format  %Y-%m-%d %H:%M (default)")),
+        );
+
+    todo_cmd()
+        .args(["config", "set", "color", "sometimes"])
+        .assert()
+        .code(4)
+        .stderr(contains("expected one of auto, always, never"));
+    todo_cmd()
+        .env("TODO_OUTPUT", "xml")
+        .args(["get", "all"])
+        .assert()
+        .code(4)
+        .stderr(contains("TODO_OUTPUT"));
+
+    write(&config_file, "output = \"json\"\nwidth = 80\n").unwrap();
+    todo_cmd()
+        .args(["get", "all"])
+        .assert()
+        .code(6)
+        .stderr(contains("line 2"));
+}
+
+#[test]
+fn tui_should_refuse_to_start_without_terminal() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let before = read(path).unwrap();
+
+    let mut tui_cmd = Command::cargo_bin("todo").unwrap();
+    tui_cmd.arg("--file").arg(path).arg("tui").write_stdin("q");
+    tui_cmd
+        .assert()
+        .code(4)
+        .stdout("")
+        .stderr(contains("must be a terminal"));
+    assert_eq!(before, read(path).unwrap());
+}
+
+// Sends one request over a fresh connection and returns the status and the JSON body.
+fn http_request(address: &str, method: &str, path: &str, body: &str) -> (u16, serde_json::Value) {
+    use std::io::{Read, Write};
+    let mut stream = std::net::TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        address,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, from_str(body).unwrap())
+}
+
+// Kills the server even when an assertion fails first.
+struct ServerProcess(std::process::Child);
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+// Runs `todo serve` on a free port over the data file at `path` and returns its address.
+fn start_server(path: &str) -> (ServerProcess, String) {
+    use std::io::{BufRead, BufReader};
+    let mut server = ServerProcess(
+        std::process::Command::new(assert_cmd::cargo::cargo_bin("todo"))
+            .args(["--file", path, "serve", "--port", "0"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    let mut first_line = String::new();
+    BufReader::new(server.0.stdout.take().unwrap())
+        .read_line(&mut first_line)
+        .unwrap();
+    let address = first_line.trim().strip_prefix("Listening on http://");
+    let address = String::from(address.unwrap());
+    (server, address)
+}
+
+#[test]
+fn serve_should_expose_todos_over_http() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+
+    let (server, address) = start_server(path);
+
+    let (status, body) = http_request(&address, "GET", "/todos", "");
+    assert_eq!(200, status);
+    assert_eq!(3, body.as_array().unwrap().len());
+
+    let (status, created) = http_request(
+        &address,
+        "POST",
+        "/todos",
+        r#"{"name": "Buy milk", "tags": ["shop"]}"#,
+    );
+    assert_eq!(201, status);
+    let todo_path = format!("/todos/{}", created["id"].as_str().unwrap());
+
+    let (status, body) = http_request(&address, "PATCH", &todo_path, r#"{"priority": "high"}"#);
+    assert_eq!(200, status);
+    assert_eq!("high", body["priority"]);
+    let (status, body) = http_request(&address, "POST", &format!("{}/complete", todo_path), "");
+    assert_eq!(200, status);
+    assert_eq!(true, body["completed"]);
+
+    // Changes made over HTTP reach the data file the CLI reads.
+    let mut get_cmd = Command::cargo_bin("todo").unwrap();
+    get_cmd
+        .arg("--file")
+        .arg(path)
+        .arg("get")
+        .arg("name")
+        .arg("Buy milk");
+    get_cmd.assert().success().stdout(contains("true"));
+
+    let (status, _) = http_request(&address, "DELETE", &todo_path, "");
+    assert_eq!(200, status);
+    let (status, body) = http_request(&address, "GET", &todo_path, "");
+    assert_eq!(404, status);
+    assert_eq!("not_found", body["error"]["kind"]);
+    let (status, body) = http_request(&address, "POST", "/todos", "{\"name\": ");
+    assert_eq!(400, status);
+    assert_eq!("parse_error", body["error"]["kind"]);
+    let (status, _) = http_request(&address, "PATCH", "/todos/nope", r#"{"name": "x"}"#);
+    assert_eq!(404, status);
+
+    drop(server);
+    assert_eq!(4, read_todo_file(path).len());
+}
+
+#[test]
+fn remote_should_run_commands_against_server() {
+    let todos = get_todo_list();
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let (server, address) = start_server(path);
+    let url = format!("http://{}", address);
+    let remote_cmd = || {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.arg("--remote").arg(&url);
+        cmd
+    };
+
+    remote_cmd()
+        .args(["add", "--name", "Buy milk"])
+        .assert()
+        .success();
+    remote_cmd()
+        .args(["get", "all"])
+        .assert()
+        .success()
+        .stdout(contains("First Todo").and(contains("Buy milk")));
+    remote_cmd()
+        .args(["complete", "--id", &todos[0].id])
+        .assert()
+        .success();
+    remote_cmd()
+        .args(["delete", "--id", &todos[1].id])
+        .assert()
+        .success();
+    remote_cmd()
+        .args(["get", "id", &todos[1].id])
+        .assert()
+        .code(3);
+    remote_cmd().arg("undo").assert().code(4);
+
+    // The server journals remote changes, so they can be undone where it runs.
+    let mut undo_cmd = Command::cargo_bin("todo").unwrap();
+    undo_cmd.arg("--file").arg(path).arg("undo");
+    undo_cmd.assert().success();
+
+    drop(server);
+    let saved = read_todo_file(path);
+    assert_eq!(4, saved.len());
+    assert!(saved[0].completed);
+    assert!(saved[1].deleted_at.is_none());
+    remote_cmd()
+        .args(["get", "all"])
+        .assert()
+        .code(7)
+        .stderr(contains("request to"));
+    Command::cargo_bin("todo")
+        .unwrap()
+        .args(["--remote", "localhost:1", "get", "all"])
+        .assert()
+        .code(2);
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+#[test]
+fn sync_should_merge_todos_through_git_remote() {
+    let root = tempfile::TempDir::new().unwrap();
+    git(
+        root.path(),
+        &["init", "--quiet", "--bare", "-b", "main", "origin.git"],
+    );
+    let clone = |name: &str| {
+        git(root.path(), &["clone", "--quiet", "origin.git", name]);
+        let dir = root.path().join(name);
+        git(&dir, &["checkout", "--quiet", "-B", "main"]);
+        git(&dir, &["config", "user.name", name]);
+        git(&dir, &["config", "user.email", "test@example.com"]);
+        dir.join("todo.json").to_str().unwrap().to_string()
+    };
+    let (alice, bob) = (clone("alice"), clone("bob"));
+    let todo_cmd = |path: &str| {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.arg("--file").arg(path);
+        cmd
+    };
+
+    todo_cmd(&alice)
+        .args(["add", "--name", "Milk"])
+        .assert()
+        .success();
+    todo_cmd(&alice)
+        .arg("sync")
+        .assert()
+        .success()
+        .stdout(contains("Committed local changes").and(contains("Pushed to origin/main")));
+    todo_cmd(&bob)
+        .arg("sync")
+        .assert()
+        .success()
+        .stdout(contains("Fast-forwarded to origin/main"));
+    let milk_id = read_todo_file(&bob)[0].id.clone();
+
+    // Both clones change the same todo and add one of their own before syncing again.
+    todo_cmd(&alice)
+        .args(["add", "--name", "Bread"])
+        .assert()
+        .success();
+    todo_cmd(&alice)
+        .args(["edit", "--id", &milk_id, "--name", "Whole milk"])
+        .assert()
+        .success();
+    todo_cmd(&alice).arg("sync").assert().success();
+    todo_cmd(&bob)
+        .args(["add", "--name", "Eggs"])
+        .assert()
+        .success();
+    todo_cmd(&bob)
+        .args(["edit", "--id", &milk_id, "--name", "Oat milk"])
+        .assert()
+        .success();
+    todo_cmd(&bob)
+        .args(["complete", "--id", &milk_id])
+        .assert()
+        .success();
+    todo_cmd(&bob)
+        .args(["--output", "json", "sync"])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#""merged": true"#)
+                .and(contains(r#""field": "name""#))
+                .and(contains(r#""kept": "ours""#)),
+        );
+    todo_cmd(&alice).arg("sync").assert().success();
+
+    let names = |path: &str| {
+        read_todo_file(path)
+            .into_iter()
+            .map(|todo| todo.name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["Oat milk", "Eggs", "Bread"], names(&bob));
+    assert_eq!(names(&bob), names(&alice));
+    assert!(read_todo_file(&alice)[0].completed);
+    todo_cmd(&alice)
+        .arg("sync")
+        .assert()
+        .success()
+        .stdout("Already in sync with origin/main\n");
+
+    let outside = setup(&get_todo_list());
+    todo_cmd(outside.path().to_str().unwrap())
+        .arg("sync")
+        .assert()
+        .code(4)
+        .stderr(contains("not inside a git work tree"));
+}
+
+#[test]
+fn search_should_rank_fuzzy_matches_and_mark_them() {
+    let mut todos = get_todo_list();
+    todos[0].name = String::from("Buy groceries");
+    todos[0].description = String::from("oat milk and bread");
+    todos[1].tags = vec![String::from("groceries")];
+    todos[2].description = String::from("Call about the milk delivery");
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let search_cmd = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.arg("--file").arg(path).arg("search").args(args);
+        cmd
+    };
+
+    search_cmd(&["grocereis"]).assert().success().stdout(
+        contains(format!(
+            "{}  false Buy [groceries] oat milk and bread score=2.10",
+            todos[0].id
+        ))
+        .and(contains("tags=[groceries] score=1.40")),
+    );
+    let output = Command::cargo_bin("todo")
+        .unwrap()
+        .args(["--file", path, "--output", "json", "search", "milk"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let hits: serde_json::Value = from_str(&String::from_utf8(output).unwrap()).unwrap();
+    let ranked = hits
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["todo"]["id"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![todos[0].id.as_str(), todos[2].id.as_str()], ranked);
+    assert_eq!("description", hits[0]["matches"][0]["field"]);
+
+    search_cmd(&["\"milk and\"", "deliv*"])
+        .assert()
+        .success()
+        .stdout("No Todos found\n");
+    search_cmd(&["-n", "1", "todo"])
+        .assert()
+        .success()
+        .stdout(contains("Second").and(contains("Third").not()));
+    search_cmd(&["\"milk"]).assert().code(4);
+}
+
+#[test]
+fn commands_should_accept_id_prefixes_indexes_and_last() {
+    let mut todos = get_todo_list();
+    todos[0].id = String::from("abc1234");
+    todos[1].id = String::from("abd5678");
+    todos[2].id = String::from("xyz9012");
+    let tempfile = setup(&todos);
+    let path = tempfile.path().to_str().unwrap();
+    let todo_cmd = || {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.arg("--file").arg(path);
+        cmd
+    };
+
+    todo_cmd()
+        .args(["--output", "table", "get", "all"])
+        .assert()
+        .success()
+        .stdout(contains("2  abd5678"));
+    todo_cmd()
+        .args(["get", "all"])
+        .assert()
+        .success()
+        .stdout(contains("index=").not());
+    todo_cmd()
+        .args(["get", "id", "abd"])
+        .assert()
+        .success()
+        .stdout(contains("abd5678"));
+    todo_cmd()
+        .args(["get", "id", "ab"])
+        .assert()
+        .code(4)
+        .stderr(contains(
+            "Todo id prefix ab is ambiguous, it matches abc1234 (First Todo), abd5678 (Second Todo)",
+        ));
+    todo_cmd()
+        .args(["complete", "--id", "3"])
+        .assert()
+        .success()
+        .stdout(contains("xyz9012  true"));
+    todo_cmd()
+        .args(["add", "--name", "Newest"])
+        .assert()
+        .success();
+    todo_cmd()
+        .args(["delete", "--id", "@last"])
+        .assert()
+        .success()
+        .stdout(contains("Newest"));
+    todo_cmd()
+        .args(["link", "--id", "abd", "--blocked-by", "1,xyz"])
+        .assert()
+        .success()
+        .stdout(contains("blocked_by=abc1234,xyz9012"));
+    todo_cmd()
+        .args(["unlink", "--id", "2", "--blocked-by", "xy"])
+        .assert()
+        .success();
+
+    let saved = read_todo_file(path);
+    assert!(saved[2].completed);
+    assert!(saved[3].deleted_at.is_some());
+    assert_eq!(vec![String::from("abc1234")], saved[1].blocked_by);
+}